@@ -6,7 +6,11 @@ use ratatui::{
     widgets::{Row, Table, Widget},
 };
 
-use crate::util::{Mode, colors::color_config, keys::KEY_CONFIG};
+use crate::util::{
+    Mode,
+    colors::color_config,
+    keys::{Action, KeyScope, keymap},
+};
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct HelpBox {
@@ -56,35 +60,50 @@ fn render_file_tree_help(expanded: bool, area: Rect, buf: &mut Buffer) {
 
     let key_actions = [
         Row::new(vec![
-            format!("{} or \u{2193}", KEY_CONFIG.down),
+            format!(
+                "{} or \u{2193}",
+                keymap(KeyScope::FileTree).keys_for(Action::Down)
+            ),
             "Move down".to_string(),
         ]),
         Row::new(vec![
-            format!("{} or \u{2191}", KEY_CONFIG.up),
+            format!(
+                "{} or \u{2191}",
+                keymap(KeyScope::FileTree).keys_for(Action::Up)
+            ),
             "Move up".to_string(),
         ]),
         Row::new(vec![
-            format!("{} or \u{2190}", KEY_CONFIG.page_up),
+            format!(
+                "{} or \u{2190}",
+                keymap(KeyScope::FileTree).keys_for(Action::PageUp)
+            ),
             "Go to previous page".to_string(),
         ]),
         Row::new(vec![
-            format!("{} or \u{2192}", KEY_CONFIG.page_down),
+            format!(
+                "{} or \u{2192}",
+                keymap(KeyScope::FileTree).keys_for(Action::PageDown)
+            ),
             "Go to next page".to_string(),
         ]),
         Row::new(vec![
-            format!("{}", KEY_CONFIG.top),
+            keymap(KeyScope::FileTree).keys_for(Action::ToTop),
             "Move to first file".to_string(),
         ]),
         Row::new(vec![
-            format!("{}", KEY_CONFIG.bottom),
+            keymap(KeyScope::FileTree).keys_for(Action::ToBottom),
             "Move to last file".to_string(),
         ]),
         Row::new(vec![
-            format!("/ or {}", KEY_CONFIG.search),
+            keymap(KeyScope::FileTree).keys_for(Action::Search),
             "Search".to_string(),
         ]),
         Row::new(vec!["\u{21b5}", "Open file"]),
-        Row::new(vec!["q", "Quit"]),
+        Row::new(vec![
+            keymap(KeyScope::FileTree).keys_for(Action::Quit),
+            "Quit".to_string(),
+        ]),
     ];
 
     let widths = [12, 20];
@@ -105,59 +124,78 @@ fn render_markdown_help(expandend: bool, area: Rect, buf: &mut Buffer) {
 
     let key_actions = [
         Row::new(vec![
-            format!("{} or \u{2193}", KEY_CONFIG.down),
+            format!(
+                "{} or \u{2193}",
+                keymap(KeyScope::View).keys_for(Action::Down)
+            ),
             "Move down".to_string(),
         ]),
         Row::new(vec![
-            format!("{} or \u{2191}", KEY_CONFIG.up),
+            format!(
+                "{} or \u{2191}",
+                keymap(KeyScope::View).keys_for(Action::Up)
+            ),
             "Move up".to_string(),
         ]),
         Row::new(vec![
-            format!("{}", KEY_CONFIG.half_page_down),
+            keymap(KeyScope::View).keys_for(Action::HalfPageDown),
             "Move half page down".to_string(),
         ]),
         Row::new(vec![
-            format!("{}", KEY_CONFIG.half_page_up),
+            keymap(KeyScope::View).keys_for(Action::HalfPageUp),
             "Move half page up".to_string(),
         ]),
         Row::new(vec![
-            format!("{} or \u{2192}", KEY_CONFIG.page_down),
+            format!(
+                "{} or \u{2192}",
+                keymap(KeyScope::View).keys_for(Action::PageDown)
+            ),
             "Move full page down".to_string(),
         ]),
         Row::new(vec![
-            format!("{} or \u{2190}", KEY_CONFIG.page_up),
+            format!(
+                "{} or \u{2190}",
+                keymap(KeyScope::View).keys_for(Action::PageUp)
+            ),
             "Move full page up".to_string(),
         ]),
         Row::new(vec![
-            format!("{}", KEY_CONFIG.bottom),
+            keymap(KeyScope::View).keys_for(Action::ToBottom),
             "Move to bottom".to_string(),
         ]),
         Row::new(vec![
-            format!("{}", KEY_CONFIG.top),
+            keymap(KeyScope::View).keys_for(Action::ToTop),
             "Move to top".to_string(),
         ]),
         Row::new(vec![
-            format!("/ or {}", KEY_CONFIG.search),
+            keymap(KeyScope::View).keys_for(Action::Search),
             "Search".to_string(),
         ]),
         Row::new(vec![
-            format!("{}", KEY_CONFIG.back),
+            keymap(KeyScope::View).keys_for(Action::Back),
             "Go back to previous file".to_string(),
         ]),
         Row::new(vec![
-            format!("{}", KEY_CONFIG.file_tree),
+            keymap(KeyScope::View).keys_for(Action::ToFileTree),
             "To file tree".to_string(),
         ]),
         Row::new(vec![
-            format!("{}", KEY_CONFIG.select_link),
+            keymap(KeyScope::View).keys_for(Action::SelectLink),
             "Enter select mode".to_string(),
         ]),
-        Row::new(vec!["\u{21b5}", "Open link/file"]),
         Row::new(vec![
-            format!("{}", KEY_CONFIG.edit),
+            keymap(KeyScope::View).keys_for(Action::SelectTask),
+            "Select tasks".to_string(),
+        ]),
+        Row::new(vec!["\u{21b5}", "Open link/toggle task"]),
+        Row::new(vec![
+            keymap(KeyScope::View).keys_for(Action::Edit),
             "Edit file".to_string(),
         ]),
-        Row::new(vec!["q", "Quit"]),
+        Row::new(vec![
+            keymap(KeyScope::View).keys_for(Action::Quit),
+            "Quit".to_string(),
+        ]),
     ];
 
     let widths = [12, 25];
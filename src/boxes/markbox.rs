@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, StatefulWidget, Widget},
+};
+
+use crate::util::{
+    colors::{color_config, heading_colors},
+    marks::Mark,
+};
+
+/// Popup listing the marks that can be jumped to from the current file.
+#[derive(Debug, Clone, Default)]
+pub struct MarkBox {
+    marks: Vec<Mark>,
+    list_state: ListState,
+}
+
+impl MarkBox {
+    pub fn set_marks(&mut self, marks: Vec<Mark>) {
+        self.marks = marks;
+        self.list_state
+            .select(if self.marks.is_empty() { None } else { Some(0) });
+    }
+
+    pub fn next(&mut self) {
+        if self.marks.is_empty() {
+            return;
+        }
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| (i + 1).min(self.marks.len() - 1));
+        self.list_state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        if self.marks.is_empty() {
+            return;
+        }
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(1));
+        self.list_state.select(Some(i));
+    }
+
+    pub fn first(&mut self) {
+        if !self.marks.is_empty() {
+            self.list_state.select(Some(0));
+        }
+    }
+
+    pub fn last(&mut self) {
+        if !self.marks.is_empty() {
+            self.list_state.select(Some(self.marks.len() - 1));
+        }
+    }
+
+    #[must_use]
+    pub fn selected(&self) -> Option<&Mark> {
+        self.list_state.selected().and_then(|i| self.marks.get(i))
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+
+    /// Height and width of the popup, borders included.
+    #[must_use]
+    pub fn dimensions(&self) -> (u16, u16) {
+        (self.marks.len() as u16 + 2, 60)
+    }
+}
+
+impl Widget for MarkBox {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let items: Vec<ListItem<'_>> = self
+            .marks
+            .iter()
+            .map(|mark| {
+                let file = Path::new(mark.path())
+                    .file_name()
+                    .map_or_else(|| mark.path().into(), |f| f.to_string_lossy());
+                let mut spans = vec![
+                    Span::styled(
+                        format!("{} ", mark.letter()),
+                        Style::default().fg(color_config().link_color),
+                    ),
+                    Span::raw(format!(" {file}:{}", mark.scroll() + 1)),
+                ];
+                if let Some(heading) = mark.heading() {
+                    spans.push(Span::styled(
+                        format!("  #{heading}"),
+                        Style::default().fg(heading_colors().level_2),
+                    ));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Marks")
+                    .title_alignment(Alignment::Center),
+            )
+            .highlight_style(
+                Style::default()
+                    .fg(color_config().link_selected_fg_color)
+                    .bg(color_config().link_selected_bg_color),
+            )
+            .highlight_symbol("\u{02503} ");
+
+        let mut state = self.list_state;
+        StatefulWidget::render(list, area, buf, &mut state);
+    }
+}
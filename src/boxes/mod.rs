@@ -1,4 +1,6 @@
 pub mod errorbox;
 pub mod help_box;
 pub mod linkbox;
+pub mod markbox;
 pub mod searchbox;
+pub mod tocbox;
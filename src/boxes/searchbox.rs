@@ -8,6 +8,9 @@ use ratatui::{
 pub struct SearchBox {
     pub text: String,
     pub cursor: usize,
+    /// Shown on the line under the text, for boxes asking for more than
+    /// a search.
+    title: Option<String>,
     height: u16,
     width: u16,
     x: u16,
@@ -20,6 +23,7 @@ impl SearchBox {
         Self {
             text: String::new(),
             cursor: 0,
+            title: None,
             height: 2,
             width: 20,
             x: 0,
@@ -78,6 +82,12 @@ impl SearchBox {
         self.width = width;
     }
 
+    #[must_use]
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_owned());
+        self
+    }
+
     #[must_use]
     pub fn x(&self) -> u16 {
         self.x
@@ -97,8 +107,12 @@ impl Default for SearchBox {
 
 impl Widget for SearchBox {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut block = Block::default().borders(Borders::BOTTOM);
+        if let Some(title) = self.title {
+            block = block.title_bottom(title);
+        }
         let paragraph = Paragraph::new(self.text)
-            .block(Block::default().borders(Borders::BOTTOM))
+            .block(block)
             .wrap(Wrap { trim: true });
         paragraph.render(area, buf);
     }
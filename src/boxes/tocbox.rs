@@ -0,0 +1,181 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, StatefulWidget, Widget},
+};
+
+use crate::util::colors::{color_config, heading_colors};
+
+#[derive(Debug, Clone)]
+pub struct TocEntry {
+    level: u8,
+    text: String,
+    y_offset: u16,
+}
+
+impl TocEntry {
+    #[must_use]
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[must_use]
+    pub fn y_offset(&self) -> u16 {
+        self.y_offset
+    }
+}
+
+/// Outline of the headings in the current document. `current` is the
+/// heading the viewport is in, while the list state tracks the heading
+/// the user has moved the cursor to.
+#[derive(Debug, Clone, Default)]
+pub struct TocBox {
+    entries: Vec<TocEntry>,
+    current: Option<usize>,
+    list_state: ListState,
+}
+
+impl TocBox {
+    /// Replace the entries with `headings` (as returned by
+    /// `ComponentRoot::headings`) and recompute which heading contains
+    /// `scroll`. The cursor is kept where it was, clamped to the new length.
+    pub fn set_entries(&mut self, headings: Vec<(u8, String, u16)>, scroll: u16) {
+        self.entries = headings
+            .into_iter()
+            .map(|(level, text, y_offset)| TocEntry {
+                level,
+                text,
+                y_offset,
+            })
+            .collect();
+        self.set_scroll(scroll);
+        let selected = self
+            .list_state
+            .selected()
+            .map(|i| i.min(self.entries.len().saturating_sub(1)));
+        self.list_state
+            .select(selected.filter(|_| !self.entries.is_empty()));
+    }
+
+    /// Mark the last heading at or above `scroll` as the current one.
+    pub fn set_scroll(&mut self, scroll: u16) {
+        self.current = self.entries.iter().rposition(|e| e.y_offset <= scroll).or(
+            if self.entries.is_empty() {
+                None
+            } else {
+                Some(0)
+            },
+        );
+    }
+
+    /// Move the cursor to the current heading.
+    pub fn select_current(&mut self) {
+        self.list_state.select(self.current);
+    }
+
+    pub fn next(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| (i + 1).min(self.entries.len() - 1));
+        self.list_state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(1));
+        self.list_state.select(Some(i));
+    }
+
+    pub fn first(&mut self) {
+        if !self.entries.is_empty() {
+            self.list_state.select(Some(0));
+        }
+    }
+
+    pub fn last(&mut self) {
+        if !self.entries.is_empty() {
+            self.list_state.select(Some(self.entries.len() - 1));
+        }
+    }
+
+    #[must_use]
+    pub fn selected(&self) -> Option<&TocEntry> {
+        self.list_state.selected().and_then(|i| self.entries.get(i))
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Width of the pane given the full terminal width.
+    #[must_use]
+    pub fn width(terminal_width: u16) -> u16 {
+        (terminal_width / 3).clamp(20, 40).min(terminal_width)
+    }
+}
+
+fn heading_color(level: u8) -> Color {
+    match level {
+        2 => heading_colors().level_2,
+        3 => heading_colors().level_3,
+        4 => heading_colors().level_4,
+        5 => heading_colors().level_5,
+        6 => heading_colors().level_6,
+        _ => color_config().link_color,
+    }
+}
+
+impl Widget for TocBox {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let items: Vec<ListItem<'_>> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let indent = "  ".repeat(entry.level.saturating_sub(1) as usize);
+                let mut style = Style::default().fg(heading_color(entry.level));
+                if Some(i) == self.current {
+                    style = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+                }
+                ListItem::new(Line::from(vec![
+                    Span::raw(indent),
+                    Span::styled(entry.text.clone(), style),
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Contents")
+                    .title_alignment(Alignment::Center),
+            )
+            .highlight_style(
+                Style::default()
+                    .fg(color_config().link_selected_fg_color)
+                    .bg(color_config().link_selected_bg_color),
+            )
+            .highlight_symbol("\u{02503} ");
+
+        let mut state = self.list_state;
+        StatefulWidget::render(list, area, buf, &mut state);
+    }
+}
@@ -1,102 +1,298 @@
-use std::{cmp, fs::read_to_string};
+use std::{
+    cmp,
+    fs::{self, read_to_string},
+    path::Path,
+};
 
-use crossterm::event::KeyCode;
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use notify::{PollWatcher, Watcher};
+use ratatui::layout::Rect;
 
 use crate::{
-    nodes::{root::ComponentRoot, word::WordType},
-    pages::file_explorer::FileTree,
+    nodes::{
+        root::{ClickTarget, ComponentRoot},
+        table::TableView,
+        textcomponent::TextNode,
+        word::WordType,
+    },
+    pages::{
+        file_explorer::{FileTree, file_tree_area},
+        markdown_renderer::markdown_area,
+        printer::render_to_text,
+    },
     parser::parse_markdown,
     util::{
         App, Boxes, Jump, LinkType, Mode,
+        buffers::{BufferList, reparse},
+        export::{
+            TableFormat, component_text, copy_to_clipboard, link_text, permalink, save_to_file,
+        },
         general::GENERAL_CONFIG,
-        keys::{Action, key_to_action},
+        keys::{Action, KeyResult, KeyScope, key_to_action},
+        marks::Mark,
+        toggle_checkbox,
     },
 };
 
+/// Lines scrolled by one step of the mouse wheel.
+const MOUSE_SCROLL_LINES: u16 = 3;
+/// Columns a focused wide table or code block moves for each key press.
+const HORIZONTAL_SCROLL_COLUMNS: i32 = 8;
+
 pub enum KeyBoardAction {
     Continue,
     Edit,
     Exit,
 }
 
+/// A key press together with what it resolved to in the keymap.
+#[derive(Debug, Clone, Copy)]
+struct Input {
+    code: KeyCode,
+    action: Action,
+    count: Option<usize>,
+}
+
+impl Input {
+    /// How many steps a movement takes, the count typed before it.
+    fn steps(self) -> usize {
+        self.count.unwrap_or(1)
+    }
+
+    /// Lines to scroll for a movement of `step` lines per step.
+    fn lines(self, step: u16) -> u16 {
+        u16::try_from(self.steps())
+            .unwrap_or(u16::MAX)
+            .saturating_mul(step)
+    }
+}
+
 pub fn handle_keyboard_input(
-    key: KeyCode,
+    key: KeyEvent,
     app: &mut App,
     markdown: &mut ComponentRoot,
+    buffers: &mut BufferList,
     file_tree: &mut FileTree,
     height: u16,
     watcher: &mut PollWatcher,
 ) -> KeyBoardAction {
-    if key == KeyCode::Char('q') && app.boxes != Boxes::Search {
+    // The key after `m` or `'` names the mark, whatever it is bound to
+    let scope = match (app.boxes, app.mode) {
+        (Boxes::Search | Boxes::Export, _) => KeyScope::Search,
+        (_, Mode::FileTree) => KeyScope::FileTree,
+        (_, Mode::View) if app.table_selected => KeyScope::Table,
+        (_, Mode::View) => KeyScope::View,
+    };
+
+    // Sequences and counts only apply outside of popups
+    let (action, count) = if app.boxes == Boxes::None {
+        match app.key_input.feed(scope, key) {
+            KeyResult::Pending => return KeyBoardAction::Continue,
+            KeyResult::Action(action, count) => (action, count),
+            KeyResult::Mark(Action::SetMark, letter) => {
+                set_mark(letter, app, markdown);
+                return KeyBoardAction::Continue;
+            }
+            KeyResult::Mark(_, letter) => {
+                jump_to_mark(letter, app, markdown, buffers, height, watcher);
+                return KeyBoardAction::Continue;
+            }
+        }
+    } else {
+        (key_to_action(scope, key), None)
+    };
+
+    if action == Action::Quit && !matches!(app.boxes, Boxes::Search | Boxes::Export) {
         return KeyBoardAction::Exit;
     }
+
+    // Movements take the count at once, see `Input::steps`
+    let repeat = match count {
+        Some(count) if action.is_repeatable() => count,
+        _ => 1,
+    };
+
+    let mut input = Input {
+        code: key.code,
+        action,
+        count,
+    };
+    let mut result = KeyBoardAction::Continue;
+    for _ in 0..repeat {
+        result = match app.mode {
+            Mode::FileTree => {
+                keyboard_mode_file_tree(input, app, markdown, buffers, file_tree, height, watcher)
+            }
+            Mode::View => keyboard_mode_view(input, app, markdown, buffers, height, watcher),
+        };
+        if !matches!(result, KeyBoardAction::Continue) {
+            break;
+        }
+        input.count = None;
+    }
+    result
+}
+
+/// Handle the mouse wheel and left clicks on a terminal of `size`. Clicks
+/// go through the same code as selecting and pressing enter.
+pub fn handle_mouse_input(
+    mouse: MouseEvent,
+    app: &mut App,
+    markdown: &mut ComponentRoot,
+    buffers: &mut BufferList,
+    file_tree: &mut FileTree,
+    size: Rect,
+    watcher: &mut PollWatcher,
+) -> KeyBoardAction {
+    if app.boxes != Boxes::None {
+        return KeyBoardAction::Continue;
+    }
+    app.key_input.clear();
+
+    let height = size.height;
+    let area = match app.mode {
+        Mode::FileTree => file_tree_area(size, app.width()),
+        Mode::View => markdown_area(size, app.width()),
+    };
+
+    let clicked = mouse.kind == MouseEventKind::Down(MouseButton::Left)
+        && (area.x..area.x + area.width).contains(&mouse.column)
+        && (area.y..area.y + area.height).contains(&mouse.row);
+    let enter = Input {
+        code: KeyCode::Enter,
+        action: Action::Enter,
+        count: None,
+    };
+
     match app.mode {
-        Mode::FileTree => keyboard_mode_file_tree(key, app, markdown, file_tree, height, watcher),
-        Mode::View => keyboard_mode_view(key, app, markdown, height, watcher),
+        Mode::FileTree => match mouse.kind {
+            MouseEventKind::ScrollDown => file_tree.next(height),
+            MouseEventKind::ScrollUp => file_tree.previous(height),
+            _ if clicked && file_tree.select_at(mouse.row - area.y, height) => {
+                return keyboard_mode_file_tree(
+                    enter, app, markdown, buffers, file_tree, height, watcher,
+                );
+            }
+            _ => {}
+        },
+        Mode::View => match mouse.kind {
+            MouseEventKind::ScrollDown => {
+                app.vertical_scroll = cmp::min(
+                    app.vertical_scroll + MOUSE_SCROLL_LINES,
+                    markdown.height().saturating_sub(height / 2),
+                );
+            }
+            MouseEventKind::ScrollUp => {
+                app.vertical_scroll = app.vertical_scroll.saturating_sub(MOUSE_SCROLL_LINES);
+            }
+            _ if clicked => {
+                let y = app.vertical_scroll + mouse.row - area.y;
+                match markdown.click_target(y, mouse.column - area.x, area.width) {
+                    Some(ClickTarget::Link(index)) => {
+                        app.table_selected = false;
+                        markdown.deselect_table(app.width() - 2);
+                        app.details_selected = false;
+                        markdown.deselect_details();
+                        app.task_selected = false;
+                        markdown.deselect_tasks();
+                        if markdown.select(index).is_err() {
+                            return KeyBoardAction::Continue;
+                        }
+                        app.selected = true;
+                        app.select_index = index;
+                    }
+                    Some(ClickTarget::Details(index)) => {
+                        app.table_selected = false;
+                        markdown.deselect_table(app.width() - 2);
+                        app.selected = false;
+                        markdown.deselect();
+                        app.task_selected = false;
+                        markdown.deselect_tasks();
+                        if markdown.select_details(index).is_err() {
+                            return KeyBoardAction::Continue;
+                        }
+                        app.details_selected = true;
+                        app.details_select_index = index;
+                    }
+                    None => return KeyBoardAction::Continue,
+                }
+                return keyboard_mode_view(enter, app, markdown, buffers, height, watcher);
+            }
+            _ => {}
+        },
     }
+    KeyBoardAction::Continue
 }
 
-pub fn keyboard_mode_file_tree(
-    key: KeyCode,
+fn keyboard_mode_file_tree(
+    input: Input,
     app: &mut App,
     markdown: &mut ComponentRoot,
+    buffers: &mut BufferList,
     file_tree: &mut FileTree,
     height: u16,
     watcher: &mut PollWatcher,
 ) -> KeyBoardAction {
     match app.boxes {
-        Boxes::Error => match key {
+        Boxes::Error => match input.code {
             KeyCode::Enter | KeyCode::Esc => {
                 app.boxes = Boxes::None;
             }
             _ => {}
         },
-        Boxes::Search => match key {
-            KeyCode::Esc => {
+        Boxes::Search => match (input.action, input.code) {
+            (Action::Escape, _) => {
                 app.search_box.clear();
                 file_tree.search(None);
                 app.boxes = Boxes::None;
             }
-            KeyCode::Enter => {
+            (Action::Enter, _) => {
                 let query = app.search_box.consume();
                 file_tree.search(Some(&query));
                 app.boxes = Boxes::None;
             }
 
-            KeyCode::Char(c) => {
-                app.search_box.insert(c);
+            (Action::DeleteBack, _) => {
+                if app.search_box.content().is_none() {
+                    app.boxes = Boxes::None;
+                }
+                app.search_box.delete();
                 file_tree.search(app.search_box.content());
                 let file_height = file_tree.height(height);
                 app.search_box.set_position(10, file_height as u16 + 2);
             }
 
-            KeyCode::Backspace => {
-                if app.search_box.content().is_none() {
-                    app.boxes = Boxes::None;
-                }
-                app.search_box.delete();
+            (_, KeyCode::Char(c)) => {
+                app.search_box.insert(c);
                 file_tree.search(app.search_box.content());
                 let file_height = file_tree.height(height);
                 app.search_box.set_position(10, file_height as u16 + 2);
             }
             _ => {}
         },
-        Boxes::None => match key_to_action(key) {
+        Boxes::None => match input.action {
             Action::Down => {
-                file_tree.next(height);
+                for _ in 0..input.steps() {
+                    file_tree.next(height);
+                }
             }
 
             Action::Up => {
-                file_tree.previous(height);
+                for _ in 0..input.steps() {
+                    file_tree.previous(height);
+                }
             }
 
             Action::PageDown => {
-                file_tree.next_page(height);
+                for _ in 0..input.steps() {
+                    file_tree.next_page(height);
+                }
             }
 
             Action::PageUp => {
-                file_tree.previous_page(height);
+                for _ in 0..input.steps() {
+                    file_tree.previous_page(height);
+                }
             }
 
             Action::ToTop => {
@@ -115,21 +311,7 @@ pub fn keyboard_mode_file_tree(
                     app.boxes = Boxes::Error;
                     return KeyBoardAction::Continue;
                 };
-                let text = if let Ok(file) = read_to_string(file.path_str()) {
-                    app.reset();
-                    file
-                } else {
-                    app.message_box
-                        .set_message(format!("Could not open file {}", file.path_str()));
-                    app.boxes = Boxes::Error;
-                    return KeyBoardAction::Continue;
-                };
-
-                *markdown = parse_markdown(Some(file.path_str()), &text, app.width() - 2);
-                let _ = watcher.watch(file.path(), notify::RecursiveMode::NonRecursive);
-                app.mode = Mode::View;
-                app.help_box.set_mode(Mode::View);
-                app.select_index = 0;
+                open_buffer(file.path_str(), app, markdown, buffers, watcher);
             }
             Action::Search => {
                 let file_height = file_tree.height(height);
@@ -139,30 +321,19 @@ pub fn keyboard_mode_file_tree(
                 app.help_box.close();
             }
 
-            Action::Back => match app.history.pop() {
-                Jump::File(e) => {
-                    let text = if let Ok(file) = read_to_string(&e) {
-                        app.vertical_scroll = 0;
-                        file
-                    } else {
-                        app.message_box
-                            .set_message(format!("Could not open file {e}"));
-                        app.boxes = Boxes::Error;
-                        return KeyBoardAction::Continue;
-                    };
-                    *markdown = parse_markdown(Some(&e), &text, app.width() - 2);
-                    let path = std::path::Path::new(&e);
-                    let _ = watcher.watch(path, notify::RecursiveMode::NonRecursive);
-                    app.reset();
-                    app.mode = Mode::View;
-                    app.help_box.set_mode(Mode::View);
-                }
-                Jump::FileTree => {
-                    markdown.clear();
-                    app.mode = Mode::FileTree;
-                    app.help_box.set_mode(Mode::FileTree);
+            Action::Back => {
+                let jump = app.history.back(current_jump(app, markdown));
+                jump_to(jump, app, markdown, buffers, height, watcher);
+            }
+
+            Action::Forward => {
+                if let Some(jump) = app.history.forward(current_jump(app, markdown)) {
+                    jump_to(jump, app, markdown, buffers, height, watcher);
                 }
-            },
+            }
+
+            Action::NextBuffer => switch_buffer(app, markdown, buffers, true),
+            Action::PreviousBuffer => switch_buffer(app, markdown, buffers, false),
             Action::Help if GENERAL_CONFIG.help_menu => {
                 app.help_box.toggle();
             }
@@ -178,8 +349,8 @@ pub fn keyboard_mode_file_tree(
             }
             _ => {}
         },
-        Boxes::LinkPreview => {
-            if key == KeyCode::Esc {
+        Boxes::Export | Boxes::LinkPreview | Boxes::Toc | Boxes::Marks => {
+            if input.code == KeyCode::Esc {
                 app.boxes = Boxes::None;
             }
         }
@@ -189,25 +360,27 @@ pub fn keyboard_mode_file_tree(
 }
 
 fn keyboard_mode_view(
-    key: KeyCode,
+    input: Input,
     app: &mut App,
     markdown: &mut ComponentRoot,
+    buffers: &mut BufferList,
     height: u16,
     watcher: &mut PollWatcher,
 ) -> KeyBoardAction {
     match app.boxes {
-        Boxes::Error => match key {
+        Boxes::Error => match input.code {
             KeyCode::Enter | KeyCode::Esc => {
                 app.boxes = Boxes::None;
             }
             _ => {}
         },
-        Boxes::Search => match key {
-            KeyCode::Esc => {
+        Boxes::Search if app.table_selected => filter_table(input, app, markdown, height),
+        Boxes::Search => match (input.action, input.code) {
+            (Action::Escape, _) => {
                 app.search_box.clear();
                 app.boxes = Boxes::None;
             }
-            KeyCode::Enter => {
+            (Action::Enter, _) => {
                 let query = app.search_box.content_str();
 
                 markdown.deselect();
@@ -236,27 +409,41 @@ fn keyboard_mode_view(
 
                 app.boxes = Boxes::None;
             }
-            KeyCode::Char(c) => {
-                app.search_box.insert(c);
-            }
-            KeyCode::Backspace => {
+            (Action::DeleteBack, _) => {
                 app.search_box.delete();
             }
+            (_, KeyCode::Char(c)) => {
+                app.search_box.insert(c);
+            }
             _ => {}
         },
-        Boxes::None => match key_to_action(key) {
+        Boxes::Export => export_to_file(input, app, markdown, height),
+        Boxes::None if app.table_selected => {
+            return keyboard_mode_table(input, app, markdown, height);
+        }
+        Boxes::None => match input.action {
             Action::Down => {
+                let steps = input.steps();
                 if app.selected {
-                    app.select_index = cmp::min(app.select_index + 1, markdown.num_links() - 1);
+                    app.select_index = cmp::min(app.select_index + steps, markdown.num_links() - 1);
                     app.vertical_scroll = if let Ok(scroll) = markdown.select(app.select_index) {
                         app.selected = true;
                         scroll.saturating_sub(height / 3)
                     } else {
                         app.vertical_scroll
                     };
+                } else if app.task_selected {
+                    let max_idx = markdown.task_index_and_height().len().saturating_sub(1);
+                    app.task_select_index = cmp::min(app.task_select_index + steps, max_idx);
+                    app.vertical_scroll =
+                        if let Ok(scroll) = markdown.select_task(app.task_select_index) {
+                            scroll.saturating_sub(height / 3)
+                        } else {
+                            app.vertical_scroll
+                        };
                 } else if app.details_selected {
                     let max_idx = markdown.num_details().saturating_sub(1);
-                    app.details_select_index = cmp::min(app.details_select_index + 1, max_idx);
+                    app.details_select_index = cmp::min(app.details_select_index + steps, max_idx);
                     app.vertical_scroll =
                         if let Ok(scroll) = markdown.select_details(app.details_select_index) {
                             app.details_selected = true;
@@ -266,22 +453,31 @@ fn keyboard_mode_view(
                         };
                 } else {
                     app.vertical_scroll = cmp::min(
-                        app.vertical_scroll + 1,
+                        app.vertical_scroll.saturating_add(input.lines(1)),
                         markdown.height().saturating_sub(height / 2),
                     );
                 }
             }
             Action::Up => {
+                let steps = input.steps();
                 if app.selected {
-                    app.select_index = app.select_index.saturating_sub(1);
+                    app.select_index = app.select_index.saturating_sub(steps);
                     app.vertical_scroll = if let Ok(scroll) = markdown.select(app.select_index) {
                         app.selected = true;
                         scroll.saturating_sub(height / 3)
                     } else {
                         app.vertical_scroll
                     };
+                } else if app.task_selected {
+                    app.task_select_index = app.task_select_index.saturating_sub(steps);
+                    app.vertical_scroll =
+                        if let Ok(scroll) = markdown.select_task(app.task_select_index) {
+                            scroll.saturating_sub(height / 3)
+                        } else {
+                            app.vertical_scroll
+                        };
                 } else if app.details_selected {
-                    app.details_select_index = app.details_select_index.saturating_sub(1);
+                    app.details_select_index = app.details_select_index.saturating_sub(steps);
                     app.vertical_scroll =
                         if let Ok(scroll) = markdown.select_details(app.details_select_index) {
                             app.details_selected = true;
@@ -290,9 +486,17 @@ fn keyboard_mode_view(
                             app.vertical_scroll
                         };
                 } else {
-                    app.vertical_scroll = app.vertical_scroll.saturating_sub(1);
+                    app.vertical_scroll = app.vertical_scroll.saturating_sub(input.lines(1));
                 }
             }
+            // With a count, both go to that line of the source, like in vim
+            Action::ToTop | Action::ToBottom if input.count.is_some() => {
+                let offset = markdown
+                    .source_line_offset(input.steps())
+                    .unwrap_or_else(|| markdown.height());
+                app.vertical_scroll =
+                    cmp::min(offset, markdown.height().saturating_sub(height / 2));
+            }
             Action::ToTop => {
                 app.vertical_scroll = 0;
             }
@@ -301,25 +505,24 @@ fn keyboard_mode_view(
             }
 
             Action::HalfPageDown => {
-                app.vertical_scroll += height / 2;
                 app.vertical_scroll = cmp::min(
-                    app.vertical_scroll,
+                    app.vertical_scroll.saturating_add(input.lines(height / 2)),
                     markdown.height().saturating_sub(height / 2),
                 );
             }
             Action::HalfPageUp => {
-                app.vertical_scroll = app.vertical_scroll.saturating_sub(height / 2);
+                app.vertical_scroll = app.vertical_scroll.saturating_sub(input.lines(height / 2));
             }
 
             Action::PageDown => {
                 app.vertical_scroll = cmp::min(
-                    app.vertical_scroll + height,
+                    app.vertical_scroll.saturating_add(input.lines(height)),
                     markdown.height().saturating_sub(height / 2),
                 );
             }
 
             Action::PageUp => {
-                app.vertical_scroll = app.vertical_scroll.saturating_sub(height);
+                app.vertical_scroll = app.vertical_scroll.saturating_sub(input.lines(height));
             }
 
             Action::Hover => {
@@ -335,6 +538,12 @@ fn keyboard_mode_view(
                         return KeyBoardAction::Continue;
                     }
 
+                    if prev_type == WordType::Abbreviation {
+                        app.link_box.set_message(format!("Abbreviation: {link}"));
+                        app.boxes = Boxes::LinkPreview;
+                        return KeyBoardAction::Continue;
+                    }
+
                     let message = match LinkType::from(link) {
                         LinkType::Internal(e) => format!("Internal link: {e}"),
                         LinkType::External(e) => format!("External link: {e}"),
@@ -372,6 +581,8 @@ fn keyboard_mode_view(
                     app.selected = true;
                     app.details_selected = false;
                     markdown.deselect_details();
+                    app.task_selected = false;
+                    markdown.deselect_tasks();
                 } else {
                     // Something weird must have happened at this point
                     markdown.deselect();
@@ -400,6 +611,8 @@ fn keyboard_mode_view(
                 app.selected = true;
                 app.details_selected = false;
                 markdown.deselect_details();
+                app.task_selected = false;
+                markdown.deselect_tasks();
                 app.vertical_scroll = if let Ok(scroll) = markdown.select(app.select_index) {
                     scroll.saturating_sub(height / 3)
                 } else {
@@ -419,10 +632,12 @@ fn keyboard_mode_view(
                     return KeyBoardAction::Continue;
                 }
 
-                // Clear any link selection first — the two modes are
+                // Clear any link or task selection first — the modes are
                 // mutually exclusive.
                 app.selected = false;
                 markdown.deselect();
+                app.task_selected = false;
+                markdown.deselect_tasks();
 
                 let next_idx = if app.details_selected {
                     // Already in details mode — advance to the next.
@@ -446,6 +661,67 @@ fn keyboard_mode_view(
                 };
             }
 
+            // Like `SelectDetails`, for `- [ ]` tasks
+            Action::SelectTask => {
+                let tasks = markdown.task_index_and_height();
+                if tasks.is_empty() {
+                    app.message_box.set_message("No tasks found".to_string());
+                    app.boxes = Boxes::Error;
+                    return KeyBoardAction::Continue;
+                }
+
+                app.selected = false;
+                markdown.deselect();
+                app.details_selected = false;
+                markdown.deselect_details();
+
+                let next_idx = if app.task_selected {
+                    cmp::min(app.task_select_index + 1, tasks.len() - 1)
+                } else {
+                    tasks
+                        .iter()
+                        .find(|(_, y)| *y >= app.vertical_scroll)
+                        .map_or(tasks.len() - 1, |(i, _)| *i)
+                };
+
+                app.task_select_index = next_idx;
+                app.task_selected = true;
+                app.vertical_scroll = if let Ok(scroll) = markdown.select_task(next_idx) {
+                    scroll.saturating_sub(height / 3)
+                } else {
+                    app.vertical_scroll
+                };
+            }
+
+            // Focus the next table or code block too wide for the view, to
+            // scroll it sideways. Other selections stay as they are.
+            Action::SelectWide => {
+                focus_wide(app, markdown, height, app.wide_selected);
+            }
+
+            Action::SelectTable => focus_table(app, markdown, height, false),
+
+            // A selected link is copied rather than opened
+            Action::Yank if app.selected => yank_link(app, markdown),
+            Action::Yank => yank(app, markdown, height),
+            Action::CopyPermalink => copy_permalink(app, markdown),
+            Action::CopyView => copy_view(app, markdown, height),
+            Action::YankToFile => prompt_export(app, height),
+
+            Action::ScrollLeft | Action::ScrollRight => {
+                let columns = if input.action == Action::ScrollLeft {
+                    -HORIZONTAL_SCROLL_COLUMNS
+                } else {
+                    HORIZONTAL_SCROLL_COLUMNS
+                };
+                // Without a focused block, the one in view is scrolled
+                if markdown.scroll_wide(columns, app.width() - 2).is_err()
+                    && focus_wide(app, markdown, height, false)
+                {
+                    let _ = markdown.scroll_wide(columns, app.width() - 2);
+                }
+            }
+
             Action::Search => {
                 app.search_box.clear();
                 app.search_box.set_position(2, height - 3);
@@ -457,9 +733,10 @@ fn keyboard_mode_view(
             Action::ToFileTree => {
                 app.mode = Mode::FileTree;
                 app.help_box.set_mode(Mode::FileTree);
-                if let Some(file) = markdown.file_name() {
-                    app.history.push(Jump::File(file.to_string()));
+                if let Some(jump) = current_jump(app, markdown) {
+                    app.history.push(jump);
                 }
+                buffers.park(markdown, app);
                 app.reset();
             }
 
@@ -497,13 +774,22 @@ fn keyboard_mode_view(
             Action::Edit => return KeyBoardAction::Edit,
 
             Action::Escape => {
+                app.wide_selected = false;
+                markdown.deselect_wide(app.width() - 2);
                 app.selected = false;
                 markdown.deselect();
                 app.details_selected = false;
                 markdown.deselect_details();
+                app.task_selected = false;
+                markdown.deselect_tasks();
             }
 
             Action::Enter => {
+                if app.task_selected {
+                    toggle_task(app, markdown);
+                    return KeyBoardAction::Continue;
+                }
+
                 // A focused `<details>` summary toggles its fold state
                 // and stays in selection mode so the user can chain
                 // multiple toggles without re-pressing `D`.
@@ -520,8 +806,13 @@ fn keyboard_mode_view(
                 let link = markdown.selected();
                 let prev_type = markdown.selected_underlying_type();
 
-                if prev_type == WordType::FootnoteInline {
-                    app.message_box.set_message(markdown.find_footnote(link));
+                if matches!(prev_type, WordType::FootnoteInline | WordType::Abbreviation) {
+                    let message = if prev_type == WordType::Abbreviation {
+                        link.to_owned()
+                    } else {
+                        markdown.find_footnote(link)
+                    };
+                    app.message_box.set_message(message);
                     app.boxes = Boxes::Error;
                     markdown.deselect();
                     app.selected = false;
@@ -530,7 +821,11 @@ fn keyboard_mode_view(
 
                 match LinkType::from(link) {
                     LinkType::Internal(heading) => {
+                        let previous = current_jump(app, markdown);
                         app.vertical_scroll = if let Ok(index) = markdown.heading_offset(heading) {
+                            if let Some(jump) = previous {
+                                app.history.push(jump);
+                            }
                             cmp::min(index, markdown.height().saturating_sub(height / 2))
                         } else {
                             app.message_box
@@ -563,79 +858,594 @@ fn keyboard_mode_view(
                             format!("{url}.md")
                         };
 
-                        let text = if let Ok(file) = read_to_string(&url) {
-                            app.vertical_scroll = 0;
-                            file
-                        } else {
-                            app.message_box
-                                .set_message(format!("Could not open file {url}"));
-                            app.boxes = Boxes::Error;
+                        markdown.deselect();
+                        app.selected = false;
+                        let previous = current_jump(app, markdown);
+
+                        if !open_buffer(&url, app, markdown, buffers, watcher) {
                             return KeyBoardAction::Continue;
-                        };
+                        }
 
-                        if let Some(file_name) = markdown.file_name() {
-                            app.history.push(Jump::File(file_name.to_string()));
+                        if let Some(jump) = previous {
+                            app.history.push(jump);
                         }
 
-                        let path = std::path::Path::new(&url);
-                        let _ = watcher.watch(path, notify::RecursiveMode::NonRecursive);
-                        *markdown = parse_markdown(Some(&url), &text, app.width() - 2);
-                        let index = if let Some(heading) = heading {
+                        if let Some(heading) = heading {
                             if let Ok(index) = markdown.heading_offset(&format!("#{heading}")) {
-                                cmp::min(index, markdown.height().saturating_sub(height / 2))
+                                app.vertical_scroll =
+                                    cmp::min(index, markdown.height().saturating_sub(height / 2));
                             } else {
                                 app.message_box
                                     .set_message(format!("Could not find heading {heading}"));
                                 app.boxes = Boxes::Error;
-                                0
                             }
-                        } else {
-                            0
-                        };
-
-                        app.reset();
-                        app.vertical_scroll = index;
+                        }
+                        return KeyBoardAction::Continue;
                     }
                 }
                 markdown.deselect();
                 app.selected = false;
             }
 
-            Action::Back => match app.history.pop() {
-                Jump::File(e) => {
-                    let text = if let Ok(file) = read_to_string(&e) {
-                        app.vertical_scroll = 0;
-                        file
-                    } else {
-                        app.message_box
-                            .set_message(format!("Could not open file {e}"));
-                        app.boxes = Boxes::Error;
-                        return KeyBoardAction::Continue;
-                    };
-                    *markdown = parse_markdown(Some(&e), &text, app.width() - 2);
-                    let path = std::path::Path::new(&e);
-                    let _ = watcher.watch(path, notify::RecursiveMode::NonRecursive);
-                    app.reset();
-                    app.mode = Mode::View;
-                    app.help_box.set_mode(Mode::View);
-                }
-                Jump::FileTree => {
-                    markdown.clear();
-                    app.mode = Mode::FileTree;
-                    app.help_box.set_mode(Mode::FileTree);
+            Action::Back => {
+                let jump = app.history.back(current_jump(app, markdown));
+                jump_to(jump, app, markdown, buffers, height, watcher);
+            }
+
+            Action::Forward => {
+                if let Some(jump) = app.history.forward(current_jump(app, markdown)) {
+                    jump_to(jump, app, markdown, buffers, height, watcher);
+                }
+            }
+
+            Action::NextBuffer => switch_buffer(app, markdown, buffers, true),
+            Action::PreviousBuffer => switch_buffer(app, markdown, buffers, false),
+
+            Action::FrontMatter => match markdown.toggle_front_matter() {
+                Ok(()) => markdown.set_scroll(app.vertical_scroll),
+                Err(message) => {
+                    app.message_box.set_message(message);
+                    app.boxes = Boxes::Error;
                 }
             },
 
+            Action::Toc => {
+                app.toc_box
+                    .set_entries(markdown.headings(), app.vertical_scroll);
+                if app.toc_box.is_empty() {
+                    app.message_box.set_message("No headings found".to_string());
+                    app.boxes = Boxes::Error;
+                    return KeyBoardAction::Continue;
+                }
+                app.toc_box.select_current();
+                app.boxes = Boxes::Toc;
+                app.help_box.close();
+            }
+
+            Action::Marks => {
+                app.mark_box
+                    .set_marks(app.marks.visible(markdown.file_name()));
+                if app.mark_box.is_empty() {
+                    app.message_box.set_message("No marks set".to_string());
+                    app.boxes = Boxes::Error;
+                    return KeyBoardAction::Continue;
+                }
+                app.boxes = Boxes::Marks;
+                app.help_box.close();
+            }
+
             Action::Help if GENERAL_CONFIG.help_menu => {
                 app.help_box.toggle();
             }
             _ => {}
         },
         Boxes::LinkPreview => {
-            if key == KeyCode::Esc {
+            if input.code == KeyCode::Esc {
+                app.boxes = Boxes::None;
+            }
+        }
+        Boxes::Marks => match input.action {
+            Action::Down => app.mark_box.next(),
+            Action::Up => app.mark_box.previous(),
+            Action::ToTop => app.mark_box.first(),
+            Action::ToBottom => app.mark_box.last(),
+            Action::Enter => {
+                app.boxes = Boxes::None;
+                if let Some(mark) = app.mark_box.selected() {
+                    let letter = mark.letter();
+                    jump_to_mark(letter, app, markdown, buffers, height, watcher);
+                }
+            }
+            Action::Escape | Action::Marks => {
                 app.boxes = Boxes::None;
             }
+            _ => {}
+        },
+        Boxes::Toc => match input.action {
+            Action::Down => app.toc_box.next(),
+            Action::Up => app.toc_box.previous(),
+            Action::ToTop => app.toc_box.first(),
+            Action::ToBottom => app.toc_box.last(),
+            Action::Enter => {
+                if let Some(entry) = app.toc_box.selected() {
+                    if let Some(jump) = current_jump(app, markdown) {
+                        app.history.push(jump);
+                    }
+                    app.vertical_scroll = cmp::min(
+                        entry.y_offset(),
+                        markdown.height().saturating_sub(height / 2),
+                    );
+                    app.toc_box.set_scroll(app.vertical_scroll);
+                }
+            }
+            Action::Escape | Action::Toc => {
+                app.boxes = Boxes::None;
+            }
+            _ => {}
+        },
+    }
+    KeyBoardAction::Continue
+}
+
+/// Where the user is right now, as a jump that can be returned to.
+/// Documents read from stdin cannot be reopened, so they give `None`.
+#[must_use]
+pub fn current_jump(app: &App, markdown: &ComponentRoot) -> Option<Jump> {
+    match app.mode {
+        Mode::FileTree => Some(Jump::FileTree),
+        Mode::View => markdown.file_name().map(|path| Jump::File {
+            path: path.to_owned(),
+            scroll: app.vertical_scroll,
+            heading: markdown.heading_anchor(app.vertical_scroll),
+        }),
+    }
+}
+
+/// Go to `jump`, opening its file if needed. If the heading the jump was
+/// in has moved since, the heading wins over the raw scroll offset.
+pub fn jump_to(
+    jump: Jump,
+    app: &mut App,
+    markdown: &mut ComponentRoot,
+    buffers: &mut BufferList,
+    height: u16,
+    watcher: &mut PollWatcher,
+) {
+    match jump {
+        Jump::File {
+            path,
+            scroll,
+            heading,
+        } => {
+            if !open_buffer(&path, app, markdown, buffers, watcher) {
+                return;
+            }
+            let max_scroll = markdown.height().saturating_sub(height / 2);
+            app.vertical_scroll = cmp::min(scroll, max_scroll);
+            if let Some(heading) = heading
+                && markdown.heading_anchor(app.vertical_scroll).as_ref() != Some(&heading)
+                && let Ok(offset) = markdown.heading_offset(&format!("#{heading}"))
+            {
+                app.vertical_scroll = cmp::min(offset, max_scroll);
+            }
+        }
+        Jump::FileTree => {
+            buffers.park(markdown, app);
+            app.reset();
+            app.mode = Mode::FileTree;
+            app.help_box.set_mode(Mode::FileTree);
+        }
+    }
+}
+
+fn set_mark(letter: char, app: &mut App, markdown: &ComponentRoot) {
+    let Some(file_name) = markdown.file_name() else {
+        app.message_box
+            .set_message("Marks can only be set in a file".to_string());
+        app.boxes = Boxes::Error;
+        return;
+    };
+    app.marks.set(Mark::new(
+        letter,
+        file_name.to_owned(),
+        app.vertical_scroll,
+        markdown.heading_anchor(app.vertical_scroll),
+    ));
+}
+
+fn jump_to_mark(
+    letter: char,
+    app: &mut App,
+    markdown: &mut ComponentRoot,
+    buffers: &mut BufferList,
+    height: u16,
+    watcher: &mut PollWatcher,
+) {
+    let file_name = match app.mode {
+        Mode::View => markdown.file_name(),
+        Mode::FileTree => None,
+    };
+    let Some(mark) = app.marks.get(letter, file_name) else {
+        app.message_box
+            .set_message(format!("Mark {letter} not set"));
+        app.boxes = Boxes::Error;
+        return;
+    };
+    let jump = mark.jump();
+    if let Some(current) = current_jump(app, markdown) {
+        app.history.push(current);
+    }
+    jump_to(jump, app, markdown, buffers, height, watcher);
+}
+
+/// Show `file_name`, switching to its buffer if it is already open and
+/// reading it into a new one otherwise. Returns `false` after showing an
+/// error if the file could not be read.
+fn open_buffer(
+    file_name: &str,
+    app: &mut App,
+    markdown: &mut ComponentRoot,
+    buffers: &mut BufferList,
+    watcher: &mut PollWatcher,
+) -> bool {
+    if !buffers.activate_file(file_name, markdown, app) {
+        let Ok(text) = read_to_string(file_name) else {
+            app.message_box
+                .set_message(format!("Could not open file {file_name}"));
+            app.boxes = Boxes::Error;
+            return false;
+        };
+        let path = std::path::Path::new(file_name);
+        let _ = watcher.watch(path, notify::RecursiveMode::NonRecursive);
+        let root = parse_markdown(Some(file_name), &text, app.width() - 2);
+        buffers.push(markdown, app, root);
+    }
+    app.boxes = Boxes::None;
+    app.help_box.close();
+    app.mode = Mode::View;
+    app.help_box.set_mode(Mode::View);
+    true
+}
+
+/// Focus a wide table or code block: the one after the focused one when
+/// `advance` is set, else the first one at or below the top of the view.
+/// Returns whether there was one.
+fn focus_wide(app: &mut App, markdown: &mut ComponentRoot, height: u16, advance: bool) -> bool {
+    let wide = markdown.wide_index_and_height();
+    if wide.is_empty() {
+        app.message_box
+            .set_message("No tables or code blocks wider than the view".to_string());
+        app.boxes = Boxes::Error;
+        return false;
+    }
+
+    let next_idx = if advance {
+        cmp::min(app.wide_select_index + 1, wide.len() - 1)
+    } else {
+        wide.iter()
+            .find(|(_, y)| *y >= app.vertical_scroll)
+            .map_or(wide.len() - 1, |(i, _)| *i)
+    };
+
+    app.wide_select_index = next_idx;
+    app.wide_selected = true;
+    if let Ok(scroll) = markdown.select_wide(next_idx, app.width() - 2) {
+        // Only scroll when the block is not in view already
+        if scroll < app.vertical_scroll || scroll > app.vertical_scroll + height / 2 {
+            app.vertical_scroll = scroll.saturating_sub(height / 3);
+        }
+    }
+    true
+}
+
+/// Keys while a table is selected: the cursor moves between its cells, and
+/// its rows can be sorted and filtered.
+fn keyboard_mode_table(
+    input: Input,
+    app: &mut App,
+    markdown: &mut ComponentRoot,
+    height: u16,
+) -> KeyBoardAction {
+    let width = app.width() - 2;
+    let moved = match input.action {
+        Action::Up => {
+            let rows = -input.steps().cast_signed();
+            markdown.update_table(width, |view| view.move_cursor(rows, 0))
+        }
+        Action::Down => {
+            let rows = input.steps().cast_signed();
+            markdown.update_table(width, |view| view.move_cursor(rows, 0))
+        }
+        Action::Left => markdown.update_table(width, |view| view.move_cursor(0, -1)),
+        Action::Right => markdown.update_table(width, |view| view.move_cursor(0, 1)),
+        Action::ToTop => markdown.update_table(width, |view| view.move_cursor(isize::MIN, 0)),
+        Action::ToBottom => markdown.update_table(width, |view| view.move_cursor(isize::MAX, 0)),
+        Action::Sort => markdown.update_table(width, TableView::cycle_sort),
+
+        // Typing in the search box filters the rows, see `filter_table`
+        Action::Search => {
+            app.search_box.clear();
+            let filter = markdown.selected_table().map(TableView::filter);
+            for c in filter.unwrap_or_default().chars() {
+                app.search_box.insert(c);
+            }
+            app.search_box.set_position(2, height - 3);
+            app.search_box.set_width(GENERAL_CONFIG.width - 3);
+            app.boxes = Boxes::Search;
+            app.help_box.close();
+            return KeyBoardAction::Continue;
+        }
+
+        // Show all of the cell, which may be wrapped or cut off
+        Action::Enter | Action::Hover => {
+            match markdown.selected_table().and_then(TableView::cell_text) {
+                Some(text) if !text.is_empty() => {
+                    app.link_box.set_message(text);
+                    app.boxes = Boxes::LinkPreview;
+                }
+                Some(_) => {
+                    app.message_box.set_message("The cell is empty".to_string());
+                    app.boxes = Boxes::Error;
+                }
+                None => {
+                    app.message_box
+                        .set_message("No rows match the filter".to_string());
+                    app.boxes = Boxes::Error;
+                }
+            }
+            return KeyBoardAction::Continue;
+        }
+
+        Action::SelectTable => {
+            focus_table(app, markdown, height, true);
+            return KeyBoardAction::Continue;
+        }
+
+        Action::Yank => {
+            yank(app, markdown, height);
+            return KeyBoardAction::Continue;
+        }
+        Action::YankToFile => {
+            prompt_export(app, height);
+            return KeyBoardAction::Continue;
+        }
+
+        Action::Escape => {
+            app.table_selected = false;
+            markdown.deselect_table(width);
+            return KeyBoardAction::Continue;
+        }
+
+        Action::Help if GENERAL_CONFIG.help_menu => {
+            app.help_box.toggle();
+            return KeyBoardAction::Continue;
         }
+        _ => return KeyBoardAction::Continue,
+    };
+
+    if let Ok(line) = moved {
+        follow_table_cursor(app, line, height);
     }
     KeyBoardAction::Continue
 }
+
+/// The search box filters the rows of the selected table as it is typed
+/// in. Escape clears the filter again.
+fn filter_table(input: Input, app: &mut App, markdown: &mut ComponentRoot, height: u16) {
+    match (input.action, input.code) {
+        (Action::Enter, _) => {
+            app.search_box.clear();
+            app.boxes = Boxes::None;
+            return;
+        }
+        (Action::Escape, _) => {
+            app.search_box.clear();
+            app.boxes = Boxes::None;
+        }
+        (Action::DeleteBack, _) => app.search_box.delete(),
+        (_, KeyCode::Char(c)) => app.search_box.insert(c),
+        _ => return,
+    }
+
+    let query = app.search_box.content_str().to_owned();
+    if let Ok(line) = markdown.update_table(app.width() - 2, |view| view.set_filter(&query)) {
+        follow_table_cursor(app, line, height);
+    }
+}
+
+/// Select a table: the one after the selected one when `advance` is set,
+/// else the first one at or below the top of the view.
+fn focus_table(app: &mut App, markdown: &mut ComponentRoot, height: u16, advance: bool) {
+    let tables = markdown.table_index_and_height();
+    if tables.is_empty() {
+        app.message_box.set_message("No tables found".to_string());
+        app.boxes = Boxes::Error;
+        return;
+    }
+
+    app.selected = false;
+    markdown.deselect();
+    app.details_selected = false;
+    markdown.deselect_details();
+    app.task_selected = false;
+    markdown.deselect_tasks();
+
+    let next_idx = if advance {
+        cmp::min(app.table_select_index + 1, tables.len() - 1)
+    } else {
+        tables
+            .iter()
+            .find(|(_, y)| *y >= app.vertical_scroll)
+            .map_or(tables.len() - 1, |(i, _)| *i)
+    };
+
+    app.table_select_index = next_idx;
+    app.table_selected = true;
+    if let Ok(line) = markdown.select_table(next_idx, app.width() - 2) {
+        follow_table_cursor(app, line, height);
+    }
+}
+
+/// Scroll when the cursor row of the selected table at `line` leaves the
+/// upper half of the view.
+fn follow_table_cursor(app: &mut App, line: u16, height: u16) {
+    if line < app.vertical_scroll || line > app.vertical_scroll + height / 2 {
+        app.vertical_scroll = line.saturating_sub(height / 3);
+    }
+}
+
+/// Copy the code block or table to yank, see `export_target`, to the
+/// clipboard. Tables are copied in the configured format.
+fn yank(app: &mut App, markdown: &ComponentRoot, height: u16) {
+    let Some(component) = markdown.export_target(app.vertical_scroll, height) else {
+        app.message_box
+            .set_message("No code block or table in view".to_string());
+        app.boxes = Boxes::Error;
+        return;
+    };
+    let Some(text) = component_text(component, GENERAL_CONFIG.table_format) else {
+        return;
+    };
+    let what = if component.kind() == TextNode::CodeBlock {
+        "the code block"
+    } else {
+        "the table"
+    };
+    copy(app, &text, what);
+}
+
+/// Copy the target of the selected link, or what a footnote or an
+/// abbreviation stands for, the text `Hover` shows.
+fn yank_link(app: &mut App, markdown: &mut ComponentRoot) {
+    let text = link_text(markdown);
+    markdown.deselect();
+    app.selected = false;
+    copy(app, &text, &text);
+}
+
+/// Copy `file.md#anchor` for the heading at or above the top of the view.
+fn copy_permalink(app: &mut App, markdown: &ComponentRoot) {
+    match permalink(markdown, app.vertical_scroll) {
+        Ok(permalink) => copy(app, &permalink, &permalink),
+        Err(message) => {
+            app.message_box.set_message(message);
+            app.boxes = Boxes::Error;
+        }
+    }
+}
+
+/// Copy the text on screen, as shown but without styling.
+fn copy_view(app: &mut App, markdown: &ComponentRoot, height: u16) {
+    let text = render_to_text(markdown, app.width() - 2, app.vertical_scroll, height);
+    copy(app, &text, "the text in view");
+}
+
+/// Copy `text` to the clipboard and say so, calling it `what`.
+fn copy(app: &mut App, text: &str, what: &str) {
+    let message = match copy_to_clipboard(text) {
+        Ok(()) => format!("Copied {what} to the clipboard"),
+        Err(e) => format!("Could not copy {what}: {e}"),
+    };
+    app.message_box.set_message(message);
+    app.boxes = Boxes::Error;
+}
+
+/// Ask for the file to save the code block or table to yank in.
+fn prompt_export(app: &mut App, height: u16) {
+    app.search_box.clear();
+    app.search_box.set_position(2, height - 3);
+    app.search_box.set_width(GENERAL_CONFIG.width - 3);
+    app.boxes = Boxes::Export;
+    app.help_box.close();
+}
+
+/// Typing the path in the box opened by `prompt_export`. Tables are saved
+/// in the format of the file extension, else the configured one.
+fn export_to_file(input: Input, app: &mut App, markdown: &ComponentRoot, height: u16) {
+    match (input.action, input.code) {
+        (Action::Escape, _) => {
+            app.search_box.clear();
+            app.boxes = Boxes::None;
+        }
+        (Action::Enter, _) => {
+            let path = app.search_box.consume();
+            app.boxes = Boxes::None;
+            if path.trim().is_empty() {
+                return;
+            }
+            let format = Path::new(&path)
+                .extension()
+                .and_then(|extension| TableFormat::from_name(&extension.to_string_lossy()))
+                .unwrap_or(GENERAL_CONFIG.table_format);
+            let text = markdown
+                .export_target(app.vertical_scroll, height)
+                .and_then(|component| component_text(component, format));
+            let message = match text.map(|text| save_to_file(path.trim(), &text)) {
+                Some(Ok(path)) => format!("Saved to {}", path.display()),
+                Some(Err(e)) => format!("Could not write {path}: {e}"),
+                None => "No code block or table in view".to_string(),
+            };
+            app.message_box.set_message(message);
+            app.boxes = Boxes::Error;
+        }
+        (Action::DeleteBack, _) => app.search_box.delete(),
+        (_, KeyCode::Char(c)) => app.search_box.insert(c),
+        _ => {}
+    }
+}
+
+/// Flip the checkbox of the selected task in the file on disk and show
+/// the file again as written.
+fn toggle_task(app: &mut App, markdown: &mut ComponentRoot) {
+    let Some(file_name) = markdown.file_name().map(str::to_string) else {
+        app.message_box
+            .set_message("Tasks can only be toggled in a file".to_string());
+        app.boxes = Boxes::Error;
+        return;
+    };
+    let Some(offset) = markdown.selected_task_marker() else {
+        return;
+    };
+
+    let text = read_to_string(&file_name)
+        .ok()
+        .and_then(|text| toggle_checkbox(&text, offset));
+    let Some(text) = text else {
+        app.message_box.set_message(format!(
+            "{file_name} changed on disk, the task was not toggled"
+        ));
+        app.boxes = Boxes::Error;
+        return;
+    };
+    if let Err(e) = fs::write(&file_name, &text) {
+        app.message_box
+            .set_message(format!("Could not write {file_name}: {e}"));
+        app.boxes = Boxes::Error;
+        return;
+    }
+
+    reparse(markdown, &text, app.width() - 2);
+    let _ = markdown.select_task(app.task_select_index);
+}
+
+fn switch_buffer(
+    app: &mut App,
+    markdown: &mut ComponentRoot,
+    buffers: &mut BufferList,
+    forward: bool,
+) {
+    if buffers.is_empty() {
+        app.message_box.set_message("No open buffers".to_string());
+        app.boxes = Boxes::Error;
+        return;
+    }
+    if forward {
+        buffers.next(markdown, app);
+    } else {
+        buffers.previous(markdown, app);
+    }
+    app.boxes = Boxes::None;
+    app.help_box.close();
+    app.mode = Mode::View;
+    app.help_box.set_mode(Mode::View);
+}
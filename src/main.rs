@@ -2,25 +2,41 @@ use std::{
     cmp, env,
     error::Error,
     fs::read_to_string,
-    io::{self, IsTerminal, Read},
+    io::{self, IsTerminal, Read, Write},
     panic,
     sync::mpsc,
     thread,
     time::{Duration, Instant},
 };
 
-use md_tui::event_handler::{KeyBoardAction, handle_keyboard_input};
+use md_tui::boxes::tocbox::TocBox;
+use md_tui::event_handler::{
+    KeyBoardAction, current_jump, handle_keyboard_input, handle_mouse_input, jump_to,
+};
 use md_tui::nodes::root::{Component, ComponentRoot};
-use md_tui::pages::file_explorer::{FileTree, MdFile};
+use md_tui::pages::file_explorer::{FileTree, MdFile, file_tree_area};
+use md_tui::pages::markdown_renderer::markdown_area;
+use md_tui::pages::printer::render_to_ansi;
 use md_tui::parser::parse_markdown;
 use md_tui::search::find_md_files_channel;
-use md_tui::util::{self, App, Boxes, Mode, destruct_terminal, general::GENERAL_CONFIG};
+use md_tui::util::{
+    App, Boxes, Mode,
+    buffers::{BufferList, reparse},
+    canonical_path,
+    cli::{Cli, USAGE},
+    default_editor_command, destruct_terminal, editor_command,
+    general::{GENERAL_CONFIG, set_config_overrides, settings_error},
+    keys,
+    state::{self, Positions},
+};
 
 use crossterm::{
     cursor,
     event::{self, DisableMouseCapture, EnableMouseCapture, Event},
     execute,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    terminal::{
+        self, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+    },
 };
 
 use notify::{Config, PollWatcher, Watcher};
@@ -34,7 +50,49 @@ use ratatui_image::{FilterType, Resize, StatefulImage};
 
 const EMPTY_FILE: &str = "";
 
+/// The file argument that stands for standard input.
+const STDIN_FILE: &str = "-";
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let cli = match Cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    if cli.help {
+        println!("{USAGE}");
+        std::process::exit(0);
+    }
+
+    if cli.version {
+        println!("mdt {}", env!("CARGO_PKG_VERSION"));
+        std::process::exit(0);
+    }
+
+    // Must happen before any configuration is read
+    set_config_overrides(cli.config_overrides());
+    if let Some(error) = settings_error() {
+        eprintln!("Could not load the configuration: {error}");
+        std::process::exit(1);
+    }
+
+    // Print straight to stdout when asked to, or when stdout is not a terminal (pipes, CI logs)
+    if cli.print || !io::stdout().is_terminal() {
+        return print_markdown(&cli.files);
+    }
+
+    let key_errors = keys::keymap_errors();
+    if !key_errors.is_empty() {
+        eprintln!("Invalid key bindings in the config:");
+        for error in key_errors {
+            eprintln!("  {error}");
+        }
+        std::process::exit(1);
+    }
+
     // Set up panic handler. If not set up, the terminal will be left in a broken state if a panic
     // occurs
     panic::set_hook(Box::new(|panic_info| {
@@ -42,23 +100,20 @@ fn main() -> Result<(), Box<dyn Error>> {
         better_panic::Settings::auto().create_panic_handler()(panic_info);
     }));
 
-    let args: Vec<String> = std::env::args().collect();
-    if args
-        .get(1)
-        .is_some_and(|a| a == "--version" || a == "-V" || a == "-v")
-    {
-        println!("mdt {}", env!("CARGO_PKG_VERSION"));
-        std::process::exit(0);
-    }
-
     let mut terminal = ratatui::init();
+    if GENERAL_CONFIG.mouse {
+        execute!(io::stdout(), EnableMouseCapture)?;
+    }
 
     // create app and run it
     let tick_rate = Duration::from_millis(100);
     let app = App::default();
-    let res = run_app(&mut terminal, app, tick_rate);
+    let res = run_app(&mut terminal, app, tick_rate, &cli);
 
     // restore terminal
+    if GENERAL_CONFIG.mouse {
+        execute!(io::stdout(), DisableMouseCapture)?;
+    }
     ratatui::restore();
 
     if let Err(err) = res {
@@ -68,7 +123,71 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn run_app(terminal: &mut DefaultTerminal, mut app: App, tick_rate: Duration) -> io::Result<()> {
+/// Render the markdown in each of `paths`, or stdin if none are given or for `-`, to stdout
+/// without entering the alternate screen
+fn print_markdown(paths: &[String]) -> Result<(), Box<dyn Error>> {
+    if paths.is_empty() && io::stdin().is_terminal() {
+        eprintln!("No FILE given and nothing piped to standard input\n\n{USAGE}");
+        std::process::exit(2);
+    }
+    match write_markdown(paths) {
+        // The reader went away early, e.g. `mdt -p file.md | head`
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        Ok(()) => Ok(()),
+    }
+}
+
+fn write_markdown(paths: &[String]) -> io::Result<()> {
+    let width = terminal::size().map_or(80, |(width, _)| width);
+    let width = cmp::min(width.saturating_sub(1), GENERAL_CONFIG.width);
+
+    let mut stdout = io::stdout().lock();
+
+    let stdin = [STDIN_FILE.to_owned()];
+    let paths = if paths.is_empty() { &stdin[..] } else { paths };
+
+    for (i, path) in paths.iter().enumerate() {
+        let (name, text) = if path == STDIN_FILE {
+            (None, read_stdin())
+        } else {
+            (Some(path.as_str()), read_to_string(path).ok())
+        };
+        let text = text.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Could not open file {path}"),
+            )
+        })?;
+
+        if i > 0 {
+            writeln!(stdout)?;
+        }
+
+        let mut markdown = parse_markdown(name, &text, width.saturating_sub(2));
+        let output = render_to_ansi(&mut markdown, width.saturating_sub(3));
+        stdout.write_all(output.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// All of standard input, or `None` if it could not be read.
+fn read_stdin() -> Option<String> {
+    let mut text = String::new();
+    io::stdin().lock().read_to_string(&mut text).ok()?;
+    Some(text)
+}
+
+fn run_app(
+    terminal: &mut DefaultTerminal,
+    mut app: App,
+    tick_rate: Duration,
+    cli: &Cli,
+) -> io::Result<()> {
     let (f_tx, f_rx) = mpsc::channel::<Option<MdFile>>();
 
     thread::spawn(move || find_md_files_channel(f_tx.clone()));
@@ -89,22 +208,97 @@ fn run_app(terminal: &mut DefaultTerminal, mut app: App, tick_rate: Duration) ->
     let potential_input = io::stdin();
     let mut stdin_buf = String::new();
 
-    let args: Vec<String> = std::env::args().collect();
-    if let Some(arg) = args.get(1) {
-        if let Ok(file) = read_to_string(arg) {
-            let path = std::path::Path::new(arg);
-            let _ = watcher.watch(path, notify::RecursiveMode::NonRecursive);
-            markdown = parse_markdown(Some(arg), &file, app.width() - 2);
-            app.mode = Mode::View;
+    if GENERAL_CONFIG.remember_position {
+        app.positions = Positions::load();
+    }
+    app.marks = state::load_marks();
+
+    let mut buffers = BufferList::new();
+    let mut missing = Vec::new();
+    // The buffer of the first file, which `--line` and `--heading` are for
+    let mut first = None;
+
+    for (i, arg) in cli.files.iter().enumerate() {
+        let open = if arg == STDIN_FILE {
+            None
+        } else {
+            buffers.position(arg)
+        };
+        if open.is_some() {
+            first = first.or(open.filter(|_| i == 0));
+            continue;
+        }
+        let text = if arg == STDIN_FILE {
+            read_stdin()
+        } else {
+            read_to_string(arg).ok()
+        };
+        if let Some(text) = text {
+            let name = (arg != STDIN_FILE).then_some(arg.as_str());
+            if let Some(name) = name {
+                let path = std::path::Path::new(name);
+                let _ = watcher.watch(path, notify::RecursiveMode::NonRecursive);
+            }
+            let root = parse_markdown(name, &text, app.width() - 2);
+            buffers.push(&mut markdown, &mut app, root);
+            if i == 0 {
+                first = buffers.current();
+            }
         } else {
+            missing.push(arg.as_str());
+        }
+    }
+
+    if !cli.files.is_empty() {
+        if let Some(index) = first {
+            buffers.activate(index, &mut markdown, &mut app);
+            app.mode = Mode::View;
+
+            let height = terminal.size()?.height;
+            if let Some(heading) = &cli.heading {
+                if let Ok(index) = markdown.heading_offset(&format!("#{heading}")) {
+                    app.vertical_scroll =
+                        cmp::min(index, markdown.height().saturating_sub(height / 2));
+                } else {
+                    app.message_box
+                        .set_message(format!("Could not find heading {heading}"));
+                    app.boxes = Boxes::Error;
+                }
+            } else if let Some(line) = cli.line {
+                let offset = markdown
+                    .source_line_offset(line)
+                    .unwrap_or_else(|| markdown.height());
+                app.vertical_scroll =
+                    cmp::min(offset, markdown.height().saturating_sub(height / 2));
+            }
+        } else if let Some(index) = buffers.len().checked_sub(1) {
+            buffers.activate(index, &mut markdown, &mut app);
+            app.mode = Mode::View;
+        }
+        if !missing.is_empty() {
             app.message_box
-                .set_message(format!("Could not open file {arg}"));
+                .set_message(format!("Could not open file {}", missing.join(", ")));
             app.boxes = Boxes::Error;
         }
     } else if !potential_input.is_terminal() {
         let _ = potential_input.lock().read_to_string(&mut stdin_buf);
-        markdown = parse_markdown(None, &stdin_buf, app.width() - 2);
+        let root = parse_markdown(None, &stdin_buf, app.width() - 2);
+        buffers.push(&mut markdown, &mut app, root);
         app.mode = Mode::View;
+    } else if GENERAL_CONFIG.persist_history {
+        let (history, current) = state::load_history();
+        app.history = history;
+        if let Some(jump) = current {
+            let height = terminal.size()?.height;
+            jump_to(
+                jump,
+                &mut app,
+                &mut markdown,
+                &mut buffers,
+                height,
+                &mut watcher,
+            );
+        }
     }
 
     let mut file_tree = FileTree::default();
@@ -112,27 +306,38 @@ fn run_app(terminal: &mut DefaultTerminal, mut app: App, tick_rate: Duration) ->
     loop {
         let height = terminal.size()?.height;
 
-        for event in rx.try_iter() {
-            if event.is_err() {
-                continue;
-            }
-            let event = event.unwrap();
-
-            if let notify::EventKind::Modify(_) = event.kind {
-                if let Ok(file) = read_to_string(markdown.file_name().unwrap()) {
-                    markdown =
-                        parse_markdown(Some(markdown.file_name().unwrap()), &file, app.width() - 2);
-                    app.mode = Mode::View;
-                    app.vertical_scroll = cmp::min(
-                        app.vertical_scroll,
-                        markdown.height().saturating_sub(height / 2),
-                    );
+        let changed = rx
+            .try_iter()
+            .filter_map(Result::ok)
+            .filter(|event| matches!(event.kind, notify::EventKind::Modify(_)))
+            .flat_map(|event| event.paths)
+            .map(canonical_path)
+            .collect::<Vec<_>>();
+        if !changed.is_empty() {
+            buffers.invalidate_files(&changed);
+            if let Some(file_name) = markdown.file_name().map(str::to_string)
+                && changed.contains(&canonical_path(&file_name))
+                && let Ok(file) = read_to_string(&file_name)
+            {
+                reparse(&mut markdown, &file, app.width() - 2);
+                if app.task_selected {
+                    let _ = markdown.select_task(app.task_select_index);
                 }
-
-                break;
+                if app.wide_selected {
+                    let _ = markdown.select_wide(app.wide_select_index, app.width() - 2);
+                }
+                if app.table_selected {
+                    let _ = markdown.select_table(app.table_select_index, app.width() - 2);
+                }
+                app.mode = Mode::View;
+                app.vertical_scroll = cmp::min(
+                    app.vertical_scroll,
+                    markdown.height().saturating_sub(height / 2),
+                );
             }
         }
         if app.set_width(terminal.size()?.width - 1) {
+            buffers.invalidate_inactive();
             let url = if let Some(url) = markdown.file_name() {
                 url
             } else {
@@ -140,7 +345,6 @@ fn run_app(terminal: &mut DefaultTerminal, mut app: App, tick_rate: Duration) ->
                 continue;
             };
             let text = if let Ok(file) = read_to_string(url) {
-                app.vertical_scroll = 0;
                 file
             } else {
                 app.message_box
@@ -149,11 +353,25 @@ fn run_app(terminal: &mut DefaultTerminal, mut app: App, tick_rate: Duration) ->
                 app.mode = Mode::FileTree;
                 continue;
             };
-            markdown = parse_markdown(markdown.file_name(), &text, app.width() - 2);
+            reparse(&mut markdown, &text, app.width() - 2);
+            // Which blocks are too wide depends on the width
+            app.wide_selected = false;
+            if app.table_selected {
+                let _ = markdown.select_table(app.table_select_index, app.width() - 2);
+            }
+            app.vertical_scroll = cmp::min(
+                app.vertical_scroll,
+                markdown.height().saturating_sub(height / 2),
+            );
         }
 
         markdown.set_scroll(app.vertical_scroll);
 
+        if app.boxes == Boxes::Toc {
+            app.toc_box
+                .set_entries(markdown.headings(), app.vertical_scroll);
+        }
+
         terminal.draw(|f| {
             match app.mode {
                 Mode::View => {
@@ -185,6 +403,16 @@ fn run_app(terminal: &mut DefaultTerminal, mut app: App, tick_rate: Duration) ->
                     height: search_height,
                 };
                 f.render_widget(app.search_box.clone(), search_area);
+            } else if app.boxes == Boxes::Export {
+                let (export_height, export_width) = app.search_box.dimensions();
+                let export_area = Rect {
+                    x: app.search_box.x(),
+                    y: app.search_box.y(),
+                    width: export_width,
+                    height: export_height,
+                };
+                let title = " Save to a .md, .csv or .tsv file ";
+                f.render_widget(app.search_box.clone().with_title(title), export_area);
             } else if app.boxes == Boxes::Error {
                 let (error_height, error_width) = app.message_box.dimensions();
                 let error_area = Rect {
@@ -209,6 +437,36 @@ fn run_app(terminal: &mut DefaultTerminal, mut app: App, tick_rate: Duration) ->
 
                 f.render_widget(Clear, link_area);
                 f.render_widget(app.link_box.clone(), link_area);
+            } else if app.boxes == Boxes::Toc {
+                let size = f.area();
+                let toc_width = TocBox::width(size.width);
+                let toc_area = Rect {
+                    x: size.width.saturating_sub(toc_width),
+                    y: 0,
+                    width: toc_width,
+                    height: if GENERAL_CONFIG.help_menu {
+                        size.height.saturating_sub(5)
+                    } else {
+                        size.height
+                    },
+                };
+
+                f.render_widget(Clear, toc_area);
+                f.render_widget(app.toc_box.clone(), toc_area);
+            } else if app.boxes == Boxes::Marks {
+                let size = f.area();
+                let (mark_height, mark_width) = app.mark_box.dimensions();
+                let mark_width = cmp::min(mark_width, size.width);
+                let mark_height = cmp::min(mark_height, size.height.saturating_sub(4));
+                let mark_area = Rect {
+                    x: (size.width - mark_width) / 2,
+                    y: (size.height - mark_height) / 3,
+                    width: mark_width,
+                    height: mark_height,
+                };
+
+                f.render_widget(Clear, mark_area);
+                f.render_widget(app.mark_box.clone(), mark_area);
             }
         })?;
 
@@ -216,28 +474,80 @@ fn run_app(terminal: &mut DefaultTerminal, mut app: App, tick_rate: Duration) ->
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
 
-        if event::poll(timeout)?
-            && let Event::Key(key) = event::read()?
-        {
-            if key.kind != event::KeyEventKind::Press {
-                continue;
-            }
-            match handle_keyboard_input(
-                key.code,
-                &mut app,
-                &mut markdown,
-                &mut file_tree,
-                height,
-                &mut watcher,
-            ) {
+        if event::poll(timeout)? {
+            let action = match event::read()? {
+                Event::Key(key) if key.kind == event::KeyEventKind::Press => handle_keyboard_input(
+                    key,
+                    &mut app,
+                    &mut markdown,
+                    &mut buffers,
+                    &mut file_tree,
+                    height,
+                    &mut watcher,
+                ),
+                Event::Mouse(mouse) => {
+                    let size = terminal.size()?;
+                    handle_mouse_input(
+                        mouse,
+                        &mut app,
+                        &mut markdown,
+                        &mut buffers,
+                        &mut file_tree,
+                        Rect::new(0, 0, size.width, size.height),
+                        &mut watcher,
+                    )
+                }
+                _ => continue,
+            };
+            match action {
                 KeyBoardAction::Exit => {
+                    if GENERAL_CONFIG.remember_position {
+                        app.remember_position(&markdown);
+                        let _ = app.positions.save();
+                    }
+                    if !app.marks.is_empty() {
+                        let _ = state::save_marks(&app.marks);
+                    }
+                    if GENERAL_CONFIG.persist_history {
+                        let current = current_jump(&app, &markdown);
+                        let _ = state::save_history(&app.history, current.as_ref());
+                    }
                     return Ok(());
                 }
                 KeyBoardAction::Continue => {}
                 KeyBoardAction::Edit => {
+                    let line = markdown.source_line(app.vertical_scroll);
+                    // How far below the top of the screen the line is, to
+                    // put it back there once the file is read again
+                    let shift = line
+                        .and_then(|line| markdown.source_line_offset(line))
+                        .map(|y| i32::from(y) - i32::from(app.vertical_scroll));
+
+                    let mut edited = false;
                     terminal.draw(|f| {
-                        open_editor(f, &mut app, markdown.file_name());
+                        edited = open_editor(f, &mut app, markdown.file_name(), line.unwrap_or(1));
                     })?;
+
+                    if edited
+                        && let Some(file_name) = markdown.file_name().map(str::to_string)
+                        && let Ok(text) = read_to_string(&file_name)
+                    {
+                        reparse(&mut markdown, &text, app.width() - 2);
+                        app.selected = false;
+                        app.details_selected = false;
+                        app.task_selected = false;
+                        app.table_selected = false;
+
+                        let height = terminal.size()?.height;
+                        if let Some(y) = line.and_then(|line| markdown.source_line_offset(line)) {
+                            let scroll = i32::from(y) - shift.unwrap_or(0);
+                            app.vertical_scroll = u16::try_from(scroll).unwrap_or(0);
+                        }
+                        app.vertical_scroll = cmp::min(
+                            app.vertical_scroll,
+                            markdown.height().saturating_sub(height / 2),
+                        );
+                    }
                 }
             }
         }
@@ -249,20 +559,8 @@ fn run_app(terminal: &mut DefaultTerminal, mut app: App, tick_rate: Duration) ->
 
 fn render_file_tree(f: &mut Frame, app: &App, file_tree: FileTree) {
     let size = f.area();
-    let x = match GENERAL_CONFIG.centering {
-        util::general::Centering::Left => 2,
-        util::general::Centering::Center => {
-            cmp::max((size.width / 2).saturating_sub(GENERAL_CONFIG.width / 2), 2)
-        }
-        util::general::Centering::Right => {
-            cmp::max(size.width.saturating_sub(GENERAL_CONFIG.width + 2), 2)
-        }
-    };
-    let area = Rect {
-        x,
-        width: app.width() - 3,
-        ..size
-    };
+    let area = file_tree_area(size, app.width());
+    let x = area.x;
     f.render_widget(file_tree, area);
 
     if GENERAL_CONFIG.help_menu {
@@ -279,30 +577,8 @@ fn render_file_tree(f: &mut Frame, app: &App, file_tree: FileTree) {
 
 fn render_markdown(f: &mut Frame, app: &App, markdown: &mut ComponentRoot) {
     let size = f.area();
-
-    let x = match GENERAL_CONFIG.centering {
-        util::general::Centering::Left => 2,
-        util::general::Centering::Center => {
-            let x = (size.width / 2).saturating_sub(GENERAL_CONFIG.width / 2);
-
-            if x > 2 { x } else { 2 }
-        }
-        util::general::Centering::Right => {
-            let x = size.width.saturating_sub(GENERAL_CONFIG.width + 2);
-            if x > 2 { x } else { 2 }
-        }
-    };
-
-    let area = Rect {
-        width: cmp::min(app.width() - 3, size.width - 1),
-        height: if GENERAL_CONFIG.help_menu {
-            size.height.saturating_sub(5)
-        } else {
-            size.height
-        },
-        x,
-        ..size
-    };
+    let area = markdown_area(size, app.width());
+    let x = area.x;
 
     for child in markdown.children_mut() {
         match child {
@@ -392,44 +668,57 @@ fn render_markdown(f: &mut Frame, app: &App, markdown: &mut ComponentRoot) {
         }
     };
 
-    if app.boxes != Boxes::Search && GENERAL_CONFIG.help_menu {
+    if !matches!(app.boxes, Boxes::Search | Boxes::Export) && GENERAL_CONFIG.help_menu {
         f.render_widget(app.help_box, area);
     }
 }
 
-fn open_editor(f: &mut Frame, app: &mut App, file_name: Option<&str>) {
-    let editor = if let Ok(editor) = env::var("EDITOR") {
-        editor
-    } else {
+/// Open `file_name` at `line` with the configured editor command. Returns
+/// `false` if no editor could be started.
+fn open_editor(f: &mut Frame, app: &mut App, file_name: Option<&str>, line: usize) -> bool {
+    let Some(file_name) = file_name else {
         app.message_box
-            .set_message("No editor found. Please set the EDITOR environment variable".to_owned());
+            .set_message("No file found to open in editor".to_owned());
         app.boxes = Boxes::Error;
-        return;
+        return false;
     };
 
-    let file_name = if let Some(file_name) = file_name {
-        file_name
-    } else {
-        app.message_box
-            .set_message("No file found to open in editor".to_owned());
-        app.boxes = Boxes::Error;
-        return;
+    let editor = env::var("EDITOR").ok();
+    let template = GENERAL_CONFIG
+        .editor_command
+        .as_deref()
+        .unwrap_or_else(|| default_editor_command(editor.as_deref()));
+    let command = match editor_command(template, editor.as_deref(), file_name, line) {
+        Ok(command) => command,
+        Err(message) => {
+            app.message_box.set_message(message);
+            app.boxes = Boxes::Error;
+            return false;
+        }
     };
 
     disable_raw_mode().unwrap();
     execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture).unwrap();
     execute!(io::stdout(), cursor::Show).unwrap();
 
-    let _ = std::process::Command::new(editor)
-        .arg(file_name)
-        .spawn()
-        .expect("Failed to open editor")
-        .wait();
+    let status = std::process::Command::new(&command[0])
+        .args(&command[1..])
+        .status();
 
     enable_raw_mode().expect("Failed to enable raw mode");
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture).unwrap();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    if GENERAL_CONFIG.mouse {
+        execute!(stdout, EnableMouseCapture).unwrap();
+    }
+    f.render_widget(Clear, f.area());
 
+    if let Err(e) = status {
+        app.message_box
+            .set_message(format!("Could not run {}: {e}", command[0]));
+        app.boxes = Boxes::Error;
+        return false;
+    }
     app.boxes = Boxes::None;
-    f.render_widget(Clear, f.area());
+    true
 }
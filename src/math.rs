@@ -0,0 +1,1290 @@
+//! TeX math rendered as Unicode text. Inline math becomes a single line,
+//! display math may take several lines so fractions, limits and matrices
+//! can be stacked.
+
+use unicode_width::UnicodeWidthStr;
+
+/// Render inline math, the part between the `$` delimiters, as one line.
+#[must_use]
+pub fn latex_to_unicode(src: &str) -> String {
+    let nodes = Parser::new(src).parse_seq(Closer::Eof);
+    let text = flat(&nodes);
+    let mut out = String::with_capacity(text.len());
+    for c in text.trim().chars() {
+        if !(c == ' ' && out.ends_with(' ')) {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Render display math, the part between `$$` delimiters, as lines of equal
+/// width. Rows separated by `\\` are centered under each other.
+#[must_use]
+pub fn latex_to_lines(src: &str) -> Vec<String> {
+    let nodes = Parser::new(src).parse_seq(Closer::Eof);
+    let rows = split(&nodes, |n| matches!(n, Node::RowSep));
+
+    let block = if rows.len() > 1 && nodes.iter().any(|n| matches!(n, Node::ColumnSep)) {
+        layout_env(ALIGNED, &rows_to_cells(&nodes))
+    } else {
+        let rows: Vec<Block> = rows.iter().map(|row| layout_seq(trim(row))).collect();
+        let width = rows.iter().map(Block::width).max().unwrap_or(0);
+        Block {
+            lines: rows
+                .into_iter()
+                .flat_map(|row| row.centered(width).lines)
+                .collect(),
+            baseline: 0,
+        }
+    };
+    block.padded().lines
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Group(Vec<Node>),
+    Scripts {
+        base: Box<Node>,
+        sub: Option<Vec<Node>>,
+        sup: Option<Vec<Node>>,
+    },
+    Frac(Vec<Node>, Vec<Node>),
+    Binom(Vec<Node>, Vec<Node>),
+    Sqrt(Option<Vec<Node>>, Vec<Node>),
+    Accent(char, Vec<Node>),
+    /// Operator whose scripts go above and below it in display math.
+    BigOp(String),
+    Env(Env, Vec<Vec<Vec<Node>>>),
+    ColumnSep,
+    RowSep,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Center,
+    Left,
+    /// Right, left, right, ... like `aligned`, so `&=` lines up.
+    Alternate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Env {
+    open: &'static str,
+    close: &'static str,
+    align: Align,
+}
+
+const ALIGNED: Env = Env {
+    open: "",
+    close: "",
+    align: Align::Alternate,
+};
+
+fn env(name: &str) -> Env {
+    let (open, close, align) = match name.trim_end_matches('*') {
+        "pmatrix" => ("(", ")", Align::Center),
+        "bmatrix" => ("[", "]", Align::Center),
+        "Bmatrix" => ("{", "}", Align::Center),
+        "vmatrix" => ("|", "|", Align::Center),
+        "Vmatrix" => ("‖", "‖", Align::Center),
+        "cases" => ("{", "", Align::Left),
+        "aligned" | "align" | "alignat" | "split" | "eqnarray" => return ALIGNED,
+        _ => ("", "", Align::Center),
+    };
+    Env { open, close, align }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Closer {
+    Eof,
+    Brace,
+    Bracket,
+    End,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(src: &str) -> Self {
+        Self {
+            chars: src.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_seq(&mut self, closer: Closer) -> Vec<Node> {
+        let mut nodes: Vec<Node> = Vec::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '}' if closer == Closer::Brace => {
+                    self.pos += 1;
+                    return nodes;
+                }
+                ']' if closer == Closer::Bracket => {
+                    self.pos += 1;
+                    return nodes;
+                }
+                '}' => self.pos += 1,
+                '{' => {
+                    self.pos += 1;
+                    nodes.push(Node::Group(self.parse_seq(Closer::Brace)));
+                }
+                '^' | '_' => {
+                    self.pos += 1;
+                    let script = self.parse_arg();
+                    attach_script(&mut nodes, c == '^', script);
+                }
+                '&' => {
+                    self.pos += 1;
+                    nodes.push(Node::ColumnSep);
+                }
+                c if c.is_whitespace() => {
+                    self.skip_whitespace();
+                    if !matches!(nodes.last(), Some(Node::Text(t)) if t == " ") {
+                        nodes.push(Node::Text(" ".to_owned()));
+                    }
+                }
+                '\\' => {
+                    self.pos += 1;
+                    let name = self.command_name();
+                    if name == "end" {
+                        self.raw_arg();
+                        if closer == Closer::End {
+                            return nodes;
+                        }
+                        continue;
+                    }
+                    nodes.extend(self.command(&name));
+                }
+                c => {
+                    self.pos += 1;
+                    nodes.push(Node::Text(symbol_char(c).to_string()));
+                }
+            }
+        }
+        nodes
+    }
+
+    fn command_name(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            // Control symbols like `\,` or `\{` are a single character
+            self.pos += 1;
+            return self
+                .chars
+                .get(start)
+                .map(char::to_string)
+                .unwrap_or_default();
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// A single argument: a braced group, a command or one character.
+    fn parse_arg(&mut self) -> Vec<Node> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                self.parse_seq(Closer::Brace)
+            }
+            Some('\\') => {
+                self.pos += 1;
+                let name = self.command_name();
+                self.command(&name)
+            }
+            Some(c) => {
+                self.pos += 1;
+                vec![Node::Text(symbol_char(c).to_string())]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// The argument as written, for text and font commands.
+    fn raw_arg(&mut self) -> String {
+        self.skip_whitespace();
+        if self.peek() != Some('{') {
+            self.pos += 1;
+            return self
+                .chars
+                .get(self.pos - 1)
+                .map(char::to_string)
+                .unwrap_or_default();
+        }
+        self.pos += 1;
+        let start = self.pos;
+        let mut depth = 1;
+        while let Some(c) = self.peek() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                break;
+            }
+            self.pos += 1;
+        }
+        let raw = self.chars[start..self.pos].iter().collect();
+        self.pos += 1;
+        raw
+    }
+
+    fn command(&mut self, name: &str) -> Vec<Node> {
+        let node = match name {
+            "\\" | "newline" | "cr" => Node::RowSep,
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let num = self.parse_arg();
+                Node::Frac(num, self.parse_arg())
+            }
+            "binom" | "dbinom" | "tbinom" => {
+                let n = self.parse_arg();
+                Node::Binom(n, self.parse_arg())
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+                let index = (self.peek() == Some('[')).then(|| {
+                    self.pos += 1;
+                    self.parse_seq(Closer::Bracket)
+                });
+                Node::Sqrt(index, self.parse_arg())
+            }
+            "begin" => {
+                let env = env(&self.raw_arg());
+                // Column specs of `array` and friends
+                self.skip_whitespace();
+                if self.peek() == Some('{') && env.open.is_empty() {
+                    self.raw_arg();
+                }
+                let body = self.parse_seq(Closer::End);
+                Node::Env(env, rows_to_cells(&body))
+            }
+            "left" | "right" | "middle" => {
+                self.skip_whitespace();
+                if self.peek() == Some('.') {
+                    self.pos += 1;
+                }
+                return Vec::new();
+            }
+            "big" | "Big" | "bigg" | "Bigg" | "bigl" | "bigr" | "Bigl" | "Bigr" | "biggl"
+            | "biggr" | "Biggl" | "Biggr" | "displaystyle" | "textstyle" | "scriptstyle"
+            | "limits" | "nolimits" | "!" => return Vec::new(),
+            "text" | "textrm" | "textbf" | "textit" | "textsf" | "texttt" | "mbox"
+            | "operatorname" => Node::Text(self.raw_arg()),
+            "mathrm" | "mathbf" | "mathit" | "mathsf" | "mathtt" | "boldsymbol" | "bm" => {
+                Node::Group(self.parse_arg())
+            }
+            "mathbb" => Node::Text(self.raw_arg().chars().map(double_struck).collect()),
+            "mathcal" | "mathscr" => Node::Text(self.raw_arg().chars().map(script).collect()),
+            "mathfrak" => Node::Text(self.raw_arg().chars().map(fraktur).collect()),
+            "not" => Node::Accent('\u{338}', self.parse_arg()),
+            "hat" | "widehat" => Node::Accent('\u{302}', self.parse_arg()),
+            "bar" | "overline" => Node::Accent('\u{305}', self.parse_arg()),
+            "vec" | "overrightarrow" => Node::Accent('\u{20d7}', self.parse_arg()),
+            "dot" => Node::Accent('\u{307}', self.parse_arg()),
+            "ddot" => Node::Accent('\u{308}', self.parse_arg()),
+            "tilde" | "widetilde" => Node::Accent('\u{303}', self.parse_arg()),
+            "check" => Node::Accent('\u{30c}', self.parse_arg()),
+            "acute" => Node::Accent('\u{301}', self.parse_arg()),
+            "grave" => Node::Accent('\u{300}', self.parse_arg()),
+            "breve" => Node::Accent('\u{306}', self.parse_arg()),
+            "underline" => Node::Accent('\u{332}', self.parse_arg()),
+            "lim" | "limsup" | "liminf" | "max" | "min" | "sup" | "inf" | "argmax" | "argmin" => {
+                Node::BigOp(match name {
+                    "limsup" => "lim sup".to_owned(),
+                    "liminf" => "lim inf".to_owned(),
+                    "argmax" => "arg max".to_owned(),
+                    "argmin" => "arg min".to_owned(),
+                    _ => name.to_owned(),
+                })
+            }
+            "sum" | "prod" | "coprod" | "bigcup" | "bigcap" | "bigoplus" | "bigotimes"
+            | "bigvee" | "bigwedge" => Node::BigOp(symbol(name).unwrap_or(name).to_owned()),
+            _ => match symbol(name) {
+                Some(symbol) => Node::Text(symbol.to_owned()),
+                None if FUNCTIONS.contains(&name) => Node::Text(name.to_owned()),
+                None => Node::Text(format!("\\{name}")),
+            },
+        };
+        vec![node]
+    }
+}
+
+fn attach_script(nodes: &mut Vec<Node>, sup: bool, script: Vec<Node>) {
+    let base = match nodes.pop() {
+        Some(Node::Scripts {
+            base,
+            sub,
+            sup: None,
+        }) if sup => {
+            nodes.push(Node::Scripts {
+                base,
+                sub,
+                sup: Some(script),
+            });
+            return;
+        }
+        Some(Node::Scripts {
+            base,
+            sub: None,
+            sup: existing,
+        }) if !sup => {
+            nodes.push(Node::Scripts {
+                base,
+                sub: Some(script),
+                sup: existing,
+            });
+            return;
+        }
+        Some(node) => node,
+        None => Node::Text(String::new()),
+    };
+    let (sub, sup) = if sup {
+        (None, Some(script))
+    } else {
+        (Some(script), None)
+    };
+    nodes.push(Node::Scripts {
+        base: Box::new(base),
+        sub,
+        sup,
+    });
+}
+
+fn split(nodes: &[Node], at: impl Fn(&Node) -> bool) -> Vec<&[Node]> {
+    nodes.split(at).collect()
+}
+
+fn rows_to_cells(nodes: &[Node]) -> Vec<Vec<Vec<Node>>> {
+    let mut rows: Vec<Vec<Vec<Node>>> = split(nodes, |n| matches!(n, Node::RowSep))
+        .into_iter()
+        .map(|row| {
+            split(row, |n| matches!(n, Node::ColumnSep))
+                .into_iter()
+                .map(|cell| trim(cell).to_vec())
+                .collect()
+        })
+        .collect();
+    // A trailing `\\` leaves an empty last row
+    if rows.last().is_some_and(|row| row.iter().all(Vec::is_empty)) {
+        rows.pop();
+    }
+    rows
+}
+
+fn trim(nodes: &[Node]) -> &[Node] {
+    let is_space = |n: &Node| matches!(n, Node::Text(t) if t.trim().is_empty());
+    let start = nodes
+        .iter()
+        .position(|n| !is_space(n))
+        .unwrap_or(nodes.len());
+    let end = nodes
+        .iter()
+        .rposition(|n| !is_space(n))
+        .map_or(start, |i| i + 1);
+    &nodes[start..end]
+}
+
+// Inline rendering
+
+fn flat(nodes: &[Node]) -> String {
+    nodes.iter().map(flat_node).collect()
+}
+
+fn flat_node(node: &Node) -> String {
+    match node {
+        Node::Text(text) | Node::BigOp(text) => text.clone(),
+        Node::Group(nodes) => flat(nodes),
+        Node::Scripts { base, sub, sup } => {
+            let mut out = flat_node(base);
+            if let Some(sub) = sub {
+                out.push_str(&flat_script(sub, false));
+            }
+            if let Some(sup) = sup {
+                out.push_str(&flat_script(sup, true));
+            }
+            out
+        }
+        Node::Frac(num, den) => {
+            let (num, den) = (flat(num), flat(den));
+            match (to_script(&num, true), to_script(&den, false)) {
+                (Some(n), Some(d)) if num.chars().count() <= 2 && den.chars().count() <= 2 => {
+                    format!("{n}\u{2044}{d}")
+                }
+                _ => format!("{}/{}", parenthesize(&num), parenthesize(&den)),
+            }
+        }
+        Node::Binom(n, k) => format!("C({}, {})", flat(n).trim(), flat(k).trim()),
+        Node::Sqrt(index, body) => {
+            let body = flat(body);
+            format!(
+                "{}{}",
+                root_sign(index.as_deref().map(flat).as_deref()),
+                parenthesize(&body)
+            )
+        }
+        Node::Accent(accent, body) => accented(&flat(body), *accent),
+        Node::Env(env, rows) => {
+            let separator = if env.align == Align::Alternate {
+                " "
+            } else {
+                ", "
+            };
+            let rows: Vec<String> = rows
+                .iter()
+                .map(|cells| {
+                    cells
+                        .iter()
+                        .map(|cell| flat(cell).trim().to_owned())
+                        .collect::<Vec<_>>()
+                        .join(if env.align == Align::Center {
+                            " "
+                        } else {
+                            separator
+                        })
+                })
+                .collect();
+            format!("{}{}{}", env.open, rows.join("; "), env.close)
+        }
+        Node::ColumnSep => " ".to_owned(),
+        Node::RowSep => "; ".to_owned(),
+    }
+}
+
+fn flat_script(nodes: &[Node], sup: bool) -> String {
+    script_text(&flat(nodes), sup)
+}
+
+/// Plain text raised or lowered, in superscript or subscript characters when
+/// it has them and after a `^` or `_` when it does not.
+#[must_use]
+pub fn script_text(text: &str, sup: bool) -> String {
+    let text = text.trim();
+    if let Some(script) = to_script(text, sup) {
+        return script;
+    }
+    let marker = if sup { '^' } else { '_' };
+    if text.chars().count() == 1 {
+        format!("{marker}{text}")
+    } else {
+        format!("{marker}({text})")
+    }
+}
+
+fn parenthesize(text: &str) -> String {
+    let text = text.trim();
+    let atomic = !text.contains([' ', '+', '−', '±', '∓', '=', '/', '⋅', '×', '<', '>'])
+        || (text.starts_with('(') && text.ends_with(')'));
+    if atomic {
+        text.to_owned()
+    } else {
+        format!("({text})")
+    }
+}
+
+fn root_sign(index: Option<&str>) -> String {
+    match index.map(str::trim) {
+        None | Some("2") => "√".to_owned(),
+        Some("3") => "∛".to_owned(),
+        Some("4") => "∜".to_owned(),
+        Some(index) => format!(
+            "{}√",
+            to_script(index, true).unwrap_or_else(|| index.into())
+        ),
+    }
+}
+
+/// Put a combining `accent` on `text`: over every character for bars, after
+/// the last one otherwise.
+fn accented(text: &str, accent: char) -> String {
+    if accent == '\u{305}' || accent == '\u{332}' {
+        text.chars().flat_map(|c| [c, accent]).collect()
+    } else {
+        format!("{text}{accent}")
+    }
+}
+
+// Display rendering
+
+#[derive(Debug, Clone)]
+struct Block {
+    lines: Vec<String>,
+    /// Line the block is aligned on when placed next to others.
+    baseline: usize,
+}
+
+impl Block {
+    fn text(text: impl Into<String>) -> Self {
+        Self {
+            lines: vec![text.into()],
+            baseline: 0,
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.lines.iter().map(|l| l.width()).max().unwrap_or(0)
+    }
+
+    fn height(&self) -> usize {
+        self.lines.len()
+    }
+
+    fn is_single_line(&self) -> bool {
+        self.lines.len() == 1
+    }
+
+    fn padded(self) -> Self {
+        let width = self.width();
+        Self {
+            lines: self.lines.iter().map(|l| pad(l, width)).collect(),
+            baseline: self.baseline,
+        }
+    }
+
+    fn centered(self, width: usize) -> Self {
+        let own = self.width();
+        let left = (width.saturating_sub(own)) / 2;
+        Self {
+            lines: self
+                .lines
+                .iter()
+                .map(|l| pad(&format!("{}{}", " ".repeat(left), pad(l, own)), width))
+                .collect(),
+            baseline: self.baseline,
+        }
+    }
+
+    fn aligned(self, width: usize, align: Align, column: usize) -> Self {
+        let right = align == Align::Alternate && column.is_multiple_of(2);
+        if align == Align::Center {
+            return self.centered(width);
+        }
+        let own = self.width();
+        Self {
+            lines: self
+                .lines
+                .iter()
+                .map(|l| {
+                    if right {
+                        format!("{}{}", " ".repeat(width - own), pad(l, own))
+                    } else {
+                        pad(l, width)
+                    }
+                })
+                .collect(),
+            baseline: self.baseline,
+        }
+    }
+}
+
+fn pad(line: &str, width: usize) -> String {
+    format!("{line}{}", " ".repeat(width.saturating_sub(line.width())))
+}
+
+/// Place blocks side by side, lined up on their baselines.
+fn hcat(blocks: Vec<Block>) -> Block {
+    let above = blocks.iter().map(|b| b.baseline).max().unwrap_or(0);
+    let below = blocks
+        .iter()
+        .map(|b| b.height() - b.baseline - 1)
+        .max()
+        .unwrap_or(0);
+    let mut lines = vec![String::new(); above + below + 1];
+    for block in &blocks {
+        let width = block.width();
+        let top = above - block.baseline;
+        for (i, line) in lines.iter_mut().enumerate() {
+            let row = i.checked_sub(top).and_then(|i| block.lines.get(i));
+            line.push_str(&pad(row.map_or("", String::as_str), width));
+        }
+    }
+    Block {
+        lines,
+        baseline: above,
+    }
+}
+
+/// Stack blocks centered on each other, with `baseline` taken from the
+/// block at index `base`.
+fn vstack(blocks: Vec<Block>, base: usize) -> Block {
+    let width = blocks.iter().map(Block::width).max().unwrap_or(0);
+    let baseline = blocks[..base].iter().map(Block::height).sum::<usize>() + blocks[base].baseline;
+    Block {
+        lines: blocks
+            .into_iter()
+            .flat_map(|b| b.centered(width).lines)
+            .collect(),
+        baseline,
+    }
+}
+
+fn layout_seq(nodes: &[Node]) -> Block {
+    if nodes.is_empty() {
+        return Block::text("");
+    }
+    let blocks: Vec<Block> = nodes.iter().map(layout_node).collect();
+    if blocks.iter().all(Block::is_single_line) {
+        return Block::text(
+            blocks
+                .into_iter()
+                .map(|b| b.lines.concat())
+                .collect::<String>(),
+        );
+    }
+    hcat(blocks)
+}
+
+fn layout_node(node: &Node) -> Block {
+    match node {
+        Node::Group(nodes) => layout_seq(nodes),
+        Node::Scripts { base, sub, sup } => {
+            let base_block = layout_node(base);
+            let sub = sub.as_deref().map(layout_seq);
+            let sup = sup.as_deref().map(layout_seq);
+
+            if matches!(**base, Node::BigOp(_)) {
+                let mut blocks = Vec::new();
+                let has_sup = sup.is_some();
+                blocks.extend(sup);
+                blocks.push(base_block);
+                blocks.extend(sub);
+                return vstack(blocks, usize::from(has_sup));
+            }
+
+            let inline = base_block.is_single_line()
+                && [&sub, &sup]
+                    .iter()
+                    .zip([false, true])
+                    .all(|(script, is_sup)| {
+                        script.as_ref().is_none_or(|s| {
+                            s.is_single_line() && to_script(s.lines[0].trim(), is_sup).is_some()
+                        })
+                    });
+            if inline {
+                return Block::text(flat_node(node));
+            }
+
+            let sup = sup.unwrap_or_else(|| Block {
+                lines: Vec::new(),
+                baseline: 0,
+            });
+            let sub = sub.unwrap_or_else(|| Block {
+                lines: Vec::new(),
+                baseline: 0,
+            });
+            let (base_width, script_width) = (base_block.width(), sup.width().max(sub.width()));
+            let mut lines = Vec::new();
+            for line in &sup.lines {
+                lines.push(format!(
+                    "{}{}",
+                    " ".repeat(base_width),
+                    pad(line, script_width)
+                ));
+            }
+            for line in &base_block.lines {
+                lines.push(format!(
+                    "{}{}",
+                    pad(line, base_width),
+                    " ".repeat(script_width)
+                ));
+            }
+            for line in &sub.lines {
+                lines.push(format!(
+                    "{}{}",
+                    " ".repeat(base_width),
+                    pad(line, script_width)
+                ));
+            }
+            Block {
+                lines,
+                baseline: sup.height() + base_block.baseline,
+            }
+        }
+        Node::Frac(num, den) => {
+            let (num, den) = (layout_seq(trim(num)), layout_seq(trim(den)));
+            let width = num.width().max(den.width()) + 2;
+            let rule = Block::text("─".repeat(width));
+            vstack(vec![num, rule, den], 1)
+        }
+        Node::Binom(n, k) => layout_env(
+            env("pmatrix"),
+            &[vec![trim(n).to_vec()], vec![trim(k).to_vec()]],
+        ),
+        Node::Sqrt(index, body) => {
+            let body = layout_seq(trim(body));
+            let sign = root_sign(index.as_deref().map(flat).as_deref());
+            let indent = " ".repeat(sign.width());
+            let last = body.height() - 1;
+            let mut lines = vec![format!("{indent}{}", "_".repeat(body.width()))];
+            for (i, line) in body.lines.iter().enumerate() {
+                let prefix = if i == last {
+                    sign.clone()
+                } else {
+                    "│".to_owned()
+                };
+                lines.push(format!("{}{}", pad(&prefix, indent.len()), line));
+            }
+            Block {
+                lines,
+                baseline: body.baseline + 1,
+            }
+        }
+        Node::Env(env, rows) => layout_env(*env, rows),
+        Node::Accent(..) | Node::Text(_) | Node::BigOp(_) | Node::ColumnSep | Node::RowSep => {
+            Block::text(flat_node(node))
+        }
+    }
+}
+
+fn layout_env(env: Env, rows: &[Vec<Vec<Node>>]) -> Block {
+    let cells: Vec<Vec<Block>> = rows
+        .iter()
+        .map(|row| row.iter().map(|cell| layout_seq(cell)).collect())
+        .collect();
+    let columns = cells.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|j| {
+            cells
+                .iter()
+                .filter_map(|row| row.get(j).map(Block::width))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut lines = Vec::new();
+    for row in cells {
+        let mut blocks = Vec::new();
+        for (j, width) in widths.iter().enumerate() {
+            if j > 0 {
+                let gap = if env.align == Align::Alternate && j % 2 == 1 {
+                    1
+                } else {
+                    2
+                };
+                blocks.push(Block::text(" ".repeat(gap)));
+            }
+            let cell = row.get(j).cloned().unwrap_or_else(|| Block::text(""));
+            blocks.push(cell.aligned(*width, env.align, j));
+        }
+        lines.extend(hcat(blocks).lines);
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+
+    let height = lines.len();
+    let open = delimiter(env.open, height);
+    let close = delimiter(env.close, height);
+    let lines = lines
+        .iter()
+        .zip(open.iter().zip(&close))
+        .map(|(line, (open, close))| {
+            let open = if open.is_empty() {
+                ""
+            } else {
+                &format!("{open} ")
+            };
+            let close = if close.is_empty() {
+                ""
+            } else {
+                &format!(" {close}")
+            };
+            format!("{open}{line}{close}")
+        })
+        .collect();
+    Block {
+        lines,
+        baseline: (height - 1) / 2,
+    }
+}
+
+/// `delim` stretched over `height` lines.
+fn delimiter(delim: &str, height: usize) -> Vec<String> {
+    if height == 1 {
+        return vec![delim.to_owned()];
+    }
+    let (top, middle, bottom, center) = match delim {
+        "(" => ("⎛", "⎜", "⎝", "⎜"),
+        ")" => ("⎞", "⎟", "⎠", "⎟"),
+        "[" => ("⎡", "⎢", "⎣", "⎢"),
+        "]" => ("⎤", "⎥", "⎦", "⎥"),
+        "{" if height == 2 => ("⎰", "⎪", "⎱", "⎪"),
+        "}" if height == 2 => ("⎱", "⎪", "⎰", "⎪"),
+        "{" => ("⎧", "⎪", "⎩", "⎨"),
+        "}" => ("⎫", "⎪", "⎭", "⎬"),
+        other => (other, other, other, other),
+    };
+    (0..height)
+        .map(|i| {
+            if i == 0 {
+                top
+            } else if i == height - 1 {
+                bottom
+            } else if i == (height - 1) / 2 {
+                center
+            } else {
+                middle
+            }
+            .to_owned()
+        })
+        .collect()
+}
+
+// Character tables
+
+/// Characters that are written differently in math than in the source.
+fn symbol_char(c: char) -> char {
+    match c {
+        '-' => '−',
+        '\'' => '′',
+        '~' => ' ',
+        '*' => '∗',
+        c => c,
+    }
+}
+
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "sinh", "cosh", "tanh", "coth", "arcsin", "arccos",
+    "arctan", "log", "ln", "lg", "exp", "det", "dim", "ker", "deg", "gcd", "hom", "arg", "Pr",
+    "tr", "diag", "rank", "sgn", "softmax",
+];
+
+const SYMBOLS: &[(&str, &str)] = &[
+    // Greek
+    ("alpha", "α"),
+    ("beta", "β"),
+    ("gamma", "γ"),
+    ("delta", "δ"),
+    ("epsilon", "ϵ"),
+    ("varepsilon", "ε"),
+    ("zeta", "ζ"),
+    ("eta", "η"),
+    ("theta", "θ"),
+    ("vartheta", "ϑ"),
+    ("iota", "ι"),
+    ("kappa", "κ"),
+    ("lambda", "λ"),
+    ("mu", "μ"),
+    ("nu", "ν"),
+    ("xi", "ξ"),
+    ("omicron", "ο"),
+    ("pi", "π"),
+    ("varpi", "ϖ"),
+    ("rho", "ρ"),
+    ("varrho", "ϱ"),
+    ("sigma", "σ"),
+    ("varsigma", "ς"),
+    ("tau", "τ"),
+    ("upsilon", "υ"),
+    ("phi", "ϕ"),
+    ("varphi", "φ"),
+    ("chi", "χ"),
+    ("psi", "ψ"),
+    ("omega", "ω"),
+    ("Gamma", "Γ"),
+    ("Delta", "Δ"),
+    ("Theta", "Θ"),
+    ("Lambda", "Λ"),
+    ("Xi", "Ξ"),
+    ("Pi", "Π"),
+    ("Sigma", "Σ"),
+    ("Upsilon", "Υ"),
+    ("Phi", "Φ"),
+    ("Psi", "Ψ"),
+    ("Omega", "Ω"),
+    // Big operators
+    ("sum", "∑"),
+    ("prod", "∏"),
+    ("coprod", "∐"),
+    ("bigcup", "⋃"),
+    ("bigcap", "⋂"),
+    ("bigoplus", "⨁"),
+    ("bigotimes", "⨂"),
+    ("bigvee", "⋁"),
+    ("bigwedge", "⋀"),
+    ("int", "∫"),
+    ("iint", "∬"),
+    ("iiint", "∭"),
+    ("oint", "∮"),
+    // Binary operators
+    ("cdot", "⋅"),
+    ("times", "×"),
+    ("div", "÷"),
+    ("pm", "±"),
+    ("mp", "∓"),
+    ("ast", "∗"),
+    ("star", "⋆"),
+    ("circ", "∘"),
+    ("bullet", "∙"),
+    ("oplus", "⊕"),
+    ("ominus", "⊖"),
+    ("otimes", "⊗"),
+    ("odot", "⊙"),
+    ("cup", "∪"),
+    ("cap", "∩"),
+    ("setminus", "∖"),
+    ("wedge", "∧"),
+    ("land", "∧"),
+    ("vee", "∨"),
+    ("lor", "∨"),
+    // Relations
+    ("leq", "≤"),
+    ("le", "≤"),
+    ("geq", "≥"),
+    ("ge", "≥"),
+    ("neq", "≠"),
+    ("ne", "≠"),
+    ("approx", "≈"),
+    ("equiv", "≡"),
+    ("sim", "∼"),
+    ("simeq", "≃"),
+    ("cong", "≅"),
+    ("propto", "∝"),
+    ("ll", "≪"),
+    ("gg", "≫"),
+    ("in", "∈"),
+    ("notin", "∉"),
+    ("ni", "∋"),
+    ("subset", "⊂"),
+    ("subseteq", "⊆"),
+    ("supset", "⊃"),
+    ("supseteq", "⊇"),
+    ("perp", "⊥"),
+    ("parallel", "∥"),
+    ("mid", "∣"),
+    ("models", "⊨"),
+    ("vdash", "⊢"),
+    ("coloneqq", "≔"),
+    ("triangleq", "≜"),
+    // Arrows
+    ("to", "→"),
+    ("rightarrow", "→"),
+    ("leftarrow", "←"),
+    ("gets", "←"),
+    ("leftrightarrow", "↔"),
+    ("Rightarrow", "⇒"),
+    ("Leftarrow", "⇐"),
+    ("Leftrightarrow", "⇔"),
+    ("implies", "⟹"),
+    ("impliedby", "⟸"),
+    ("iff", "⟺"),
+    ("mapsto", "↦"),
+    ("uparrow", "↑"),
+    ("downarrow", "↓"),
+    ("longrightarrow", "⟶"),
+    ("longleftarrow", "⟵"),
+    // Misc
+    ("infty", "∞"),
+    ("partial", "∂"),
+    ("nabla", "∇"),
+    ("forall", "∀"),
+    ("exists", "∃"),
+    ("nexists", "∄"),
+    ("neg", "¬"),
+    ("lnot", "¬"),
+    ("emptyset", "∅"),
+    ("varnothing", "∅"),
+    ("ldots", "…"),
+    ("dots", "…"),
+    ("cdots", "⋯"),
+    ("vdots", "⋮"),
+    ("ddots", "⋱"),
+    ("prime", "′"),
+    ("hbar", "ℏ"),
+    ("ell", "ℓ"),
+    ("Re", "ℜ"),
+    ("Im", "ℑ"),
+    ("aleph", "ℵ"),
+    ("angle", "∠"),
+    ("top", "⊤"),
+    ("bot", "⊥"),
+    ("therefore", "∴"),
+    ("because", "∵"),
+    ("degree", "°"),
+    ("langle", "⟨"),
+    ("rangle", "⟩"),
+    ("lceil", "⌈"),
+    ("rceil", "⌉"),
+    ("lfloor", "⌊"),
+    ("rfloor", "⌋"),
+    ("lvert", "|"),
+    ("rvert", "|"),
+    ("vert", "|"),
+    ("lVert", "‖"),
+    ("rVert", "‖"),
+    ("Vert", "‖"),
+    ("|", "‖"),
+    // Spacing and escapes
+    ("quad", "  "),
+    ("qquad", "    "),
+    (",", " "),
+    (":", " "),
+    (";", " "),
+    (" ", " "),
+    ("{", "{"),
+    ("}", "}"),
+    ("%", "%"),
+    ("$", "$"),
+    ("&", "&"),
+    ("#", "#"),
+    ("_", "_"),
+];
+
+fn symbol(name: &str) -> Option<&'static str> {
+    SYMBOLS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, symbol)| *symbol)
+}
+
+const SUPERSCRIPTS: &[(char, char)] = &[
+    ('0', '⁰'),
+    ('1', '¹'),
+    ('2', '²'),
+    ('3', '³'),
+    ('4', '⁴'),
+    ('5', '⁵'),
+    ('6', '⁶'),
+    ('7', '⁷'),
+    ('8', '⁸'),
+    ('9', '⁹'),
+    ('+', '⁺'),
+    ('−', '⁻'),
+    ('=', '⁼'),
+    ('(', '⁽'),
+    (')', '⁾'),
+    ('a', 'ᵃ'),
+    ('b', 'ᵇ'),
+    ('c', 'ᶜ'),
+    ('d', 'ᵈ'),
+    ('e', 'ᵉ'),
+    ('f', 'ᶠ'),
+    ('g', 'ᵍ'),
+    ('h', 'ʰ'),
+    ('i', 'ⁱ'),
+    ('j', 'ʲ'),
+    ('k', 'ᵏ'),
+    ('l', 'ˡ'),
+    ('m', 'ᵐ'),
+    ('n', 'ⁿ'),
+    ('o', 'ᵒ'),
+    ('p', 'ᵖ'),
+    ('r', 'ʳ'),
+    ('s', 'ˢ'),
+    ('t', 'ᵗ'),
+    ('u', 'ᵘ'),
+    ('v', 'ᵛ'),
+    ('w', 'ʷ'),
+    ('x', 'ˣ'),
+    ('y', 'ʸ'),
+    ('z', 'ᶻ'),
+    ('A', 'ᴬ'),
+    ('B', 'ᴮ'),
+    ('D', 'ᴰ'),
+    ('E', 'ᴱ'),
+    ('G', 'ᴳ'),
+    ('H', 'ᴴ'),
+    ('I', 'ᴵ'),
+    ('J', 'ᴶ'),
+    ('K', 'ᴷ'),
+    ('L', 'ᴸ'),
+    ('M', 'ᴹ'),
+    ('N', 'ᴺ'),
+    ('O', 'ᴼ'),
+    ('P', 'ᴾ'),
+    ('R', 'ᴿ'),
+    ('T', 'ᵀ'),
+    ('U', 'ᵁ'),
+    ('V', 'ⱽ'),
+    ('W', 'ᵂ'),
+    ('α', 'ᵅ'),
+    ('β', 'ᵝ'),
+    ('γ', 'ᵞ'),
+    ('δ', 'ᵟ'),
+    ('ε', 'ᵋ'),
+    ('θ', 'ᶿ'),
+    ('ι', 'ᶥ'),
+    ('ϕ', 'ᵠ'),
+    ('φ', 'ᵠ'),
+    ('χ', 'ᵡ'),
+    ('′', '′'),
+    ('∗', '*'),
+    (' ', ' '),
+];
+
+const SUBSCRIPTS: &[(char, char)] = &[
+    ('0', '₀'),
+    ('1', '₁'),
+    ('2', '₂'),
+    ('3', '₃'),
+    ('4', '₄'),
+    ('5', '₅'),
+    ('6', '₆'),
+    ('7', '₇'),
+    ('8', '₈'),
+    ('9', '₉'),
+    ('+', '₊'),
+    ('−', '₋'),
+    ('=', '₌'),
+    ('(', '₍'),
+    (')', '₎'),
+    ('a', 'ₐ'),
+    ('e', 'ₑ'),
+    ('h', 'ₕ'),
+    ('i', 'ᵢ'),
+    ('j', 'ⱼ'),
+    ('k', 'ₖ'),
+    ('l', 'ₗ'),
+    ('m', 'ₘ'),
+    ('n', 'ₙ'),
+    ('o', 'ₒ'),
+    ('p', 'ₚ'),
+    ('r', 'ᵣ'),
+    ('s', 'ₛ'),
+    ('t', 'ₜ'),
+    ('u', 'ᵤ'),
+    ('v', 'ᵥ'),
+    ('x', 'ₓ'),
+    ('β', 'ᵦ'),
+    ('γ', 'ᵧ'),
+    ('ρ', 'ᵨ'),
+    ('ϕ', 'ᵩ'),
+    ('φ', 'ᵩ'),
+    ('χ', 'ᵪ'),
+    (' ', ' '),
+];
+
+/// `text` in superscript or subscript characters, if every character has
+/// one.
+fn to_script(text: &str, sup: bool) -> Option<String> {
+    let table = if sup { SUPERSCRIPTS } else { SUBSCRIPTS };
+    text.chars()
+        .map(|c| table.iter().find(|(from, _)| *from == c).map(|(_, to)| *to))
+        .collect()
+}
+
+fn offset(c: char, base: u32) -> char {
+    char::from_u32(base + (c as u32 - 'A' as u32)).unwrap_or(c)
+}
+
+fn double_struck(c: char) -> char {
+    match c {
+        'C' => 'ℂ',
+        'H' => 'ℍ',
+        'N' => 'ℕ',
+        'P' => 'ℙ',
+        'Q' => 'ℚ',
+        'R' => 'ℝ',
+        'Z' => 'ℤ',
+        'A'..='Z' => offset(c, 0x1d538),
+        'a'..='z' => char::from_u32(0x1d552 + (c as u32 - 'a' as u32)).unwrap_or(c),
+        '0'..='9' => char::from_u32(0x1d7d8 + (c as u32 - '0' as u32)).unwrap_or(c),
+        c => c,
+    }
+}
+
+fn script(c: char) -> char {
+    match c {
+        'B' => 'ℬ',
+        'E' => 'ℰ',
+        'F' => 'ℱ',
+        'H' => 'ℋ',
+        'I' => 'ℐ',
+        'L' => 'ℒ',
+        'M' => 'ℳ',
+        'R' => 'ℛ',
+        'e' => 'ℯ',
+        'g' => 'ℊ',
+        'o' => 'ℴ',
+        'A'..='Z' => offset(c, 0x1d49c),
+        'a'..='z' => char::from_u32(0x1d4b6 + (c as u32 - 'a' as u32)).unwrap_or(c),
+        c => c,
+    }
+}
+
+fn fraktur(c: char) -> char {
+    match c {
+        'C' => 'ℭ',
+        'H' => 'ℌ',
+        'I' => 'ℑ',
+        'R' => 'ℜ',
+        'Z' => 'ℨ',
+        'A'..='Z' => offset(c, 0x1d504),
+        'a'..='z' => char::from_u32(0x1d51e + (c as u32 - 'a' as u32)).unwrap_or(c),
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_symbols_and_scripts() {
+        assert_eq!(latex_to_unicode(r"\alpha + \beta^2"), "α + β²");
+        assert_eq!(latex_to_unicode(r"\sum_{i=1}^{n} x_i"), "∑ᵢ₌₁ⁿ xᵢ");
+        assert_eq!(latex_to_unicode(r"x^{q}"), "x^q");
+        assert_eq!(latex_to_unicode(r"\mathbb{R}^{n \times m}"), "ℝ^(n × m)");
+        assert_eq!(latex_to_unicode(r"a \leq b \to \infty"), "a ≤ b → ∞");
+        assert_eq!(latex_to_unicode(r"\foo + 1"), r"\foo + 1");
+    }
+
+    #[test]
+    fn inline_fractions_roots_and_matrices() {
+        assert_eq!(latex_to_unicode(r"\frac{1}{2}"), "¹⁄₂");
+        assert_eq!(latex_to_unicode(r"\frac{a+b}{c}"), "(a+b)/c");
+        assert_eq!(latex_to_unicode(r"\sqrt{x^2 + 1}"), "√(x² + 1)");
+        assert_eq!(latex_to_unicode(r"\sqrt[3]{x}"), "∛x");
+        assert_eq!(
+            latex_to_unicode(r"\begin{bmatrix} a & b \\ c & d \end{bmatrix}"),
+            "[a b; c d]"
+        );
+    }
+
+    #[test]
+    fn display_fraction_is_stacked() {
+        assert_eq!(
+            latex_to_lines(r"x = \frac{a+b}{2}"),
+            vec!["     a+b ", "x = ─────", "      2  "]
+        );
+    }
+
+    #[test]
+    fn display_matrix_and_limits() {
+        let lines = latex_to_lines(r"\begin{pmatrix} 1 & 0 \\ 0 & 1 \end{pmatrix}");
+        assert_eq!(lines, vec!["⎛ 1  0 ⎞", "⎝ 0  1 ⎠"]);
+
+        let lines =
+            latex_to_lines(r"|x| = \begin{cases} x & x > 0 \\ 0 & x = 0 \\ -x & x < 0 \end{cases}");
+        assert_eq!(
+            lines,
+            vec![
+                "      ⎧ x   x > 0",
+                "|x| = ⎨ 0   x = 0",
+                "      ⎩ −x  x < 0"
+            ]
+        );
+
+        let lines = latex_to_lines(r"\sum_{i=1}^{n} i");
+        assert_eq!(lines, vec![" n   ", " ∑  i", "i=1  "]);
+    }
+}
@@ -6,7 +6,7 @@ use ratatui_image::{picker::Picker, protocol::StatefulProtocol};
 use super::{root::ComponentProps, textcomponent::TextNode};
 
 pub struct ImageComponent {
-    _alt_text: String,
+    alt_text: String,
     y_offset: u16,
     height: u16,
     scroll_offset: u16,
@@ -26,12 +26,17 @@ impl ImageComponent {
         Some(Self {
             height,
             image,
-            _alt_text: alt_text.to_string(),
+            alt_text: alt_text.to_string(),
             scroll_offset: 0,
             y_offset: 0,
         })
     }
 
+    #[must_use]
+    pub fn alt_text(&self) -> &str {
+        &self.alt_text
+    }
+
     pub fn image_mut(&mut self) -> &mut StatefulProtocol {
         &mut self.image
     }
@@ -1,4 +1,5 @@
 pub mod image;
 pub mod root;
+pub mod table;
 pub mod textcomponent;
 pub mod word;
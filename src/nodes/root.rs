@@ -1,17 +1,20 @@
 use std::collections::HashSet;
 
-use crate::search::{compare_heading, find_and_mark};
+use crate::search::{compare_heading, find_and_mark, heading_anchor};
 
 use super::{
     image::ImageComponent,
+    table::TableView,
     textcomponent::{TextComponent, TextNode},
-    word::{Word, WordType},
+    word::{MetaData, Word, WordType},
 };
 
 pub struct ComponentRoot {
     file_name: Option<String>,
     components: Vec<Component>,
     is_focused: bool,
+    /// The last query marked by `find_and_mark`.
+    search: Option<String>,
 }
 
 impl ComponentRoot {
@@ -21,6 +24,7 @@ impl ComponentRoot {
             file_name,
             components,
             is_focused: false,
+            search: None,
         }
     }
 
@@ -72,6 +76,7 @@ impl ComponentRoot {
     }
 
     pub fn find_and_mark(&mut self, search: &str) {
+        self.search = Some(search.to_owned());
         let mut words = self
             .components
             .iter_mut()
@@ -84,6 +89,13 @@ impl ComponentRoot {
         find_and_mark(search, &mut words);
     }
 
+    /// The query of the search results marked, to mark them again after
+    /// the file is parsed anew.
+    #[must_use]
+    pub fn search(&self) -> Option<&str> {
+        self.search.as_deref()
+    }
+
     #[must_use]
     pub fn search_results_heights(&self) -> Vec<usize> {
         self.components
@@ -103,6 +115,7 @@ impl ComponentRoot {
     pub fn clear(&mut self) {
         self.file_name = None;
         self.components.clear();
+        self.search = None;
     }
 
     pub fn select(&mut self, index: usize) -> Result<u16, String> {
@@ -184,7 +197,10 @@ impl ComponentRoot {
                     row.iter().for_each(|c| {
                         if matches!(
                             c.kind(),
-                            WordType::Link | WordType::Selected | WordType::FootnoteInline
+                            WordType::Link
+                                | WordType::Selected
+                                | WordType::FootnoteInline
+                                | WordType::Abbreviation
                         ) {
                             indexes.push((count, height + index as u16));
                             count += 1;
@@ -211,9 +227,7 @@ impl ComponentRoot {
         for component in &self.components {
             match component {
                 Component::TextComponent(comp) => {
-                    if comp.kind() == TextNode::Heading
-                        && compare_heading(&heading[1..], comp.content())
-                    {
+                    if comp.kind() == TextNode::Heading && compare_heading(&heading[1..], comp) {
                         return Ok(y_offset);
                     }
                     y_offset += comp.height();
@@ -224,6 +238,67 @@ impl ComponentRoot {
         Err(format!("Heading not found: {heading}"))
     }
 
+    /// Returns `(level, text, y_offset)` for each visible heading, in
+    /// document order. Offsets are computed the same way as in
+    /// `heading_offset`, so jumping to one lands on the heading itself.
+    #[must_use]
+    pub fn headings(&self) -> Vec<(u8, String, u16)> {
+        let mut headings = Vec::new();
+        let mut y_offset = 0;
+        for component in &self.components {
+            match component {
+                Component::TextComponent(comp) => {
+                    if comp.kind() == TextNode::Heading && !comp.is_hidden() {
+                        let level = comp
+                            .meta_info()
+                            .iter()
+                            .find_map(|w| match w.kind() {
+                                WordType::MetaInfo(MetaData::HeadingLevel(level)) => Some(level),
+                                _ => None,
+                            })
+                            .unwrap_or(1);
+                        let text = comp
+                            .content()
+                            .iter()
+                            .flatten()
+                            .map(Word::content)
+                            .collect::<String>()
+                            .trim_start_matches('#')
+                            .trim()
+                            .to_owned();
+                        headings.push((level, text, y_offset));
+                    }
+                    y_offset += comp.height();
+                }
+                Component::Image(e) => y_offset += e.height(),
+            }
+        }
+        headings
+    }
+
+    /// Anchor of the last heading at or above `scroll`, suitable for
+    /// `heading_offset` once prefixed with `#`.
+    #[must_use]
+    pub fn heading_anchor(&self, scroll: u16) -> Option<String> {
+        let mut anchor = None;
+        let mut y_offset = 0;
+        for component in &self.components {
+            if y_offset > scroll {
+                break;
+            }
+            match component {
+                Component::TextComponent(comp) => {
+                    if comp.kind() == TextNode::Heading && !comp.is_hidden() {
+                        anchor = Some(heading_anchor(comp));
+                    }
+                    y_offset += comp.height();
+                }
+                Component::Image(e) => y_offset += e.height(),
+            }
+        }
+        anchor
+    }
+
     /// Return the content of the components, where each element a line
     #[must_use]
     pub fn content(&self) -> Vec<String> {
@@ -289,6 +364,7 @@ impl ComponentRoot {
             if let Some(next) = iter.peek()
                 && kind != TextNode::LineBreak
                 && next.kind() != TextNode::LineBreak
+                && !matches!(next, Component::TextComponent(tc) if tc.is_list_continuation())
             {
                 let next_ids: Vec<u32> = match next {
                     Component::TextComponent(tc) => tc.owning_details_ids().to_vec(),
@@ -313,6 +389,7 @@ impl ComponentRoot {
             file_name: self.file_name,
             components,
             is_focused: self.is_focused,
+            search: self.search,
         }
     }
 
@@ -333,6 +410,34 @@ impl ComponentRoot {
             .sum()
     }
 
+    /// What is drawn at document row `y` and `column` of the markdown area,
+    /// which is `width` wide. Uses the offsets from the last `set_scroll`.
+    #[must_use]
+    pub fn click_target(&self, y: u16, column: u16, width: u16) -> Option<ClickTarget> {
+        let mut links = 0;
+        let mut details = 0;
+        for comp in self.components.iter().filter_map(|f| match f {
+            Component::TextComponent(comp) => Some(comp),
+            Component::Image(_) => None,
+        }) {
+            if comp.is_hidden() {
+                continue;
+            }
+            let summary = matches!(comp.kind(), TextNode::DetailsSummary { .. });
+            if (comp.y_offset()..comp.y_offset() + comp.height()).contains(&y) {
+                return if summary {
+                    Some(ClickTarget::Details(details))
+                } else {
+                    comp.link_at(y - comp.y_offset(), column, width)
+                        .map(|link| ClickTarget::Link(links + link))
+                };
+            }
+            links += comp.num_links();
+            details += usize::from(summary);
+        }
+        None
+    }
+
     /// Walk all components and set their `hidden` flag based on whether
     /// any of their `owning_details_ids` references a currently-folded
     /// `<details>` block. Must be called after parse and after every
@@ -349,7 +454,8 @@ impl ComponentRoot {
             .filter_map(|tc| match tc.kind() {
                 TextNode::DetailsSummary {
                     id, folded: true, ..
-                } => Some(id),
+                }
+                | TextNode::FrontMatter { id, folded: true } => Some(id),
                 _ => None,
             })
             .collect();
@@ -430,6 +536,47 @@ impl ComponentRoot {
         }
     }
 
+    /// Fold state of the front matter and every `<details>` block, in
+    /// document order.
+    #[must_use]
+    pub fn details_folds(&self) -> Vec<bool> {
+        self.components
+            .iter()
+            .filter_map(|c| match c {
+                Component::TextComponent(comp) => match comp.kind() {
+                    TextNode::DetailsSummary { folded, .. }
+                    | TextNode::FrontMatter { folded, .. } => Some(folded),
+                    _ => None,
+                },
+                Component::Image(_) => None,
+            })
+            .collect()
+    }
+
+    /// Restore fold state taken from `details_folds`. Nothing happens if
+    /// the number of `<details>` blocks differs, since the document has
+    /// changed too much for the state to still apply.
+    pub fn set_details_folds(&mut self, folds: &[bool]) {
+        if folds.len() != self.details_folds().len() {
+            return;
+        }
+        let summaries = self.components.iter_mut().filter_map(|c| match c {
+            Component::TextComponent(comp)
+                if matches!(
+                    comp.kind(),
+                    TextNode::DetailsSummary { .. } | TextNode::FrontMatter { .. }
+                ) =>
+            {
+                Some(comp)
+            }
+            _ => None,
+        });
+        for (comp, folded) in summaries.zip(folds) {
+            comp.set_details_folded(*folded);
+        }
+        self.recompute_visibility();
+    }
+
     /// Flip the `folded` flag on the currently-focused details summary
     /// and recompute visibility. Returns `Err` if no details summary is
     /// focused.
@@ -451,6 +598,269 @@ impl ComponentRoot {
         self.recompute_visibility();
         Ok(())
     }
+
+    /// Show or hide the raw front matter under its header. Returns `Err` if
+    /// the document has none.
+    pub fn toggle_front_matter(&mut self) -> Result<(), String> {
+        let header = self.components.iter_mut().find_map(|c| match c {
+            Component::TextComponent(comp)
+                if matches!(comp.kind(), TextNode::FrontMatter { .. }) =>
+            {
+                Some(comp)
+            }
+            _ => None,
+        });
+        let Some(header) = header else {
+            return Err("No front matter in this file".to_string());
+        };
+        if let TextNode::FrontMatter { folded, .. } = header.kind() {
+            header.set_details_folded(!folded);
+        }
+        self.recompute_visibility();
+        Ok(())
+    }
+
+    /// First source line, counted from one, of the selected link, task or
+    /// details block, or else of the topmost component shown at `scroll`.
+    #[must_use]
+    pub fn source_line(&self, scroll: u16) -> Option<usize> {
+        let components = self.components();
+        let visible = components
+            .iter()
+            .filter(|comp| !comp.is_hidden() && !comp.source().lines.is_empty());
+        visible
+            .clone()
+            .find(|comp| comp.is_focused() || comp.is_task_selected())
+            .or_else(|| {
+                visible
+                    .clone()
+                    .find(|comp| comp.y_offset() + comp.height() > scroll)
+            })
+            .map(|comp| comp.source().first_line())
+    }
+
+    /// Where the component holding source line `line`, counted from one,
+    /// is drawn. Falls back to the first one after it, for lines that are
+    /// not part of any component.
+    #[must_use]
+    pub fn source_line_offset(&self, line: usize) -> Option<u16> {
+        self.components()
+            .into_iter()
+            .filter(|comp| !comp.is_hidden() && !comp.source().lines.is_empty())
+            .find(|comp| comp.source().lines.end >= line)
+            .map(TextComponent::y_offset)
+    }
+
+    /// Returns `(index, y_offset)` for each visible task, in document
+    /// order, like `details_index_and_height`.
+    #[must_use]
+    pub fn task_index_and_height(&self) -> Vec<(usize, u16)> {
+        self.components()
+            .into_iter()
+            .filter(|comp| !comp.is_hidden() && comp.kind() == TextNode::Task)
+            .enumerate()
+            .map(|(i, comp)| (i, comp.y_offset()))
+            .collect()
+    }
+
+    /// Select the `index`-th visible task, returning its `y_offset`.
+    pub fn select_task(&mut self, index: usize) -> Result<u16, String> {
+        self.deselect_tasks();
+        let tasks = self
+            .components_mut()
+            .into_iter()
+            .filter(|comp| !comp.is_hidden() && comp.kind() == TextNode::Task);
+        let mut count = 0;
+        for (i, comp) in tasks.enumerate() {
+            if i == index {
+                comp.set_task_selected(true);
+                return Ok(comp.y_offset());
+            }
+            count += 1;
+        }
+        Err(format!("Task index out of bounds: {index} >= {count}"))
+    }
+
+    pub fn deselect_tasks(&mut self) {
+        for comp in self.components_mut() {
+            comp.set_task_selected(false);
+        }
+    }
+
+    /// Source offset of the selected task's checkbox, see
+    /// `TextComponent::task_marker`.
+    #[must_use]
+    pub fn selected_task_marker(&self) -> Option<usize> {
+        self.components()
+            .into_iter()
+            .find(|comp| comp.is_task_selected())
+            .and_then(TextComponent::task_marker)
+    }
+
+    /// Returns `(index, y_offset)` for each visible table or code block
+    /// wider than the view, like `task_index_and_height`.
+    #[must_use]
+    pub fn wide_index_and_height(&self) -> Vec<(usize, u16)> {
+        self.components()
+            .into_iter()
+            .filter(|comp| !comp.is_hidden() && comp.is_wide())
+            .enumerate()
+            .map(|(i, comp)| (i, comp.y_offset()))
+            .collect()
+    }
+
+    /// Focus the `index`-th visible wide component for horizontal scrolling,
+    /// laying it out at its natural width, and return its `y_offset`.
+    pub fn select_wide(&mut self, index: usize, width: u16) -> Result<u16, String> {
+        self.deselect_wide(width);
+        let count = self.wide_index_and_height().len();
+        let comp = self
+            .components_mut()
+            .into_iter()
+            .filter(|comp| !comp.is_hidden() && comp.is_wide())
+            .nth(index)
+            .ok_or(format!(
+                "Wide block index out of bounds: {index} >= {count}"
+            ))?;
+        comp.set_wide_scroll(Some(0), width);
+        self.update_y_offsets();
+        Ok(self
+            .components()
+            .into_iter()
+            .find(|comp| comp.wide_scroll().is_some())
+            .map_or(0, TextComponent::y_offset))
+    }
+
+    /// Unfocus the wide component, fitting it to `width` again.
+    pub fn deselect_wide(&mut self, width: u16) {
+        for comp in self.components_mut() {
+            comp.set_wide_scroll(None, width);
+        }
+        self.update_y_offsets();
+    }
+
+    /// Scroll the focused wide component right by `columns`, or left when
+    /// negative, as far as its edges allow in a view `width` wide.
+    pub fn scroll_wide(&mut self, columns: i32, width: u16) -> Result<(), String> {
+        let comp = self
+            .components_mut()
+            .into_iter()
+            .find(|comp| comp.wide_scroll().is_some())
+            .ok_or("No table or code block is focused")?;
+        let max = comp
+            .natural_width()
+            .saturating_sub(width.saturating_sub(comp.indent()));
+        let scroll = i32::from(comp.wide_scroll().unwrap_or_default()) + columns;
+        let scroll = u16::try_from(scroll.max(0)).unwrap_or(u16::MAX).min(max);
+        comp.set_wide_scroll(Some(scroll), width);
+        Ok(())
+    }
+
+    /// Returns `(index, y_offset)` for each visible table, like
+    /// `task_index_and_height`.
+    #[must_use]
+    pub fn table_index_and_height(&self) -> Vec<(usize, u16)> {
+        self.components()
+            .into_iter()
+            .filter(|comp| !comp.is_hidden() && is_table(comp))
+            .enumerate()
+            .map(|(i, comp)| (i, comp.y_offset()))
+            .collect()
+    }
+
+    /// Focus the `index`-th visible table in table-select mode and return
+    /// the line of its cursor row.
+    pub fn select_table(&mut self, index: usize, width: u16) -> Result<u16, String> {
+        self.deselect_table(width);
+        let count = self.table_index_and_height().len();
+        let comp = self
+            .components_mut()
+            .into_iter()
+            .filter(|comp| !comp.is_hidden() && is_table(comp))
+            .nth(index)
+            .ok_or(format!("Table index out of bounds: {index} >= {count}"))?;
+        comp.set_table_selected(true, width);
+        self.update_y_offsets();
+        self.table_cursor_line()
+    }
+
+    /// Unfocus the focused table, showing its rows as written again.
+    pub fn deselect_table(&mut self, width: u16) {
+        for comp in self.components_mut() {
+            comp.set_table_selected(false, width);
+        }
+        self.update_y_offsets();
+    }
+
+    /// Change the focused table with `update` and return the line of its
+    /// cursor row, which moves when rows are sorted or filtered.
+    pub fn update_table(
+        &mut self,
+        width: u16,
+        update: impl FnOnce(&mut TableView),
+    ) -> Result<u16, String> {
+        let comp = self
+            .components_mut()
+            .into_iter()
+            .find(|comp| comp.table_view().is_some())
+            .ok_or("No table is selected")?;
+        comp.update_table_view(width, update);
+        self.update_y_offsets();
+        self.table_cursor_line()
+    }
+
+    /// The focused table, in table-select mode.
+    #[must_use]
+    pub fn selected_table(&self) -> Option<&TableView> {
+        self.components()
+            .into_iter()
+            .find_map(TextComponent::table_view)
+    }
+
+    /// The code block or table to yank: the selected table, else the
+    /// focused wide block, else the first one on the `height` lines shown
+    /// from `top`.
+    #[must_use]
+    pub fn export_target(&self, top: u16, height: u16) -> Option<&TextComponent> {
+        let components = self.components();
+        components
+            .iter()
+            .find(|comp| comp.table_view().is_some())
+            .or_else(|| components.iter().find(|comp| comp.wide_scroll().is_some()))
+            .or_else(|| {
+                components.iter().find(|comp| {
+                    !comp.is_hidden()
+                        && (comp.kind() == TextNode::CodeBlock || is_table(comp))
+                        && comp.y_offset() + comp.height() > top
+                        && comp.y_offset() < top.saturating_add(height)
+                })
+            })
+            .copied()
+    }
+
+    fn table_cursor_line(&self) -> Result<u16, String> {
+        self.components()
+            .into_iter()
+            .find_map(|comp| Some(comp.y_offset() + comp.table_cursor_offset()?))
+            .ok_or("No table is selected".to_owned())
+    }
+
+    /// Place the components below each other again after one changed height.
+    fn update_y_offsets(&mut self) {
+        let mut y_offset = 0;
+        for component in &mut self.components {
+            component.set_y_offset(y_offset);
+            y_offset += component.height();
+        }
+    }
+}
+
+/// Something in the document that reacts to a mouse click, by the index
+/// `select` or `select_details` takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickTarget {
+    Link(usize),
+    Details(usize),
 }
 
 pub trait ComponentProps {
@@ -500,3 +910,66 @@ impl ComponentProps for Component {
         }
     }
 }
+
+fn is_table(comp: &TextComponent) -> bool {
+    matches!(comp.kind(), TextNode::Table(widths, _, _) if !widths.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{pages::printer::render_to_ansi, parser::parse_markdown};
+
+    #[test]
+    fn focused_wide_table_scrolls_sideways() {
+        let md = "| Feature | Alpha implementation | Beta implementation |\n|---|---|---|\n| Speed | fast | slow |\n";
+        let mut root = parse_markdown(None, md, 30);
+        assert_eq!(root.wide_index_and_height(), [(0, 0)]);
+        let squeezed = render_to_ansi(&mut root, 30);
+        assert!(!squeezed.contains('\u{25b6}'), "got {squeezed:?}");
+
+        root.select_wide(0, 30).unwrap();
+        let start = render_to_ansi(&mut root, 30);
+        assert!(start.contains("Feature"), "got {start:?}");
+        assert!(start.contains("2 \u{25b6}"), "got {start:?}");
+        assert!(!start.contains('\u{25c0}'), "got {start:?}");
+
+        // Scrolling stops at the right edge of the table
+        root.scroll_wide(1000, 30).unwrap();
+        let end = render_to_ansi(&mut root, 30);
+        assert!(end.contains("\u{25c0}"), "got {end:?}");
+        assert!(end.contains("Beta implementation"), "got {end:?}");
+        assert!(!end.contains('\u{25b6}'), "got {end:?}");
+
+        root.deselect_wide(30);
+        assert_eq!(render_to_ansi(&mut root, 30), squeezed);
+        assert!(root.scroll_wide(8, 30).is_err());
+    }
+
+    #[test]
+    fn selected_table_is_sorted_and_filtered_until_deselected() {
+        let md = "| Item | Qty |\n|---|---:|\n| Widget | 12 |\n| Gadget | 1,200 |\n| Bolt | 7 |\n";
+        let mut root = parse_markdown(None, md, 40);
+        let written = render_to_ansi(&mut root, 40);
+        assert_eq!(root.table_index_and_height(), [(0, 0)]);
+        // Below the top border, the header and the line under it
+        assert_eq!(root.select_table(0, 40), Ok(3));
+
+        let line = root.update_table(40, |view| {
+            view.move_cursor(0, 1);
+            view.cycle_sort();
+            view.set_filter("DG");
+        });
+        assert_eq!(line, Ok(3));
+        let table = root.selected_table().unwrap();
+        assert_eq!(table.cell_text().as_deref(), Some("12"));
+        assert_eq!(table.row_counts(), (2, 3));
+
+        let output = render_to_ansi(&mut root, 40);
+        assert!(output.contains("Qty \u{25b2}"), "got {output:?}");
+        assert!(output.contains("/DG 2 of 3"), "got {output:?}");
+        assert!(!output.contains("Bolt"), "got {output:?}");
+
+        root.deselect_table(40);
+        assert_eq!(render_to_ansi(&mut root, 40), written);
+    }
+}
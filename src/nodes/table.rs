@@ -0,0 +1,285 @@
+//! A table focused in table-select mode: a cursor on one of its cells, and
+//! the order and filter its rows are shown in. The rows are kept as parsed,
+//! so the table can be shown as written again when it loses focus.
+
+use std::cmp::Ordering;
+
+use itertools::Itertools;
+
+use super::word::{Word, WordType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Clone)]
+pub struct TableView {
+    header: Vec<Vec<Word>>,
+    rows: Vec<Vec<Vec<Word>>>,
+    /// Indexes into `rows` of the rows shown, in the order they are shown.
+    shown: Vec<usize>,
+    row: usize,
+    column: usize,
+    sort: Option<(usize, SortOrder)>,
+    filter: String,
+}
+
+impl TableView {
+    /// The view of a table with `content` as its cells, header first.
+    #[must_use]
+    pub fn new(content: &[Vec<Word>], column_count: usize) -> Self {
+        let mut rows = content
+            .chunks(column_count.max(1))
+            .map(<[Vec<Word>]>::to_vec);
+        let header = rows.next().unwrap_or_default();
+        let rows = rows.collect_vec();
+        Self {
+            shown: (0..rows.len()).collect(),
+            header,
+            rows,
+            row: 0,
+            column: 0,
+            sort: None,
+            filter: String::new(),
+        }
+    }
+
+    /// The cell under the cursor, by its row among the rows shown, below
+    /// the header, and its column.
+    #[must_use]
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.column)
+    }
+
+    #[must_use]
+    pub fn sort(&self) -> Option<(usize, SortOrder)> {
+        self.sort
+    }
+
+    #[must_use]
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// The rows shown and the rows in the table.
+    #[must_use]
+    pub fn row_counts(&self) -> (usize, usize) {
+        (self.shown.len(), self.rows.len())
+    }
+
+    /// The cells as shown, header first, with a mark on the sorted column.
+    #[must_use]
+    pub fn content(&self) -> Vec<Vec<Word>> {
+        let mut content = self.shown_content();
+        if let Some((column, order)) = self.sort
+            && let Some(cell) = content.get_mut(column)
+        {
+            let mark = match order {
+                SortOrder::Ascending => " \u{25b2}",
+                SortOrder::Descending => " \u{25bc}",
+            };
+            cell.push(Word::new(mark.to_owned(), WordType::Normal));
+        }
+        content
+    }
+
+    /// The cells as shown, header first.
+    #[must_use]
+    pub fn shown_content(&self) -> Vec<Vec<Word>> {
+        self.header
+            .iter()
+            .chain(self.shown.iter().flat_map(|i| self.rows[*i].iter()))
+            .cloned()
+            .collect()
+    }
+
+    /// The cells in the order they were written.
+    #[must_use]
+    pub fn original_content(&self) -> Vec<Vec<Word>> {
+        self.header
+            .iter()
+            .chain(self.rows.iter().flatten())
+            .cloned()
+            .collect()
+    }
+
+    /// The text of the cell under the cursor, if any row is shown.
+    #[must_use]
+    pub fn cell_text(&self) -> Option<String> {
+        let row = &self.rows[*self.shown.get(self.row)?];
+        row.get(self.column).map(|cell| cell_text(cell))
+    }
+
+    /// Move the cursor by `rows` and `columns`, stopping at the edges.
+    pub fn move_cursor(&mut self, rows: isize, columns: isize) {
+        self.row = self
+            .row
+            .saturating_add_signed(rows)
+            .min(self.shown.len().saturating_sub(1));
+        self.column = self
+            .column
+            .saturating_add_signed(columns)
+            .min(self.header.len().saturating_sub(1));
+    }
+
+    /// Sort by the column under the cursor: ascending, then descending,
+    /// then back in the order the rows were written.
+    pub fn cycle_sort(&mut self) {
+        self.sort = match self.sort {
+            Some((column, SortOrder::Ascending)) if column == self.column => {
+                Some((column, SortOrder::Descending))
+            }
+            Some((column, SortOrder::Descending)) if column == self.column => None,
+            _ => Some((self.column, SortOrder::Ascending)),
+        };
+        self.update();
+    }
+
+    /// Show only the rows with a cell containing `query`, ignoring case.
+    pub fn set_filter(&mut self, query: &str) {
+        query.clone_into(&mut self.filter);
+        self.update();
+    }
+
+    fn update(&mut self) {
+        let query = self.filter.to_lowercase();
+        let texts = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|cell| cell_text(cell)).collect_vec())
+            .collect_vec();
+
+        self.shown = (0..self.rows.len())
+            .filter(|i| {
+                query.is_empty()
+                    || texts[*i]
+                        .iter()
+                        .any(|text| text.to_lowercase().contains(&query))
+            })
+            .collect();
+
+        if let Some((column, order)) = self.sort {
+            let text = |i: &usize| texts[*i].get(column).map_or("", String::as_str);
+            // Stable, so equal cells stay in the order they were written
+            self.shown
+                .sort_by(|a, b| compare_sorted(text(a), text(b), order));
+        }
+
+        self.move_cursor(0, 0);
+    }
+}
+
+fn cell_text(cell: &[Word]) -> String {
+    cell.iter().map(Word::content).join("").trim().to_owned()
+}
+
+/// Empty cells go last in either order.
+fn compare_sorted(a: &str, b: &str, order: SortOrder) -> Ordering {
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) if order == SortOrder::Descending => compare_cells(a, b).reverse(),
+        (false, false) => compare_cells(a, b),
+    }
+}
+
+/// Cells starting with a number, such as `1,024`, `3.5 ms` or `$12`, by
+/// its value and before the others, which are compared ignoring case.
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (leading_number(a), leading_number(b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y).then_with(|| a.cmp(b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a
+            .to_lowercase()
+            .cmp(&b.to_lowercase())
+            .then_with(|| a.cmp(b)),
+    }
+}
+
+/// The number `text` starts with, after any currency sign. Separators
+/// between thousands are skipped and units after the number ignored.
+fn leading_number(text: &str) -> Option<f64> {
+    let text = text.trim_start_matches(['$', '€', '£', '¥']);
+    let mut number = String::new();
+    for (i, c) in text.char_indices() {
+        match c {
+            '-' | '+' if i == 0 => number.push(c),
+            '0'..='9' | '.' => number.push(c),
+            ',' | '_' if !number.is_empty() => {}
+            _ => break,
+        }
+    }
+    number.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(rows: &[&[&str]]) -> TableView {
+        let content = rows
+            .iter()
+            .flat_map(|row| row.iter())
+            .map(|cell| vec![Word::new((*cell).to_owned(), WordType::Normal)])
+            .collect_vec();
+        TableView::new(&content, rows[0].len())
+    }
+
+    fn column(view: &TableView, column: usize) -> Vec<String> {
+        let columns = view.header.len();
+        view.content()
+            .chunks(columns)
+            .skip(1)
+            .map(|row| cell_text(&row[column]))
+            .collect()
+    }
+
+    #[test]
+    fn sorts_numbers_by_value_and_cycles_back() {
+        let mut view = view(&[
+            &["Item", "Size"],
+            &["b", "1,024 KB"],
+            &["a", "9.5 KB"],
+            &["c", ""],
+            &["d", "n/a"],
+            &["e", "-3"],
+        ]);
+        view.move_cursor(0, 1);
+
+        view.cycle_sort();
+        assert_eq!(column(&view, 1), ["-3", "9.5 KB", "1,024 KB", "n/a", ""]);
+        assert_eq!(view.sort(), Some((1, SortOrder::Ascending)));
+        assert_eq!(cell_text(&view.content()[1]), "Size \u{25b2}");
+
+        view.cycle_sort();
+        assert_eq!(column(&view, 1), ["n/a", "1,024 KB", "9.5 KB", "-3", ""]);
+
+        view.cycle_sort();
+        assert_eq!(view.sort(), None);
+        assert_eq!(view.content(), view.original_content());
+    }
+
+    #[test]
+    fn filters_rows_and_keeps_the_cursor_on_them() {
+        let mut view = view(&[
+            &["Name", "Owner"],
+            &["Alpha", "ops"],
+            &["Beta", "dev"],
+            &["Gamma", "Ops team"],
+        ]);
+        view.move_cursor(2, 0);
+        assert_eq!(view.cell_text().as_deref(), Some("Gamma"));
+
+        view.set_filter("OPS");
+        assert_eq!(view.row_counts(), (2, 3));
+        assert_eq!(column(&view, 0), ["Alpha", "Gamma"]);
+        assert_eq!(view.cursor(), (1, 0));
+
+        view.set_filter("nothing");
+        assert_eq!(view.cell_text(), None);
+    }
+}
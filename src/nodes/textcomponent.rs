@@ -1,19 +1,21 @@
-use std::cmp;
-
 use itertools::Itertools;
 use mermaid_text::render_with_width;
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use ratatui::style::Color;
+use ratatui::{layout::Alignment, style::Color};
 use tree_sitter_highlight::HighlightEvent;
 
 use crate::{
     highlight::{COLOR_MAP, HighlightInfo, highlight_code},
     nodes::word::MetaData,
+    parser::SourceSpan,
     util::general::GENERAL_CONFIG,
 };
 
-use super::word::{Word, WordType};
+use super::{
+    table::TableView,
+    word::{Word, WordType},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TextNode {
@@ -24,16 +26,27 @@ pub enum TextNode {
     Task,
     List,
     Footnote,
-    /// (`widths_by_column`, `heights_by_row`)
-    Table(Vec<u16>, Vec<u16>),
+    /// (`widths_by_column`, `heights_by_row`, `alignments_by_column`)
+    Table(Vec<u16>, Vec<u16>, Vec<Alignment>),
     CodeBlock,
+    /// Display math, one pre-rendered line per row, centered as a whole.
+    MathBlock,
     Quote,
     HorizontalSeparator,
+    /// The term of a definition list, with its definitions right below it.
+    DefinitionTerm,
+    Definition,
     DetailsSummary {
         id: u32,
         folded: bool,
         body_len: usize,
     },
+    /// The chosen fields of the front matter. Its raw block is folded
+    /// away like the body of a `<details>` with the same `id`.
+    FrontMatter {
+        id: u32,
+        folded: bool,
+    },
 }
 
 pub(crate) const TABLE_CELL_PADDING: u16 = 1;
@@ -50,6 +63,14 @@ pub struct TextComponent {
     focused_index: usize,
     owning_details_ids: Vec<u32>,
     hidden: bool,
+    task_marker: Option<usize>,
+    task_selected: bool,
+    source: SourceSpan,
+    indent: u16,
+    list_continuation: bool,
+    wide: bool,
+    wide_scroll: Option<u16>,
+    table_view: Option<Box<TableView>>,
 }
 
 impl TextComponent {
@@ -74,6 +95,14 @@ impl TextComponent {
             focused_index: 0,
             owning_details_ids: Vec::new(),
             hidden: false,
+            task_marker: None,
+            task_selected: false,
+            source: SourceSpan::default(),
+            indent: 0,
+            list_continuation: false,
+            wide: false,
+            wide_scroll: None,
+            table_view: None,
         }
     }
 
@@ -112,6 +141,14 @@ impl TextComponent {
             focused_index: 0,
             owning_details_ids: Vec::new(),
             hidden: false,
+            task_marker: None,
+            task_selected: false,
+            source: SourceSpan::default(),
+            indent: 0,
+            list_continuation: false,
+            wide: false,
+            wide_scroll: None,
+            table_view: None,
         }
     }
 
@@ -127,7 +164,7 @@ impl TextComponent {
 
     #[must_use]
     pub fn content_as_lines(&self) -> Vec<String> {
-        if let TextNode::Table(widths, _) = self.kind() {
+        if let TextNode::Table(widths, _, _) = self.kind() {
             let column_count = widths.len();
 
             let moved_content = self.content.chunks(column_count).collect::<Vec<_>>();
@@ -205,25 +242,28 @@ impl TextComponent {
         self.hidden = hidden;
     }
 
-    /// If this component is a `DetailsSummary`, set its `folded` field.
-    /// Returns the new folded state on success, `None` if the component
-    /// is not a `DetailsSummary`.
+    /// If this component is a `DetailsSummary` or `FrontMatter`, set its
+    /// `folded` field. Returns the new folded state on success, `None` if
+    /// the component is neither.
     pub fn set_details_folded(&mut self, folded: bool) -> Option<bool> {
-        if let TextNode::DetailsSummary {
-            id,
-            folded: _,
-            body_len,
-        } = self.kind.clone()
-        {
-            self.kind = TextNode::DetailsSummary {
+        match self.kind.clone() {
+            TextNode::DetailsSummary {
                 id,
-                folded,
+                folded: _,
                 body_len,
-            };
-            Some(folded)
-        } else {
-            None
+            } => {
+                self.kind = TextNode::DetailsSummary {
+                    id,
+                    folded,
+                    body_len,
+                };
+            }
+            TextNode::FrontMatter { id, folded: _ } => {
+                self.kind = TextNode::FrontMatter { id, folded };
+            }
+            _ => return None,
         }
+        Some(folded)
     }
 
     #[must_use]
@@ -269,6 +309,206 @@ impl TextComponent {
         self.focused = true;
     }
 
+    /// Where in the source the component was parsed from. Empty for
+    /// components that have no source of their own, like the line breaks
+    /// added between blocks.
+    #[must_use]
+    pub fn source(&self) -> &SourceSpan {
+        &self.source
+    }
+
+    pub fn set_source(&mut self, source: SourceSpan) {
+        self.source = source;
+    }
+
+    /// Columns the component is drawn to the right of the left edge, for
+    /// blocks nested in list items.
+    #[must_use]
+    pub fn indent(&self) -> u16 {
+        self.indent
+    }
+
+    pub fn set_indent(&mut self, indent: u16) {
+        self.indent = indent;
+    }
+
+    /// The id a heading was given with `{#id}`, used as its anchor instead of
+    /// the one made from its text.
+    #[must_use]
+    pub fn heading_id(&self) -> Option<&str> {
+        self.meta_info
+            .iter()
+            .find(|w| w.kind() == WordType::MetaInfo(MetaData::HeadingId))
+            .map(Word::content)
+    }
+
+    /// Whether a paragraph was in a `<p align="center">` or `<div align="center">`.
+    #[must_use]
+    pub fn is_centered(&self) -> bool {
+        self.meta_info
+            .iter()
+            .any(|w| w.kind() == WordType::MetaInfo(MetaData::Centered))
+    }
+
+    /// Whether the component goes right below the one before it, like the
+    /// items of a list, instead of after a line break.
+    #[must_use]
+    pub fn is_list_continuation(&self) -> bool {
+        self.list_continuation
+    }
+
+    pub fn set_list_continuation(&mut self, list_continuation: bool) {
+        self.list_continuation = list_continuation;
+    }
+
+    /// Byte offset of a task's `[ ]` checkbox in the source, so a toggled
+    /// checkbox can be written back.
+    #[must_use]
+    pub fn task_marker(&self) -> Option<usize> {
+        self.task_marker
+    }
+
+    pub fn set_task_marker(&mut self, offset: Option<usize>) {
+        self.task_marker = offset;
+    }
+
+    /// Whether the task is selected in task-select mode. Kept apart from
+    /// `focused`, which a task also gets when a link inside it is selected.
+    #[must_use]
+    pub fn is_task_selected(&self) -> bool {
+        self.task_selected
+    }
+
+    pub fn set_task_selected(&mut self, selected: bool) {
+        self.task_selected = selected;
+    }
+
+    /// Whether a table or code block is wider than the view, so a table is
+    /// squeezed to fit and code is cut off at the edge.
+    #[must_use]
+    pub fn is_wide(&self) -> bool {
+        self.wide
+    }
+
+    /// Columns a wide component is scrolled by, while it is focused for
+    /// horizontal scrolling and laid out at its natural width.
+    #[must_use]
+    pub fn wide_scroll(&self) -> Option<u16> {
+        self.wide_scroll
+    }
+
+    /// Focus or unfocus the component for horizontal scrolling. A table is
+    /// laid out again to fit `width`, or at its natural width when focused.
+    pub fn set_wide_scroll(&mut self, scroll: Option<u16>, width: u16) {
+        let relayout = self.wide_scroll.is_some() != scroll.is_some();
+        self.wide_scroll = scroll;
+        if relayout && matches!(self.kind, TextNode::Table(..)) {
+            self.transform(width);
+        }
+    }
+
+    /// The cursor, sorting and filter of a table in table-select mode.
+    #[must_use]
+    pub fn table_view(&self) -> Option<&TableView> {
+        self.table_view.as_deref()
+    }
+
+    /// Focus or unfocus a table in table-select mode. Unfocused, its rows
+    /// are shown as written again. It is laid out again to fit `width`.
+    pub fn set_table_selected(&mut self, selected: bool, width: u16) {
+        let TextNode::Table(widths, _, _) = self.kind() else {
+            return;
+        };
+        match (selected, self.table_view.take()) {
+            (true, None) => {
+                self.table_view = Some(Box::new(TableView::new(&self.content, widths.len())));
+            }
+            (true, view @ Some(_)) => self.table_view = view,
+            (false, Some(view)) => {
+                self.content = view.original_content();
+                self.transform(width);
+            }
+            (false, None) => {}
+        }
+    }
+
+    /// Change the focused table with `update`, then show its rows in the
+    /// order and with the filter it left.
+    pub fn update_table_view(&mut self, width: u16, update: impl FnOnce(&mut TableView)) {
+        let Some(view) = &mut self.table_view else {
+            return;
+        };
+        update(view);
+        self.content = view.content();
+        self.transform(width);
+    }
+
+    /// The cells of a table as text, row by row and header first. A table
+    /// in table-select mode gives the rows it shows, in the order shown.
+    #[must_use]
+    pub fn table_cells(&self) -> Vec<Vec<String>> {
+        let TextNode::Table(widths, _, _) = self.kind() else {
+            return Vec::new();
+        };
+        let content = self
+            .table_view()
+            .map_or_else(|| self.content.clone(), TableView::shown_content);
+        content
+            .chunks(widths.len().max(1))
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.iter().map(Word::content).join("").trim().to_owned())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The code of a code block as written, without highlighting.
+    #[must_use]
+    pub fn code(&self) -> Option<&str> {
+        self.meta_info
+            .iter()
+            .find(|w| w.kind() == WordType::MetaInfo(MetaData::Code))
+            .map(Word::content)
+    }
+
+    /// Line of the cursor row in the focused table, from the top of the
+    /// component.
+    #[must_use]
+    pub fn table_cursor_offset(&self) -> Option<u16> {
+        let (TextNode::Table(_, heights, _), Some(view)) = (self.kind(), self.table_view()) else {
+            return None;
+        };
+        // The border, the header and the line below it come first
+        let row = view.cursor().0 + 1;
+        Some(1 + heights.iter().take(row).sum::<u16>() + 1)
+    }
+
+    /// Columns the component takes when nothing is squeezed or cut off.
+    #[must_use]
+    pub fn natural_width(&self) -> u16 {
+        match self.kind {
+            TextNode::Table(..) => {
+                let widths = natural_column_widths(self);
+                widths.iter().sum::<u16>() + table_styling_width(widths.len())
+            }
+            TextNode::CodeBlock => {
+                let line_length = self.meta_info.iter().find_map(|w| match w.kind() {
+                    WordType::MetaInfo(MetaData::LineLength(len)) => Some(len),
+                    _ => None,
+                });
+                // Drawn with a column of padding on both sides
+                line_length.unwrap_or_default() + 2
+            }
+            _ => self
+                .content
+                .iter()
+                .map(|line| content_entry_len(line) as u16)
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
     /// Clear focus on a `DetailsSummary` component.
     pub fn deselect_summary(&mut self) {
         self.focused = false;
@@ -301,11 +541,17 @@ impl TextComponent {
         let mut selection: Vec<Vec<&mut Word>> = Vec::new();
         let mut iter = self.content.iter_mut().flatten().peekable();
         while let Some(e) = iter.peek() {
-            if matches!(e.kind(), WordType::Link | WordType::FootnoteInline) {
+            if matches!(
+                e.kind(),
+                WordType::Link | WordType::FootnoteInline | WordType::Abbreviation
+            ) {
                 selection.push(
                     iter.by_ref()
                         .take_while(|c| {
-                            matches!(c.kind(), WordType::Link | WordType::FootnoteInline)
+                            matches!(
+                                c.kind(),
+                                WordType::Link | WordType::FootnoteInline | WordType::Abbreviation
+                            )
                         })
                         .collect(),
                 );
@@ -331,7 +577,7 @@ impl TextComponent {
         Ok(self
             .meta_info()
             .iter()
-            .filter(|c| matches!(c.kind(), WordType::LinkData | WordType::FootnoteInline))
+            .filter(|c| is_link_data(c.kind()))
             .nth(self.focused_index)
             .ok_or("index out of bounds")?
             .content())
@@ -344,7 +590,7 @@ impl TextComponent {
         }
         self.meta_info
             .iter()
-            .filter(|c| matches!(c.kind(), WordType::LinkData | WordType::FootnoteInline))
+            .filter(|c| is_link_data(c.kind()))
             .count()
     }
 
@@ -355,7 +601,7 @@ impl TextComponent {
             return heights;
         }
 
-        if let TextNode::Table(widths, row_heights) = self.kind() {
+        if let TextNode::Table(widths, row_heights, _) = self.kind() {
             let column_count = widths.len();
             let iter = self.content.chunks(column_count).enumerate();
 
@@ -382,45 +628,163 @@ impl TextComponent {
         heights
     }
 
+    /// Index of the link drawn at `column` on `row`, both relative to the
+    /// top left corner of the component. `width` is the width it is
+    /// rendered in, which centered headings need. Links are counted the
+    /// same way as in `visually_select`.
+    #[must_use]
+    pub fn link_at(&self, row: u16, column: u16, width: u16) -> Option<usize> {
+        let column = column.checked_sub(self.indent)?;
+        let width = width.saturating_sub(self.indent);
+        let indent = match self.kind {
+            TextNode::Paragraph
+            | TextNode::List
+            | TextNode::DefinitionTerm
+            | TextNode::Definition => 0,
+            TextNode::Quote => 1,
+            TextNode::Task => 4,
+            TextNode::Heading => match self.meta_info.first().map(Word::kind) {
+                Some(WordType::MetaInfo(MetaData::HeadingLevel(level))) if level > 1 => 0,
+                _ => {
+                    let len = self
+                        .content
+                        .iter()
+                        .flatten()
+                        .map(|w| display_width(w.content()));
+                    (width as usize).saturating_sub(len.sum()) / 2
+                }
+            },
+            _ => return None,
+        };
+        let is_link = |word: &Word| {
+            matches!(
+                word.kind(),
+                WordType::Link
+                    | WordType::Selected
+                    | WordType::FootnoteInline
+                    | WordType::Abbreviation
+            )
+        };
+
+        let centered = self.kind == TextNode::Paragraph && self.is_centered();
+
+        let mut link = None;
+        let mut in_link = false;
+        for (i, line) in self.content.iter().enumerate() {
+            let mut x = if centered {
+                let len = line
+                    .iter()
+                    .map(|w| display_width(w.content()))
+                    .sum::<usize>();
+                (width as usize).saturating_sub(len) / 2
+            } else {
+                indent
+            };
+            for word in line {
+                if is_link(word) && !in_link {
+                    link = Some(link.map_or(0, |l| l + 1));
+                }
+                in_link = is_link(word);
+                let end = x + display_width(word.content());
+                if i == row as usize && in_link && (x..end).contains(&(column as usize)) {
+                    return link;
+                }
+                x = end;
+            }
+        }
+        None
+    }
+
     pub fn words_mut(&mut self) -> Vec<&mut Word> {
         self.content.iter_mut().flatten().collect()
     }
 
     pub fn transform(&mut self, width: u16) {
+        let width = width.saturating_sub(self.indent);
         match self.kind {
             TextNode::List => {
                 transform_list(self, width);
             }
             TextNode::CodeBlock => {
                 transform_codeblock(self);
+                self.wide = self.natural_width() > width;
             }
-            TextNode::Paragraph | TextNode::Task | TextNode::Quote => {
+            TextNode::Paragraph
+            | TextNode::Task
+            | TextNode::Quote
+            | TextNode::DefinitionTerm
+            | TextNode::Definition => {
                 transform_paragraph(self, width);
             }
+            TextNode::FrontMatter { .. } => {
+                // Rows are kept apart by hard breaks, and indented past the fold marker
+                let lines = word_wrapping(
+                    self.content.iter().flatten(),
+                    (width as usize).saturating_sub(3),
+                    true,
+                );
+                self.height = lines.len() as u16;
+                self.content = lines;
+            }
             TextNode::LineBreak | TextNode::Heading | TextNode::DetailsSummary { .. } => {
                 self.height = 1;
             }
-            TextNode::Table(_, _) => {
+            TextNode::Table(..) => {
+                // As in `transform_table`, which leaves a column free
+                self.wide = self.natural_width() > width.saturating_sub(1);
+                let width = if self.wide_scroll.is_some() {
+                    u16::MAX
+                } else {
+                    width
+                };
                 transform_table(self, width);
             }
             TextNode::HorizontalSeparator => self.height = 1,
+            TextNode::MathBlock => self.height = self.content.len() as u16,
             TextNode::Image => unreachable!("Image should not be transformed"),
             TextNode::Footnote => self.height = 0,
         }
     }
 }
 
+/// Whether a meta word is what a selectable word leads to, in the same
+/// order as the words.
+fn is_link_data(kind: WordType) -> bool {
+    matches!(
+        kind,
+        WordType::LinkData | WordType::FootnoteInline | WordType::AbbreviationData
+    )
+}
+
+/// The content of a word that ends its line, from a `<br>`.
+pub(crate) const HARD_BREAK: &str = "\n";
+
 pub(crate) fn word_wrapping<'a>(
     words: impl IntoIterator<Item = &'a Word>,
     width: usize,
     allow_hyphen: bool,
 ) -> Vec<Vec<Word>> {
+    // Deeply indented text still gets a column, one character per line
+    let width = width.max(1);
     let enable_hyphen = allow_hyphen && width > 4;
 
     let mut lines = Vec::new();
     let mut line = Vec::new();
     let mut line_len = 0;
+    let mut line_start = false;
     for word in words {
+        if word.content() == HARD_BREAK {
+            lines.push(std::mem::take(&mut line));
+            line_len = 0;
+            line_start = true;
+            continue;
+        }
+        // The words after a hard break start at the margin
+        if line_start && word.content().trim().is_empty() {
+            continue;
+        }
+        line_start = false;
+
         let word_len = display_width(word.content());
         if line_len + word_len <= width {
             line_len += word_len;
@@ -436,7 +800,7 @@ pub(crate) fn word_wrapping<'a>(
         } else {
             let content = word.content().to_owned();
 
-            if width - line_len < 4 {
+            if width - line_len < 4 && !line.is_empty() {
                 line_len = 0;
                 lines.push(line);
                 line = Vec::new();
@@ -529,9 +893,11 @@ fn split_by_width(text: &str, max_width: usize) -> (String, String) {
 
 fn transform_paragraph(component: &mut TextComponent, width: u16) {
     let width = match component.kind {
-        TextNode::Paragraph => width as usize - 1,
-        TextNode::Task => width as usize - 4,
-        TextNode::Quote => width as usize - 2,
+        TextNode::Paragraph | TextNode::DefinitionTerm | TextNode::Definition => {
+            (width as usize).saturating_sub(1)
+        }
+        TextNode::Task => (width as usize).saturating_sub(4),
+        TextNode::Quote => (width as usize).saturating_sub(2),
         _ => unreachable!(),
     };
 
@@ -655,162 +1021,26 @@ fn transform_codeblock(component: &mut TextComponent) {
 }
 
 fn transform_list(component: &mut TextComponent, width: u16) {
-    let mut len = 0;
-    let mut lines = Vec::new();
-    let mut line = Vec::new();
-    let indent_iter = component
-        .meta_info
-        .iter()
-        .filter(|c| c.content().trim() == "");
-    let list_type_iter = component.meta_info.iter().filter(|c| {
-        matches!(
-            c.kind(),
-            WordType::MetaInfo(MetaData::OList | MetaData::UList)
-        )
-    });
-
-    let mut zip_iter = indent_iter.zip(list_type_iter);
-
-    let mut o_list_counter_stack = vec![0];
-    let mut max_stack_len = 1;
-    let mut indent = 0;
-    let mut extra_indent = 0;
-    let mut tmp = indent;
-    for word in component.content.iter_mut().flatten() {
-        let word_len = display_width(word.content());
-        if word_len + len < width as usize && word.kind() != WordType::ListMarker {
-            len += word_len;
-            line.push(word.clone());
-        } else {
-            let filler_content = if word.kind() == WordType::ListMarker {
-                indent = if let Some((meta, list_type)) = zip_iter.next() {
-                    match tmp.cmp(&display_width(meta.content())) {
-                        cmp::Ordering::Less => {
-                            o_list_counter_stack.push(0);
-                            max_stack_len += 1;
-                        }
-                        cmp::Ordering::Greater => {
-                            o_list_counter_stack.pop();
-                        }
-                        cmp::Ordering::Equal => (),
-                    }
-                    if list_type.kind() == WordType::MetaInfo(MetaData::OList) {
-                        let counter = o_list_counter_stack
-                            .last_mut()
-                            .expect("List parse error. Stack is empty");
-
-                        *counter += 1;
-
-                        word.set_content(format!("{counter}. "));
-
-                        extra_indent = 1; // Ordered list is longer than unordered and needs extra space
-                    } else {
-                        extra_indent = 0;
-                    }
-                    tmp = display_width(meta.content());
-                    tmp
-                } else {
-                    0
-                };
-
-                " ".repeat(indent)
-            } else {
-                " ".repeat(indent + 2 + extra_indent)
-            };
-
-            let filler = Word::new(filler_content, WordType::Normal);
-
-            lines.push(line);
-            let content = word.content().trim_start().to_owned();
-            word.set_content(content);
-            len = display_width(word.content()) + display_width(filler.content());
-            line = vec![filler, word.to_owned()];
-        }
-    }
-    lines.push(line);
-    // Remove empty lines
-    lines.retain(|l| l.iter().any(|c| c.content() != ""));
-
-    // Find out if there are ordered indexes longer than 3 chars. F.ex. `1. ` is three chars, but `10. ` is four chars.
-    // To align the list on the same column, we need to find the longest index and add the difference to the shorter indexes.
-    let mut indent_correction = vec![0; max_stack_len];
-    let mut indent_index: u32 = 0;
-    let mut indent_len = 0;
-
-    for line in &lines {
-        if !line[1]
-            .content()
-            .strip_prefix(['1', '2', '3', '4', '5', '6', '7', '8', '9'])
-            .is_some_and(|c| c.ends_with(". "))
-        {
-            continue;
-        }
-
-        match indent_len.cmp(&display_width(line[0].content())) {
-            cmp::Ordering::Less => {
-                indent_index += 1;
-                indent_len = display_width(line[0].content());
-            }
-            cmp::Ordering::Greater => {
-                indent_index = indent_index.saturating_sub(1);
-                indent_len = display_width(line[0].content());
-            }
-            cmp::Ordering::Equal => (),
-        }
-
-        indent_correction[indent_index as usize] = cmp::max(
-            indent_correction[indent_index as usize],
-            display_width(line[1].content()),
-        );
-    }
-
-    // Finally, apply the indent correction to the list for each ordered index which is shorter
-    // than the longest index.
-
-    indent_index = 0;
-    indent_len = 0;
-    let mut unordered_list_skip = true; // Skip unordered list items. They are already aligned.
-
-    for line in &mut lines {
-        if line[1]
-            .content()
-            .strip_prefix(['1', '2', '3', '4', '5', '6', '7', '8', '9'])
-            .is_some_and(|c| c.ends_with(". "))
-        {
-            unordered_list_skip = false;
-        }
-
-        if line[1].content() == "• " || unordered_list_skip {
-            unordered_list_skip = true;
-            continue;
-        }
+    // The marker hangs in front of the item, wrapped lines start under its text
+    let mut words = component.content.iter().flatten().cloned().collect_vec();
+    let marker = if words
+        .first()
+        .is_some_and(|w| w.kind() == WordType::ListMarker)
+    {
+        words.remove(0)
+    } else {
+        Word::new(String::new(), WordType::ListMarker)
+    };
+    let hang = display_width(marker.content());
 
-        let amount = if line[1]
-            .content()
-            .strip_prefix(['1', '2', '3', '4', '5', '6', '7', '8', '9'])
-            .is_some_and(|c| c.ends_with(". "))
-        {
-            match indent_len.cmp(&display_width(line[0].content())) {
-                cmp::Ordering::Less => {
-                    indent_index += 1;
-                    indent_len = display_width(line[0].content());
-                }
-                cmp::Ordering::Greater => {
-                    indent_index = indent_index.saturating_sub(1);
-                    indent_len = display_width(line[0].content());
-                }
-                cmp::Ordering::Equal => (),
-            }
-            indent_correction[indent_index as usize]
-                .saturating_sub(display_width(line[1].content()))
-                + display_width(line[0].content())
+    let mut lines = word_wrapping(&words, (width as usize).saturating_sub(hang + 1), true);
+    for (i, line) in lines.iter_mut().enumerate() {
+        let prefix = if i == 0 {
+            marker.clone()
         } else {
-            // -3 because that is the length of the shortest ordered index (1. )
-            (indent_correction[indent_index as usize] + display_width(line[0].content()))
-                .saturating_sub(3)
+            Word::new(" ".repeat(hang), WordType::Normal)
         };
-
-        line[0].set_content(" ".repeat(amount));
+        line.insert(0, prefix);
     }
 
     component.height = lines.len() as u16;
@@ -824,17 +1054,20 @@ fn table_styling_width(column_count: usize) -> u16 {
 fn transform_table(component: &mut TextComponent, width: u16) {
     // Subtract 1 to match the actual render area width (consistent with transform_paragraph)
     let width = width.saturating_sub(1);
-    let content = &mut component.content;
 
-    let column_count = component
+    let alignments = component
         .meta_info
         .iter()
         .filter(|w| w.kind() == WordType::MetaInfo(MetaData::ColumnsCount))
-        .count();
+        .map(|w| column_alignment(w.content()))
+        .collect_vec();
+    let column_count = alignments.len();
+    let widths = natural_column_widths(component);
+    let content = &mut component.content;
 
-    if !content.len().is_multiple_of(column_count) || column_count == 0 {
+    if column_count == 0 || !content.len().is_multiple_of(column_count) {
         component.height = 1;
-        component.kind = TextNode::Table(vec![], vec![]);
+        component.kind = TextNode::Table(vec![], vec![], vec![]);
         return;
     }
 
@@ -847,23 +1080,6 @@ fn transform_table(component: &mut TextComponent, width: u16) {
 
     let row_count = content.len() / column_count;
 
-    ///////////////////////////
-    // Find unbalanced width //
-    ///////////////////////////
-    let widths = {
-        let mut widths = vec![0; column_count];
-        content.chunks(column_count).for_each(|row| {
-            row.iter().enumerate().for_each(|(col_i, entry)| {
-                let len = content_entry_len(entry);
-                if len > widths[col_i] as usize {
-                    widths[col_i] = len as u16;
-                }
-            });
-        });
-
-        widths
-    };
-
     let styling_width = table_styling_width(column_count);
     let unbalanced_cells_width = widths.iter().sum::<u16>();
 
@@ -872,7 +1088,7 @@ fn transform_table(component: &mut TextComponent, width: u16) {
     /////////////////////////////////////
     if width >= unbalanced_cells_width + styling_width {
         component.height = row_count as u16 + 3;
-        component.kind = TextNode::Table(widths, vec![1; row_count]);
+        component.kind = TextNode::Table(widths, vec![1; row_count], alignments);
         return;
     }
 
@@ -901,7 +1117,7 @@ fn transform_table(component: &mut TextComponent, width: u16) {
 
     if overflowing_columns.is_empty() {
         component.height = row_count as u16 + 3;
-        component.kind = TextNode::Table(widths, vec![1; row_count]);
+        component.kind = TextNode::Table(widths, vec![1; row_count], alignments);
         return;
     }
 
@@ -939,31 +1155,50 @@ fn transform_table(component: &mut TextComponent, width: u16) {
     ////////////////////////////////////////
     // Wrap words based on balanced width //
     ////////////////////////////////////////
+    // The cells keep their words, so the table can be laid out again at
+    // another width. They are wrapped the same way when drawn.
     let mut heights = vec![1; row_count];
-    for (row_i, row) in content
-        .iter_mut()
-        .chunks(column_count)
-        .into_iter()
-        .enumerate()
-    {
-        for (column_i, entry) in row.into_iter().enumerate() {
-            let lines = word_wrapping(
-                entry.drain(..).as_ref(),
-                widths_balanced[column_i] as usize,
-                true,
-            );
+    for (row_i, row) in content.chunks(column_count).enumerate() {
+        for (column_i, entry) in row.iter().enumerate() {
+            let lines = word_wrapping(entry, widths_balanced[column_i] as usize, true);
 
             if heights[row_i] < lines.len() as u16 {
                 heights[row_i] = lines.len() as u16;
             }
-
-            let _drop = std::mem::replace(entry, lines.into_iter().flatten().collect());
         }
     }
 
     component.height = heights.iter().copied().sum::<u16>() + 3;
 
-    component.kind = TextNode::Table(widths_balanced, heights);
+    component.kind = TextNode::Table(widths_balanced, heights, alignments);
+}
+
+/// The width of the widest cell of each column of a table.
+fn natural_column_widths(component: &TextComponent) -> Vec<u16> {
+    let column_count = component
+        .meta_info
+        .iter()
+        .filter(|w| w.kind() == WordType::MetaInfo(MetaData::ColumnsCount))
+        .count();
+    let mut widths = vec![0; column_count];
+    if column_count == 0 {
+        return widths;
+    }
+    for row in component.content.chunks(column_count) {
+        for (column_i, entry) in row.iter().enumerate() {
+            widths[column_i] = widths[column_i].max(content_entry_len(entry) as u16);
+        }
+    }
+    widths
+}
+
+/// The alignment set by the colons of a `:---:` separator.
+fn column_alignment(separator: &str) -> Alignment {
+    match (separator.starts_with(':'), separator.ends_with(':')) {
+        (true, true) => Alignment::Center,
+        (false, true) => Alignment::Right,
+        _ => Alignment::Left,
+    }
 }
 
 #[must_use]
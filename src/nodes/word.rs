@@ -4,8 +4,6 @@ use crate::parser::MdParseEnum;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MetaData {
-    UList,
-    OList,
     PLanguage,
     Other,
     ColumnsCount,
@@ -16,10 +14,20 @@ pub enum MetaData {
     Caution,
     LineLength(u16),
     HeadingLevel(u8),
+    /// The `{#id}` of a heading, in the content of the word.
+    HeadingId,
+    /// A paragraph from `<p align="center">` or `<div align="center">`.
+    Centered,
+    /// The code of a code block as written, in the content of the word.
+    Code,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WordType {
+    /// A term from an `*[term]: text` definition, selectable to show its text.
+    Abbreviation,
+    /// The text an abbreviation stands for, after its term.
+    AbbreviationData,
     Bold,
     BoldItalic,
     Code,
@@ -27,10 +35,12 @@ pub enum WordType {
     Footnote,
     FootnoteData,
     FootnoteInline,
+    Highlight,
     Italic,
     Link,
     LinkData,
     ListMarker,
+    Math,
     MetaInfo(MetaData),
     Normal,
     Selected,
@@ -52,9 +62,13 @@ impl From<MdParseEnum> for WordType {
             MdParseEnum::Bold => WordType::Bold,
             MdParseEnum::Italic => WordType::Italic,
             MdParseEnum::Strikethrough => WordType::Strikethrough,
+            MdParseEnum::Highlight => WordType::Highlight,
+            MdParseEnum::Abbreviation => WordType::Abbreviation,
+            MdParseEnum::AbbreviationData => WordType::AbbreviationData,
             MdParseEnum::Link | MdParseEnum::WikiLink | MdParseEnum::InlineLink => WordType::Link,
             MdParseEnum::BoldItalic => WordType::BoldItalic,
             MdParseEnum::Digit => WordType::ListMarker,
+            MdParseEnum::Math | MdParseEnum::MathBlockStr => WordType::Math,
             MdParseEnum::Paragraph
             | MdParseEnum::AltText
             | MdParseEnum::Quote
@@ -66,6 +80,7 @@ impl From<MdParseEnum> for WordType {
             MdParseEnum::Tip => WordType::MetaInfo(MetaData::Tip),
             MdParseEnum::Warning => WordType::MetaInfo(MetaData::Warning),
             MdParseEnum::Caution => WordType::MetaInfo(MetaData::Caution),
+            MdParseEnum::AlignCenter => WordType::MetaInfo(MetaData::Centered),
             MdParseEnum::Heading
             | MdParseEnum::BoldItalicStr
             | MdParseEnum::BoldStr
@@ -75,15 +90,31 @@ impl From<MdParseEnum> for WordType {
             | MdParseEnum::DetailsBody
             | MdParseEnum::DetailsOpenAttr
             | MdParseEnum::DetailsSummary
+            | MdParseEnum::AbbreviationDefinition
+            | MdParseEnum::Definition
+            | MdParseEnum::DefinitionList
+            | MdParseEnum::DefinitionTerm
+            | MdParseEnum::HighlightStr
+            | MdParseEnum::FrontMatter
+            | MdParseEnum::FrontMatterContent
+            | MdParseEnum::HtmlTag
             | MdParseEnum::Image
             | MdParseEnum::ItalicStr
+            | MdParseEnum::LinkLabel
+            | MdParseEnum::LinkReference
+            | MdParseEnum::LinkReferenceDefinition
+            | MdParseEnum::ListCodeBlock
             | MdParseEnum::ListContainer
+            | MdParseEnum::ListGap
+            | MdParseEnum::ListParagraph
+            | MdParseEnum::MathBlock
+            | MdParseEnum::MathStr
             | MdParseEnum::OrderedList
             | MdParseEnum::StrikethroughStr
             | MdParseEnum::Footnote
             | MdParseEnum::Table
             | MdParseEnum::TableCell
-            | MdParseEnum::Task
+            | MdParseEnum::TableRow
             | MdParseEnum::UnorderedList
             | MdParseEnum::TableSeparator => {
                 unreachable!("Edit this or pest file to fix for value: {:?}", value)
@@ -149,7 +180,10 @@ impl Word {
     pub fn is_renderable(&self) -> bool {
         !matches!(
             self.kind(),
-            WordType::MetaInfo(_) | WordType::LinkData | WordType::FootnoteData
+            WordType::MetaInfo(_)
+                | WordType::LinkData
+                | WordType::FootnoteData
+                | WordType::AbbreviationData
         )
     }
 
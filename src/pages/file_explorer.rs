@@ -13,6 +13,7 @@ use ratatui::{
     widgets::{Block, List, ListItem, ListState, StatefulWidget},
 };
 
+use crate::pages::markdown_renderer::left_margin;
 use crate::search::find_files;
 use crate::util::colors::color_config;
 
@@ -275,6 +276,23 @@ impl FileTree {
         self.page = (i / partition) as u32;
     }
 
+    /// Select the file drawn at `row` of the tree, counted from its top.
+    /// Every file takes two rows followed by a spacer, below the title.
+    /// Returns `false` if there is no file there.
+    pub fn select_at(&mut self, row: u16, height: u16) -> bool {
+        let Some(row) = (row as usize).checked_sub(1) else {
+            return false;
+        };
+        let partition = self.partition(height);
+        let in_page = row / 3 * 2;
+        let i = self.page as usize * partition + in_page;
+        if row % 3 == 2 || in_page >= partition || i >= self.files.len() {
+            return false;
+        }
+        self.list_state.select(Some(i));
+        true
+    }
+
     pub fn unselect(&mut self) {
         self.list_state.select(None);
     }
@@ -344,6 +362,17 @@ impl FileTree {
     }
 }
 
+/// Where the file tree is drawn on a terminal of `size`, given the width
+/// the app is laid out for.
+#[must_use]
+pub fn file_tree_area(size: Rect, width: u16) -> Rect {
+    Rect {
+        x: left_margin(size),
+        width: width - 3,
+        ..size
+    }
+}
+
 impl Widget for FileTree {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let mut state = self.state().to_owned();
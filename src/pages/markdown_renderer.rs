@@ -11,6 +11,7 @@ use ratatui::{
 
 use crate::{
     nodes::{
+        table::TableView,
         textcomponent::{
             TABLE_CELL_PADDING, TextComponent, TextNode, content_entry_len, word_wrapping,
         },
@@ -18,10 +19,38 @@ use crate::{
     },
     util::{
         colors::{color_config, heading_colors},
-        general::GENERAL_CONFIG,
+        general::{Centering, GENERAL_CONFIG},
     },
 };
 
+/// Where the document is drawn on a terminal of `size`, given the width
+/// the app is laid out for.
+#[must_use]
+pub fn markdown_area(size: Rect, width: u16) -> Rect {
+    Rect {
+        width: cmp::min(width - 3, size.width - 1),
+        height: if GENERAL_CONFIG.help_menu {
+            size.height.saturating_sub(5)
+        } else {
+            size.height
+        },
+        x: left_margin(size),
+        ..size
+    }
+}
+
+/// Column the document and the file tree start at, following the
+/// `alignment` setting.
+#[must_use]
+pub fn left_margin(size: Rect) -> u16 {
+    let x = match GENERAL_CONFIG.centering {
+        Centering::Left => 2,
+        Centering::Center => (size.width / 2).saturating_sub(GENERAL_CONFIG.width / 2),
+        Centering::Right => size.width.saturating_sub(GENERAL_CONFIG.width + 2),
+    };
+    cmp::max(x, 2)
+}
+
 fn clips_upper_bound(_area: Rect, component: &TextComponent) -> bool {
     component.scroll_offset() > component.y_offset()
 }
@@ -80,16 +109,26 @@ impl Widget for TextComponent {
             .cloned()
             .unwrap_or_else(|| Word::new(String::new(), WordType::Normal));
 
-        let area = Rect { height, y, ..area };
+        let indent = cmp::min(self.indent(), area.width);
+        let area = Rect {
+            x: area.x + indent,
+            width: area.width - indent,
+            height,
+            y,
+        };
 
         match kind {
-            TextNode::Paragraph => render_paragraph(area, buf, self, clips),
+            TextNode::Paragraph | TextNode::Definition => {
+                render_paragraph(area, buf, self, clips);
+            }
+            TextNode::DefinitionTerm => render_definition_term(area, buf, self, clips),
             TextNode::Heading => render_heading(area, buf, self),
             TextNode::Task => render_task(area, buf, self, clips, &meta_info),
             TextNode::List => render_list(area, buf, self, clips),
             TextNode::CodeBlock => render_code_block(area, buf, self, clips),
-            TextNode::Table(widths, heights) => {
-                render_table(area, buf, self, clips, widths, heights);
+            TextNode::MathBlock => render_math_block(area, buf, self, clips),
+            TextNode::Table(widths, heights, alignments) => {
+                render_table(area, buf, self, clips, widths, heights, &alignments);
             }
             TextNode::Quote => render_quote(area, buf, self, clips),
             TextNode::LineBreak => (),
@@ -99,13 +138,19 @@ impl Widget for TextComponent {
             TextNode::DetailsSummary { folded, .. } => {
                 render_details_summary(area, buf, self, folded);
             }
+            TextNode::FrontMatter { folded, .. } => {
+                render_front_matter(area, buf, self, clips, folded);
+            }
         }
     }
 }
 
 fn style_word_content<'a>(word: &Word, content: impl Into<Cow<'a, str>>) -> Span<'a> {
     match word.kind() {
-        WordType::MetaInfo(_) | WordType::LinkData | WordType::FootnoteData => unreachable!(),
+        WordType::MetaInfo(_)
+        | WordType::LinkData
+        | WordType::FootnoteData
+        | WordType::AbbreviationData => unreachable!(),
         WordType::Selected => Span::styled(
             content,
             Style::default()
@@ -118,6 +163,18 @@ fn style_word_content<'a>(word: &Word, content: impl Into<Cow<'a, str>>) -> Span
         WordType::Link | WordType::FootnoteInline => {
             Span::styled(content, Style::default().fg(color_config().link_color))
         }
+        WordType::Abbreviation => Span::styled(
+            content,
+            Style::default()
+                .fg(color_config().link_color)
+                .add_modifier(Modifier::UNDERLINED),
+        ),
+        WordType::Highlight => Span::styled(
+            content,
+            Style::default()
+                .fg(color_config().highlight_fg_color)
+                .bg(color_config().highlight_bg_color),
+        ),
         WordType::Italic => Span::styled(
             content,
             Style::default().fg(color_config().italic_color).italic(),
@@ -133,6 +190,7 @@ fn style_word_content<'a>(word: &Word, content: impl Into<Cow<'a, str>>) -> Span
                 .add_modifier(Modifier::CROSSED_OUT),
         ),
         WordType::White => Span::styled(content, Style::default().fg(Color::White)),
+        WordType::Math => Span::styled(content, Style::default().fg(color_config().math_color)),
         WordType::ListMarker => Span::styled(content, Style::default().fg(Color::White)),
         WordType::BoldItalic => Span::styled(
             content,
@@ -186,9 +244,15 @@ fn table_border_line(
 fn build_table_row_lines(
     row: &[Vec<Word>],
     widths: &[u16],
+    alignments: &[Alignment],
     row_height: u16,
     row_style: Option<Style>,
+    cursor: Option<usize>,
 ) -> Vec<Line<'static>> {
+    let cursor_style = Style::default()
+        .fg(color_config().link_selected_fg_color)
+        .bg(color_config().link_selected_bg_color);
+
     let wrapped_cells = row
         .iter()
         .zip(widths.iter())
@@ -200,20 +264,33 @@ fn build_table_row_lines(
             let mut spans = vec![Span::raw("│")];
 
             for (column_i, cell_lines) in wrapped_cells.iter().enumerate() {
+                let start = spans.len();
                 spans.push(Span::raw(" ".repeat(TABLE_CELL_PADDING as usize)));
 
                 if let Some(words) = cell_lines.get(line_i) {
-                    spans.extend(words.iter().map(style_word_owned));
-
                     let padding = widths[column_i] as usize - content_entry_len(words);
-                    if padding > 0 {
-                        spans.push(Span::raw(" ".repeat(padding)));
+                    let before = match alignments.get(column_i) {
+                        Some(Alignment::Right) => padding,
+                        Some(Alignment::Center) => padding / 2,
+                        _ => 0,
+                    };
+                    if before > 0 {
+                        spans.push(Span::raw(" ".repeat(before)));
+                    }
+                    spans.extend(words.iter().map(style_word_owned));
+                    if padding > before {
+                        spans.push(Span::raw(" ".repeat(padding - before)));
                     }
                 } else {
                     spans.push(Span::raw(" ".repeat(widths[column_i] as usize)));
                 }
 
                 spans.push(Span::raw(" ".repeat(TABLE_CELL_PADDING as usize)));
+                if cursor == Some(column_i) {
+                    for span in &mut spans[start..] {
+                        *span = span.clone().patch_style(cursor_style);
+                    }
+                }
                 spans.push(Span::raw("│"));
             }
 
@@ -227,7 +304,13 @@ fn build_table_row_lines(
         .collect()
 }
 
-fn build_table_lines(content: &[Vec<Word>], widths: &[u16], heights: &[u16]) -> Vec<Line<'static>> {
+fn build_table_lines(
+    content: &[Vec<Word>],
+    widths: &[u16],
+    heights: &[u16],
+    alignments: &[Alignment],
+    cursor: Option<(usize, usize)>,
+) -> Vec<Line<'static>> {
     let column_count = widths.len();
     let header_style = Style::default()
         .fg(color_config().table_header_fg_color)
@@ -240,8 +323,13 @@ fn build_table_lines(content: &[Vec<Word>], widths: &[u16], heights: &[u16]) ->
         lines.extend(build_table_row_lines(
             row,
             widths,
+            alignments,
             heights[row_i],
             row_style,
+            // The cursor row counts from below the header
+            cursor
+                .filter(|(row, _)| row + 1 == row_i)
+                .map(|(_, column)| column),
         ));
 
         if row_i == 0 {
@@ -385,7 +473,44 @@ fn render_heading(area: Rect, buf: &mut Buffer, component: TextComponent) {
     paragraph.render(area, buf);
 }
 
+fn render_definition_term(area: Rect, buf: &mut Buffer, component: TextComponent, clip: Clipping) {
+    let top = component
+        .scroll_offset()
+        .saturating_sub(component.y_offset());
+    let mut content = component.content_owned();
+    let content = match clip {
+        Clipping::Both => {
+            content.drain(0..top as usize);
+            content.drain(area.height as usize..);
+            content
+        }
+        Clipping::Upper => {
+            let offset = content.len() - area.height as usize;
+            content.drain(0..offset);
+            content
+        }
+        Clipping::Lower => {
+            content.drain(area.height as usize..);
+            content
+        }
+        Clipping::None => content,
+    };
+
+    // Bold on top of the styling of each word
+    let lines = content
+        .iter()
+        .map(|c| Line::from(c.iter().map(|w| style_word(w).bold()).collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+
+    Paragraph::new(lines).render(area, buf);
+}
+
 fn render_paragraph(area: Rect, buf: &mut Buffer, component: TextComponent, clip: Clipping) {
+    let alignment = if component.is_centered() {
+        Alignment::Center
+    } else {
+        Alignment::Left
+    };
     let top = component
         .scroll_offset()
         .saturating_sub(component.y_offset());
@@ -417,11 +542,38 @@ fn render_paragraph(area: Rect, buf: &mut Buffer, component: TextComponent, clip
         .map(|c| Line::from(c.iter().map(style_word).collect::<Vec<_>>()))
         .collect::<Vec<_>>();
 
-    let paragraph = Paragraph::new(lines);
+    let paragraph = Paragraph::new(lines).alignment(alignment);
 
     paragraph.render(area, buf);
 }
 
+fn render_math_block(area: Rect, buf: &mut Buffer, component: TextComponent, clip: Clipping) {
+    let top = component
+        .scroll_offset()
+        .saturating_sub(component.y_offset());
+    let mut content = component.content_owned();
+    match clip {
+        Clipping::Both => {
+            content.drain(0..top as usize);
+            content.truncate(area.height as usize);
+        }
+        Clipping::Upper => {
+            content.drain(0..content.len() - area.height as usize);
+        }
+        Clipping::Lower => content.truncate(area.height as usize),
+        Clipping::None => (),
+    }
+
+    let lines = content
+        .iter()
+        .map(|c| Line::from(c.iter().map(style_word).collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+
+    Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .render(area, buf);
+}
+
 fn render_details_summary(area: Rect, buf: &mut Buffer, component: TextComponent, folded: bool) {
     let focused = component.is_focused();
     let mut style = Style::default().add_modifier(Modifier::BOLD);
@@ -438,6 +590,45 @@ fn render_details_summary(area: Rect, buf: &mut Buffer, component: TextComponent
     Paragraph::new(Line::from(spans)).render(area, buf);
 }
 
+fn render_front_matter(
+    area: Rect,
+    buf: &mut Buffer,
+    component: TextComponent,
+    clip: Clipping,
+    folded: bool,
+) {
+    let marker = if folded { "▶ " } else { "▼ " };
+    let mut lines = component
+        .content()
+        .iter()
+        .enumerate()
+        .map(|(i, words)| {
+            let prefix = if i == 0 { marker } else { "  " };
+            let mut spans = vec![Span::styled(prefix, Style::default().bold())];
+            spans.extend(words.iter().map(style_word));
+            Line::from(spans)
+        })
+        .collect::<Vec<_>>();
+
+    match clip {
+        Clipping::Both => {
+            let top = component.scroll_offset() - component.y_offset();
+            lines.drain(0..top as usize);
+            lines.drain(area.height as usize..);
+        }
+        Clipping::Upper => {
+            let offset = lines.len() - area.height as usize;
+            lines.drain(0..offset);
+        }
+        Clipping::Lower => {
+            lines.drain(area.height as usize..);
+        }
+        Clipping::None => (),
+    }
+
+    Paragraph::new(lines).render(area, buf);
+}
+
 fn render_list(area: Rect, buf: &mut Buffer, component: TextComponent, clip: Clipping) {
     let top = component
         .scroll_offset()
@@ -539,9 +730,24 @@ fn render_code_block(area: Rect, buf: &mut Buffer, component: TextComponent, cli
         }
     };
 
-    let paragraph = Paragraph::new(content);
-
-    paragraph.render(area, buf);
+    let Some(scroll) = component.wide_scroll() else {
+        Paragraph::new(content).render(area, buf);
+        return;
+    };
+    Paragraph::new(content)
+        .scroll((0, scroll))
+        .render(area, buf);
+
+    // The block is as wide as its longest line, of which only some is shown
+    let area = area.intersection(*buf.area());
+    // Without the padding around the code
+    let hidden_right = component.natural_width().saturating_sub(2) > scroll + area.width;
+    render_scroll_indicators(
+        area,
+        buf,
+        (scroll > 0).then(|| "\u{25c0}".to_owned()),
+        hidden_right.then(|| "\u{25b6}".to_owned()),
+    );
 }
 
 fn render_table(
@@ -551,6 +757,7 @@ fn render_table(
     clip: Clipping,
     widths: Vec<u16>,
     heights: Vec<u16>,
+    alignments: &[Alignment],
 ) {
     let column_count = widths.len();
 
@@ -563,7 +770,20 @@ fn render_table(
         .scroll_offset()
         .saturating_sub(component.y_offset());
 
-    let mut lines = build_table_lines(component.content(), &widths, &heights);
+    let view = component.table_view();
+    let cursor = view
+        .filter(|view| view.row_counts().0 > 0)
+        .map(TableView::cursor);
+    let mut lines = build_table_lines(component.content(), &widths, &heights, alignments, cursor);
+    // Drawn on the bottom border when it is in view
+    let filter = view
+        .filter(|view| {
+            !view.filter().is_empty() && matches!(clip, Clipping::None | Clipping::Upper)
+        })
+        .map(|view| {
+            let (shown, total) = view.row_counts();
+            format!(" /{} {shown} of {total} ", view.filter())
+        });
 
     let lines = match clip {
         Clipping::Both => {
@@ -583,7 +803,66 @@ fn render_table(
         Clipping::None => lines,
     };
 
-    Paragraph::new(lines).render(area, buf);
+    let scroll = component.wide_scroll();
+    Paragraph::new(lines)
+        .scroll((0, scroll.unwrap_or_default()))
+        .render(area, buf);
+
+    if let Some(filter) = filter {
+        let style = Style::default()
+            .fg(color_config().link_selected_fg_color)
+            .bg(color_config().link_selected_bg_color);
+        buf.set_stringn(
+            area.x + 1,
+            area.bottom().saturating_sub(1),
+            filter,
+            area.width.saturating_sub(2) as usize,
+            style,
+        );
+    }
+
+    let Some(scroll) = scroll else {
+        return;
+    };
+
+    // Count the columns not fully in view on each side
+    let mut start = 1 + TABLE_CELL_PADDING;
+    let (mut left, mut right) = (0, 0);
+    for width in &widths {
+        if start < scroll {
+            left += 1;
+        }
+        if start + width > scroll + area.width {
+            right += 1;
+        }
+        start += width + TABLE_CELL_PADDING * 2 + 1;
+    }
+    render_scroll_indicators(
+        area,
+        buf,
+        (left > 0).then(|| format!("\u{25c0} {left}")),
+        (right > 0).then(|| format!("{right} \u{25b6}")),
+    );
+}
+
+/// Marks on the top row of a horizontally scrolled table or code block,
+/// with what is hidden on each side.
+fn render_scroll_indicators(
+    area: Rect,
+    buf: &mut Buffer,
+    left: Option<String>,
+    right: Option<String>,
+) {
+    let style = Style::default()
+        .fg(color_config().link_selected_fg_color)
+        .bg(color_config().link_selected_bg_color);
+    if let Some(left) = left {
+        buf.set_string(area.x, area.y, left, style);
+    }
+    if let Some(right) = right {
+        let x = area.right().saturating_sub(right.chars().count() as u16);
+        buf.set_string(x, area.y, right, style);
+    }
 }
 
 fn render_task(
@@ -596,13 +875,20 @@ fn render_task(
     const CHECKBOX: &str = "✅ ";
     const UNCHECKED: &str = "❌ ";
 
-    let checkbox = if meta_info.content() == "- [ ] " {
+    let checkbox = if meta_info.content().starts_with("[ ]") {
         UNCHECKED
     } else {
         CHECKBOX
     };
 
-    let paragraph = Paragraph::new(checkbox);
+    let mut paragraph = Paragraph::new(checkbox);
+    if component.is_task_selected() {
+        paragraph = paragraph.style(
+            Style::default()
+                .fg(color_config().link_selected_fg_color)
+                .bg(color_config().link_selected_bg_color),
+        );
+    }
 
     paragraph.render(area, buf);
 
@@ -0,0 +1,204 @@
+use std::{cmp, fmt::Write};
+
+use unicode_width::UnicodeWidthStr;
+
+use ratatui::{
+    backend::IntoCrossterm,
+    buffer::Buffer,
+    crossterm::style::ContentStyle,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+use crate::nodes::root::{Component, ComponentProps, ComponentRoot};
+
+/// Render every visible component of `markdown` into ANSI-styled text, one
+/// terminal line per rendered row. This goes through the same widgets as
+/// the interactive viewer, so headings, code highlighting, tables and
+/// quotes look the same, just without the alternate screen.
+pub fn render_to_ansi(markdown: &mut ComponentRoot, width: u16) -> String {
+    let mut out = String::new();
+
+    for child in markdown.children_mut() {
+        let height = child.height();
+        if height == 0 {
+            continue;
+        }
+
+        child.set_y_offset(0);
+        child.set_scroll_offset(0);
+
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+
+        match child {
+            Component::TextComponent(comp) => {
+                if comp.is_hidden() {
+                    continue;
+                }
+                comp.clone().render(area, &mut buf);
+            }
+            Component::Image(img) => {
+                let _ = writeln!(out, "[{}]", img.alt_text());
+                continue;
+            }
+        }
+
+        write_buffer(&buf, &mut out);
+    }
+
+    out
+}
+
+/// The lines `top` to `top + height` of `markdown` as shown in the viewer,
+/// as plain text without trailing blanks.
+#[must_use]
+pub fn render_to_text(markdown: &ComponentRoot, width: u16, top: u16, height: u16) -> String {
+    let bottom = top.saturating_add(height);
+    let mut lines = Vec::new();
+    let mut y_offset = 0;
+
+    for child in markdown.children() {
+        let start = y_offset;
+        let height = child.height();
+        y_offset += height;
+        if height == 0 || start + height <= top || start >= bottom {
+            continue;
+        }
+        let rows = top.saturating_sub(start)..cmp::min(bottom - start, height);
+
+        match child {
+            Component::TextComponent(comp) => {
+                if comp.is_hidden() {
+                    continue;
+                }
+                let mut comp = comp.clone();
+                comp.set_y_offset(0);
+                comp.set_scroll_offset(0);
+
+                let area = Rect::new(0, 0, width, height);
+                let mut buf = Buffer::empty(area);
+                comp.render(area, &mut buf);
+                lines.extend(rows.map(|y| plain_row(&buf, y)));
+            }
+            Component::Image(img) if rows.start == 0 => {
+                lines.push(format!("[{}]", img.alt_text()));
+            }
+            Component::Image(_) => {}
+        }
+    }
+
+    lines.join("\n").trim_matches('\n').to_owned()
+}
+
+fn plain_row(buf: &Buffer, y: u16) -> String {
+    let area = buf.area();
+    let mut row = String::new();
+    let mut to_skip: usize = 0;
+    for cell in (area.left()..area.right()).filter_map(|x| buf.cell((x, y))) {
+        if to_skip > 0 {
+            to_skip -= 1;
+            continue;
+        }
+        to_skip = cell.symbol().width().saturating_sub(1);
+        row.push_str(cell.symbol());
+    }
+    row.trim_end().to_owned()
+}
+
+/// Append the rows of `buf` to `out`, grouping runs of equally styled
+/// cells into a single escape sequence and dropping trailing blanks.
+fn write_buffer(buf: &Buffer, out: &mut String) {
+    let area = buf.area();
+    for y in area.top()..area.bottom() {
+        let row: Vec<_> = (area.left()..area.right())
+            .filter_map(|x| buf.cell((x, y)))
+            .collect();
+
+        let end = row
+            .iter()
+            .rposition(|c| c.symbol() != " " || c.bg != Color::Reset)
+            .map_or(0, |i| i + 1);
+
+        let mut run = String::new();
+        let mut run_style = Style::default();
+        // Cells covered by a wide character are blanks that the terminal
+        // backend skips over as well.
+        let mut to_skip: usize = 0;
+        for cell in &row[..end] {
+            if to_skip > 0 {
+                to_skip = to_skip.saturating_sub(1);
+                continue;
+            }
+            to_skip = cell.symbol().width().saturating_sub(1);
+            let style = cell.style();
+            if style != run_style && !run.is_empty() {
+                push_styled(out, &run, run_style);
+                run.clear();
+            }
+            run_style = style;
+            run.push_str(cell.symbol());
+        }
+        if !run.is_empty() {
+            push_styled(out, &run, run_style);
+        }
+        out.push('\n');
+    }
+}
+
+fn push_styled(out: &mut String, text: &str, style: Style) {
+    let plain = [None, Some(Color::Reset)];
+    if plain.contains(&style.fg) && plain.contains(&style.bg) && style.add_modifier.is_empty() {
+        out.push_str(text);
+        return;
+    }
+    let style: ContentStyle = Style {
+        underline_color: None,
+        ..style
+    }
+    .into_crossterm();
+    let _ = write!(out, "{}", style.apply(text));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_markdown;
+
+    #[test]
+    fn plain_text_is_printed_without_escapes() {
+        let mut root = parse_markdown(None, "Just a paragraph.\n", 40);
+        let output = render_to_ansi(&mut root, 40);
+        assert_eq!(output.trim_end(), "Just a paragraph.");
+    }
+
+    #[test]
+    fn text_in_view_is_rendered_plain() {
+        let md = "# Title\n\nSome **bold** text\n\n- one\n- two\n";
+        let root = parse_markdown(None, md, 40);
+        assert_eq!(
+            render_to_text(&root, 40, 0, 3)
+                .lines()
+                .map(str::trim)
+                .collect::<Vec<_>>(),
+            ["Title", "", "Some bold text"]
+        );
+        assert_eq!(render_to_text(&root, 40, 4, 20), "• one\n• two");
+        assert_eq!(render_to_text(&root, 40, 100, 20), "");
+    }
+
+    #[test]
+    fn styled_text_keeps_content_in_order() {
+        let mut root = parse_markdown(None, "# Title\n\nSome **bold** text\n", 40);
+        let output = render_to_ansi(&mut root, 40);
+        assert!(output.contains("Title"), "got {output:?}");
+        assert!(
+            output.contains("\u{1b}["),
+            "expected ANSI escapes, got {output:?}"
+        );
+        let bold = output.find("bold").expect("bold word missing");
+        let some = output.find("Some").expect("leading word missing");
+        assert!(some < bold);
+    }
+}
@@ -1,4 +1,8 @@
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::{
+    cmp,
+    ops::Range,
+    sync::atomic::{AtomicU32, Ordering},
+};
 
 use image::ImageReader;
 use itertools::Itertools;
@@ -9,13 +13,24 @@ use pest::{
 use pest_derive::Parser;
 use ratatui::style::Color;
 
-use crate::nodes::{
-    image::ImageComponent,
-    root::{Component, ComponentRoot},
-    textcomponent::{TextComponent, TextNode},
-    word::{MetaData, Word, WordType},
+use crate::{
+    math::{latex_to_lines, latex_to_unicode},
+    nodes::{
+        image::ImageComponent,
+        root::{Component, ComponentRoot},
+        textcomponent::{TextComponent, TextNode},
+        word::{MetaData, Word, WordType},
+    },
+    util::general::GENERAL_CONFIG,
 };
 
+mod abbreviations;
+mod definitions;
+mod front_matter;
+mod html;
+mod list;
+mod references;
+
 /// Process-wide monotonic counter for assigning unique IDs to `<details>`
 /// blocks. Each parsed details summary gets a fresh ID so it can be addressed
 /// by the runtime fold-toggle and selector independently of its position in
@@ -51,8 +66,12 @@ pub fn parse_markdown(name: Option<&str>, content: &str, width: u16) -> Componen
 
     let root_pair = root.into_iter().next().unwrap();
 
-    let children = parse_text(root_pair)
-        .children_owned()
+    let lines = LineIndex::new(content);
+    let mut children = parse_text(root_pair, &lines).children_owned();
+    references::resolve_references(&mut children);
+    html::resolve_html(&mut children);
+    abbreviations::resolve_abbreviations(&mut children);
+    let children = children
         .into_iter()
         .dedup_by(|x, y| {
             x.kind() == MdParseEnum::BlockSeparator && y.kind == MdParseEnum::BlockSeparator
@@ -68,22 +87,58 @@ pub fn parse_markdown(name: Option<&str>, content: &str, width: u16) -> Componen
     root
 }
 
-fn parse_text(pair: Pair<'_, Rule>) -> ParseNode {
-    let content = if pair.as_rule() == Rule::code_line {
+/// Where each line of the source starts, to turn byte offsets into lines.
+struct LineIndex<'a> {
+    source: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(source: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { source, starts }
+    }
+
+    fn line(&self, byte: usize) -> usize {
+        self.starts.partition_point(|start| *start <= byte) - 1
+    }
+
+    /// The span of `bytes`, without the line breaks many rules start or
+    /// end with.
+    fn span(&self, bytes: Range<usize>) -> SourceSpan {
+        let text = &self.source[bytes.clone()];
+        let start = bytes.start + (text.len() - text.trim_start_matches(['\r', '\n']).len());
+        let end = cmp::max(
+            start,
+            bytes.end - (text.len() - text.trim_end_matches(['\r', '\n']).len()),
+        );
+        let last = if end > start { end - 1 } else { start };
+        SourceSpan {
+            bytes: start..end,
+            lines: self.line(start)..self.line(last) + 1,
+        }
+    }
+}
+
+fn parse_text(pair: Pair<'_, Rule>, lines: &LineIndex) -> ParseNode {
+    let content = if matches!(pair.as_rule(), Rule::code_line | Rule::front_matter_content) {
         pair.as_str().replace('\t', "    ").replace('\r', "")
     } else {
         pair.as_str().replace('\n', " ")
     };
     let mut component = ParseNode::new(pair.as_rule().into(), content);
-    let children = parse_node_children(pair.into_inner());
+    component.span = lines.span(pair.as_span().start()..pair.as_span().end());
+    let children = parse_node_children(pair.into_inner(), lines);
     component.add_children(children);
     component
 }
 
-fn parse_node_children(pair: Pairs<'_, Rule>) -> Vec<ParseNode> {
+fn parse_node_children(pair: Pairs<'_, Rule>, lines: &LineIndex) -> Vec<ParseNode> {
     let mut children = Vec::new();
     for inner_pair in pair {
-        children.push(parse_text(inner_pair));
+        children.push(parse_text(inner_pair, lines));
     }
     children
 }
@@ -99,16 +154,26 @@ fn node_to_component(root: ParseRoot) -> ComponentRoot {
 }
 
 fn parse_components(parse_node: ParseNode) -> Vec<Component> {
-    if parse_node.kind() == MdParseEnum::Details {
-        return parse_details(parse_node);
+    match parse_node.kind() {
+        MdParseEnum::Details => return parse_details(parse_node),
+        MdParseEnum::ListContainer => return list::parse_list(parse_node),
+        MdParseEnum::FrontMatter => return front_matter::parse_front_matter(parse_node),
+        MdParseEnum::DefinitionList => return definitions::parse_definition_list(parse_node),
+        _ => (),
+    }
+    let span = parse_node.span().clone();
+    let mut component = parse_component(parse_node);
+    if let Component::TextComponent(comp) = &mut component {
+        comp.set_source(span);
     }
-    vec![parse_component(parse_node)]
+    vec![component]
 }
 
 fn parse_details(parse_node: ParseNode) -> Vec<Component> {
     let mut header_text = String::from("Details");
     let mut body_components: Vec<Component> = Vec::new();
     let mut open_attr_present = false;
+    let span = parse_node.span().clone();
 
     for child in parse_node.children_owned() {
         match child.kind() {
@@ -143,15 +208,19 @@ fn parse_details(parse_node: ParseNode) -> Vec<Component> {
     let body_len = body_components.len();
     let folded = !open_attr_present;
 
-    let mut out = Vec::with_capacity(1 + body_len);
-    out.push(Component::TextComponent(TextComponent::new(
+    // The summary stands for the whole block in the source
+    let mut summary = TextComponent::new(
         TextNode::DetailsSummary {
             id,
             folded,
             body_len,
         },
         vec![Word::new(header_text, WordType::Normal)],
-    )));
+    );
+    summary.set_source(span);
+
+    let mut out = Vec::with_capacity(1 + body_len);
+    out.push(Component::TextComponent(summary));
     out.extend(body_components);
     out
 }
@@ -169,6 +238,8 @@ fn parse_component(parse_node: ParseNode) -> Component {
             for node in leaf_nodes {
                 if node.kind() == MdParseEnum::AltText {
                     node.content().clone_into(&mut alt_text);
+                } else if !GENERAL_CONFIG.images {
+                    continue;
                 } else if is_url(node.content()) {
                     #[cfg(feature = "network")]
                     {
@@ -205,6 +276,11 @@ fn parse_component(parse_node: ParseNode) -> Component {
                     let comp = TextComponent::new(TextNode::Paragraph, word.into());
                     Component::TextComponent(comp)
                 }
+            } else if !GENERAL_CONFIG.images {
+                let word = [Word::new(format!("[{alt_text}]"), WordType::Normal)];
+
+                let comp = TextComponent::new(TextNode::Paragraph, word.into());
+                Component::TextComponent(comp)
             } else {
                 let word = [
                     Word::new("Image".to_string(), WordType::Normal),
@@ -223,33 +299,6 @@ fn parse_component(parse_node: ParseNode) -> Component {
             }
         }
 
-        MdParseEnum::Task => {
-            let leaf_nodes = get_leaf_nodes(parse_node);
-            let mut words = Vec::new();
-            for node in leaf_nodes {
-                let word_type = WordType::from(node.kind());
-
-                let mut content: String = node
-                    .content()
-                    .chars()
-                    .dedup_by(|x, y| *x == ' ' && *y == ' ')
-                    .collect();
-
-                if matches!(node.kind(), MdParseEnum::WikiLink | MdParseEnum::InlineLink) {
-                    let comp = Word::new(content.clone(), WordType::LinkData);
-                    words.push(comp);
-                }
-
-                if content.starts_with(' ') {
-                    content.remove(0);
-                    let comp = Word::new(" ".to_owned(), word_type);
-                    words.push(comp);
-                }
-                words.push(Word::new(content, word_type));
-            }
-            Component::TextComponent(TextComponent::new(TextNode::Task, words))
-        }
-
         MdParseEnum::Quote => {
             let leaf_nodes = get_leaf_nodes(parse_node);
             let mut words = Vec::new();
@@ -275,11 +324,17 @@ fn parse_component(parse_node: ParseNode) -> Component {
         }
 
         MdParseEnum::Heading => {
-            let indent = parse_node
+            // Setext headings have no `#`s, only an underline
+            let indent = match parse_node
                 .content()
                 .chars()
                 .take_while(|c| *c == '#')
-                .count();
+                .count()
+            {
+                0 if parse_node.content().trim_end().ends_with('=') => 1,
+                0 => 2,
+                indent => indent,
+            };
             let leaf_nodes = get_leaf_nodes(parse_node);
             let mut words = Vec::new();
 
@@ -326,6 +381,37 @@ fn parse_component(parse_node: ParseNode) -> Component {
                 w.set_content(w.content().trim_start().to_owned());
             }
 
+            // Closing `#`s and a `{#id}` at the end are not part of the text
+            let mut id = None;
+            loop {
+                while words
+                    .last()
+                    .is_some_and(|w| w.is_renderable() && w.content().trim().is_empty())
+                {
+                    words.pop();
+                }
+                let Some(last) = words.last().filter(|w| w.kind() == WordType::Normal) else {
+                    break;
+                };
+                if last.content().chars().all(|c| c == '#') && words.len() > 2 {
+                    words.pop();
+                } else if let Some(anchor) = last
+                    .content()
+                    .strip_prefix("{#")
+                    .and_then(|c| c.strip_suffix('}'))
+                    .filter(|c| !c.is_empty() && id.is_none())
+                {
+                    id = Some(Word::new(
+                        anchor.to_owned(),
+                        WordType::MetaInfo(MetaData::HeadingId),
+                    ));
+                    words.pop();
+                } else {
+                    break;
+                }
+            }
+            words.extend(id);
+
             Component::TextComponent(TextComponent::new(TextNode::Heading, words))
         }
 
@@ -360,6 +446,19 @@ fn parse_component(parse_node: ParseNode) -> Component {
 
             let mut space_indented = false;
 
+            let code = code_word(leaf_nodes.iter().filter_map(|node| {
+                let line = node.content().strip_prefix(['\n', ' '])?;
+                match node.kind() {
+                    MdParseEnum::CodeBlockStr => Some(line),
+                    MdParseEnum::CodeBlockStrSpaceIndented => Some(
+                        line.strip_prefix("    ")
+                            .or_else(|| line.strip_prefix('\t'))
+                            .unwrap_or(line),
+                    ),
+                    _ => None,
+                }
+            }));
+
             for node in leaf_nodes {
                 if node.kind() == MdParseEnum::CodeBlockStrSpaceIndented {
                     space_indented = true;
@@ -375,102 +474,86 @@ fn parse_component(parse_node: ParseNode) -> Component {
                     WordType::CodeBlock(Color::Reset),
                 )]);
             }
+            if let Some(row) = words.last_mut() {
+                row.push(code);
+            }
 
             Component::TextComponent(TextComponent::new_formatted(TextNode::CodeBlock, words))
         }
 
-        MdParseEnum::ListContainer => {
-            let mut words = Vec::new();
-            for child in parse_node.children_owned() {
-                let kind = child.kind();
-                let leaf_nodes = get_leaf_nodes(child);
-                let mut inner_words = Vec::new();
-                for node in leaf_nodes {
-                    let word_type = WordType::from(node.kind());
-
-                    let mut content = match node.kind() {
-                        MdParseEnum::Indent => node.content().to_owned(),
-                        _ => node
-                            .content()
-                            .chars()
-                            .dedup_by(|x, y| *x == ' ' && *y == ' ')
-                            .collect(),
-                    };
-
-                    if matches!(node.kind(), MdParseEnum::WikiLink | MdParseEnum::InlineLink) {
-                        let comp = Word::new(content.clone(), WordType::LinkData);
-                        inner_words.push(comp);
-                    }
-                    if content.starts_with(' ') && node.kind() != MdParseEnum::Indent {
-                        content.remove(0);
-                        let comp = Word::new(" ".to_owned(), word_type);
-                        inner_words.push(comp);
-                    }
-
-                    inner_words.push(Word::new(content, word_type));
-                }
-                if kind == MdParseEnum::UnorderedList {
-                    inner_words.push(Word::new(
-                        "X".to_owned(),
-                        WordType::MetaInfo(MetaData::UList),
-                    ));
-                    let list_symbol = Word::new("• ".to_owned(), WordType::ListMarker);
-                    inner_words.insert(1, list_symbol);
-                } else if kind == MdParseEnum::OrderedList {
-                    inner_words.push(Word::new(
-                        "X".to_owned(),
-                        WordType::MetaInfo(MetaData::OList),
-                    ));
-                }
-                words.push(inner_words);
-            }
-            Component::TextComponent(TextComponent::new_formatted(TextNode::List, words))
-        }
-
         MdParseEnum::Table => {
-            let mut words = Vec::new();
-            let mut meta_info = Vec::new();
-            for cell in parse_node.children_owned() {
-                if cell.kind() == MdParseEnum::TableSeparator {
-                    meta_info.push(Word::new(
-                        cell.content().to_owned(),
+            let (separators, rows): (Vec<_>, Vec<_>) = parse_node
+                .children_owned()
+                .into_iter()
+                .partition(|c| c.kind() == MdParseEnum::TableSeparator);
+            // The `:---:` of each column, which also sets how many there are
+            let meta_info = separators
+                .iter()
+                .map(|separator| {
+                    Word::new(
+                        separator.content().trim().to_owned(),
                         WordType::MetaInfo(MetaData::ColumnsCount),
-                    ));
-                    continue;
-                }
-                let mut inner_words = Vec::new();
+                    )
+                })
+                .collect_vec();
 
-                if cell.children().is_empty() {
-                    words.push(inner_words);
-                    continue;
-                }
+            let mut words = Vec::new();
+            for row in rows {
+                // Missing cells are left empty and extra ones dropped
+                let mut cells = row.children_owned();
+                cells.resize_with(meta_info.len(), || {
+                    ParseNode::new(MdParseEnum::TableCell, String::new())
+                });
+
+                for cell in cells {
+                    let mut inner_words = Vec::new();
+
+                    if cell.children().is_empty() {
+                        words.push(inner_words);
+                        continue;
+                    }
 
-                for word in get_leaf_nodes(cell) {
-                    let word_type = WordType::from(word.kind());
-                    let mut content = word.content().to_owned();
+                    for word in get_leaf_nodes(cell) {
+                        let word_type = WordType::from(word.kind());
+                        // A `\|` is a pipe in the cell, also in code
+                        let mut content = word.content().replace("\\|", "|");
 
-                    if matches!(word.kind(), MdParseEnum::WikiLink | MdParseEnum::InlineLink) {
-                        let comp = Word::new(content.clone(), WordType::LinkData);
-                        inner_words.push(comp);
-                    }
+                        if matches!(word.kind(), MdParseEnum::WikiLink | MdParseEnum::InlineLink) {
+                            let comp = Word::new(content.clone(), WordType::LinkData);
+                            inner_words.push(comp);
+                        }
 
-                    if content.starts_with(' ') {
-                        content.remove(0);
-                        let comp = Word::new(" ".to_owned(), word_type);
-                        inner_words.push(comp);
-                    }
+                        if content.starts_with(' ') {
+                            content.remove(0);
+                            let comp = Word::new(" ".to_owned(), word_type);
+                            inner_words.push(comp);
+                        }
 
-                    inner_words.push(Word::new(content, word_type));
+                        inner_words.push(Word::new(content, word_type));
+                    }
+                    words.push(inner_words);
                 }
-                words.push(inner_words);
             }
             Component::TextComponent(TextComponent::new_formatted_with_meta(
-                TextNode::Table(vec![], vec![]),
+                TextNode::Table(vec![], vec![], vec![]),
                 words,
                 meta_info,
             ))
         }
 
+        MdParseEnum::MathBlock => {
+            let source: String = parse_node
+                .children()
+                .iter()
+                .map(ParseNode::content)
+                .collect();
+            let lines = latex_to_lines(&source)
+                .into_iter()
+                .map(|line| vec![Word::new(line, WordType::Math)])
+                .collect();
+            Component::TextComponent(TextComponent::new_formatted(TextNode::MathBlock, lines))
+        }
+
         MdParseEnum::BlockSeparator => {
             Component::TextComponent(TextComponent::new(TextNode::LineBreak, Vec::new()))
         }
@@ -495,6 +578,49 @@ fn parse_component(parse_node: ParseNode) -> Component {
     }
 }
 
+/// A meta word with the code of a code block, from its `lines` without the
+/// fences or the indentation, so it can be copied out as written.
+fn code_word<'a>(lines: impl IntoIterator<Item = &'a str>) -> Word {
+    let code = lines.into_iter().join("\n");
+    Word::new(
+        code.trim_end_matches('\n').to_owned(),
+        WordType::MetaInfo(MetaData::Code),
+    )
+}
+
+/// Remove every `kind` node in `nodes` and below, which define something
+/// for the rest of the document, and collect the `(key, value)` `extract`
+/// reads from each, in document order. The first definition of a key wins.
+fn take_definitions(
+    nodes: &mut Vec<ParseNode>,
+    kind: MdParseEnum,
+    extract: impl Fn(&ParseNode) -> Option<(String, String)>,
+) -> Vec<(String, String)> {
+    fn walk(
+        nodes: &mut Vec<ParseNode>,
+        kind: MdParseEnum,
+        extract: &dyn Fn(&ParseNode) -> Option<(String, String)>,
+        definitions: &mut Vec<(String, String)>,
+    ) {
+        nodes.retain_mut(|node| {
+            if node.kind() != kind {
+                walk(&mut node.children, kind, extract, definitions);
+                return true;
+            }
+            if let Some((key, value)) = extract(node)
+                && !definitions.iter().any(|(k, _)| *k == key)
+            {
+                definitions.push((key, value));
+            }
+            false
+        });
+    }
+
+    let mut definitions = Vec::new();
+    walk(nodes, kind, &extract, &mut definitions);
+    definitions
+}
+
 fn get_leaf_nodes(node: ParseNode) -> Vec<ParseNode> {
     let mut leaf_nodes = Vec::new();
 
@@ -508,6 +634,24 @@ fn get_leaf_nodes(node: ParseNode) -> Vec<ParseNode> {
         leaf_nodes.push(comp);
     }
 
+    // Inline math is converted as a whole, since TeX commands span words
+    if node.kind() == MdParseEnum::MathStr {
+        let source: String = node.children().iter().map(ParseNode::content).collect();
+        let math = latex_to_unicode(&source);
+        for (i, word) in math.split(' ').filter(|w| !w.is_empty()).enumerate() {
+            let separator = if i > 0 || node.content().starts_with(' ') {
+                " "
+            } else {
+                ""
+            };
+            leaf_nodes.push(ParseNode::new(
+                MdParseEnum::Math,
+                format!("{separator}{word}"),
+            ));
+        }
+        return leaf_nodes;
+    }
+
     if matches!(
         node.kind(),
         MdParseEnum::CodeStr
@@ -515,6 +659,7 @@ fn get_leaf_nodes(node: ParseNode) -> Vec<ParseNode> {
             | MdParseEnum::BoldStr
             | MdParseEnum::BoldItalicStr
             | MdParseEnum::StrikethroughStr
+            | MdParseEnum::HighlightStr
     ) && node.content().starts_with(' ')
     {
         let comp = ParseNode::new(MdParseEnum::Word, " ".to_owned());
@@ -532,6 +677,7 @@ fn get_leaf_nodes(node: ParseNode) -> Vec<ParseNode> {
                 | MdParseEnum::BoldStr
                 | MdParseEnum::BoldItalicStr
                 | MdParseEnum::StrikethroughStr
+                | MdParseEnum::HighlightStr
                 | MdParseEnum::CodeStr
         ) {
             leaf_nodes.push(node);
@@ -567,6 +713,22 @@ fn print_component(component: &TextComponent, _depth: usize) {
     });
 }
 
+/// A range of the parsed source, in bytes and in zero-based lines. Both
+/// ranges are half-open and leave out the line breaks around a block.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceSpan {
+    pub bytes: Range<usize>,
+    pub lines: Range<usize>,
+}
+
+impl SourceSpan {
+    /// First line of the span counted from one, as editors do.
+    #[must_use]
+    pub fn first_line(&self) -> usize {
+        self.lines.start + 1
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ParseRoot {
     file_name: Option<String>,
@@ -603,6 +765,9 @@ pub struct ParseNode {
     kind: MdParseEnum,
     content: String,
     children: Vec<ParseNode>,
+    /// Where in the source the node was parsed from. Empty for nodes made
+    /// up while building components.
+    span: SourceSpan,
 }
 
 impl ParseNode {
@@ -612,9 +777,15 @@ impl ParseNode {
             kind,
             content,
             children: Vec::new(),
+            span: SourceSpan::default(),
         }
     }
 
+    #[must_use]
+    pub fn span(&self) -> &SourceSpan {
+        &self.span
+    }
+
     #[must_use]
     pub fn kind(&self) -> MdParseEnum {
         self.kind
@@ -642,6 +813,10 @@ impl ParseNode {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MdParseEnum {
+    Abbreviation,
+    AbbreviationData,
+    AbbreviationDefinition,
+    AlignCenter,
     AltText,
     BlockSeparator,
     Bold,
@@ -654,6 +829,9 @@ pub enum MdParseEnum {
     CodeBlockStr,
     CodeBlockStrSpaceIndented,
     CodeStr,
+    Definition,
+    DefinitionList,
+    DefinitionTerm,
     Details,
     DetailsBody,
     DetailsOpenAttr,
@@ -661,8 +839,13 @@ pub enum MdParseEnum {
     Digit,
     FootnoteRef,
     Footnote,
+    FrontMatter,
+    FrontMatterContent,
     Heading,
+    Highlight,
+    HighlightStr,
     HorizontalSeparator,
+    HtmlTag,
     Image,
     Imortant,
     Indent,
@@ -671,7 +854,17 @@ pub enum MdParseEnum {
     ItalicStr,
     Link,
     LinkData,
+    LinkLabel,
+    LinkReference,
+    LinkReferenceDefinition,
+    ListCodeBlock,
     ListContainer,
+    ListGap,
+    ListParagraph,
+    Math,
+    MathBlock,
+    MathBlockStr,
+    MathStr,
     Note,
     OrderedList,
     PLanguage,
@@ -682,8 +875,8 @@ pub enum MdParseEnum {
     StrikethroughStr,
     Table,
     TableCell,
+    TableRow,
     TableSeparator,
-    Task,
     TaskClosed,
     TaskOpen,
     Tip,
@@ -696,7 +889,11 @@ pub enum MdParseEnum {
 impl From<Rule> for MdParseEnum {
     fn from(value: Rule) -> Self {
         match value {
-            Rule::word | Rule::h_word | Rule::latex_word | Rule::t_word => Self::Word,
+            Rule::word | Rule::h_word | Rule::t_word => Self::Word,
+            Rule::latex_word => Self::Math,
+            Rule::latex => Self::MathStr,
+            Rule::math_block => Self::MathBlock,
+            Rule::math_block_content => Self::MathBlockStr,
             Rule::indent => Self::Indent,
             Rule::italic_word_var_1 | Rule::italic_word_var_2 => Self::Italic,
             Rule::italic_var_1 | Rule::italic_var_2 => Self::ItalicStr,
@@ -706,6 +903,8 @@ impl From<Rule> for MdParseEnum {
             Rule::bold_italic => Self::BoldItalicStr,
             Rule::strikethrough_word => Self::Strikethrough,
             Rule::strikethrough => Self::StrikethroughStr,
+            Rule::highlight_word => Self::Highlight,
+            Rule::highlight => Self::HighlightStr,
             Rule::code_word => Self::Code,
             Rule::code => Self::CodeStr,
             Rule::programming_language => Self::PLanguage,
@@ -721,24 +920,44 @@ impl From<Rule> for MdParseEnum {
             }
             Rule::sentence | Rule::t_sentence | Rule::footnote_sentence => Self::Sentence,
             Rule::table_cell => Self::TableCell,
+            Rule::table_row => Self::TableRow,
             Rule::table_separator => Self::TableSeparator,
             Rule::u_list => Self::UnorderedList,
             Rule::o_list => Self::OrderedList,
-            Rule::h1 | Rule::h2 | Rule::h3 | Rule::h4 | Rule::h5 | Rule::h6 | Rule::heading => {
-                Self::Heading
-            }
+            Rule::h1
+            | Rule::h2
+            | Rule::h3
+            | Rule::h4
+            | Rule::h5
+            | Rule::h6
+            | Rule::setext_h1
+            | Rule::setext_h2
+            | Rule::heading => Self::Heading,
             Rule::list_container => Self::ListContainer,
+            Rule::list_gap => Self::ListGap,
+            Rule::list_paragraph => Self::ListParagraph,
+            Rule::list_code_block => Self::ListCodeBlock,
             Rule::paragraph => Self::Paragraph,
             Rule::code_block | Rule::indented_code_block => Self::CodeBlock,
             Rule::table => Self::Table,
             Rule::quote => Self::Quote,
-            Rule::task => Self::Task,
             Rule::block_sep => Self::BlockSeparator,
             Rule::horizontal_sep => Self::HorizontalSeparator,
-            Rule::link_data | Rule::wiki_link_data => Self::LinkData,
+            Rule::link_data | Rule::wiki_link_data | Rule::link_reference_url => Self::LinkData,
+            Rule::link_label => Self::LinkLabel,
+            Rule::link_reference => Self::LinkReference,
+            Rule::link_reference_definition => Self::LinkReferenceDefinition,
             Rule::details => Self::Details,
             Rule::details_body => Self::DetailsBody,
             Rule::details_open_attr => Self::DetailsOpenAttr,
+            Rule::abbreviation_term => Self::Abbreviation,
+            Rule::abbreviation_text => Self::AbbreviationData,
+            Rule::abbreviation_definition => Self::AbbreviationDefinition,
+            Rule::definition_list => Self::DefinitionList,
+            Rule::definition_term => Self::DefinitionTerm,
+            Rule::definition => Self::Definition,
+            Rule::front_matter => Self::FrontMatter,
+            Rule::front_matter_content => Self::FrontMatterContent,
             Rule::summary | Rule::summary_text => Self::DetailsSummary,
             Rule::warning => Self::Warning,
             Rule::note => Self::Note,
@@ -751,16 +970,15 @@ impl From<Rule> for MdParseEnum {
             | Rule::wiki_link_char
             | Rule::normal
             | Rule::t_normal
-            | Rule::latex
             | Rule::comment
             | Rule::txt
-            | Rule::task_prefix
             | Rule::quote_prefix
             | Rule::code_block_prefix
             | Rule::table_prefix
             | Rule::list_prefix
             | Rule::forbidden_sentence_prefix => Self::Paragraph,
             Rule::image => Self::Image,
+            Rule::html_tag => Self::HtmlTag,
             Rule::alt_word | Rule::alt_text => Self::AltText,
             Rule::footnote_ref => Self::FootnoteRef,
             Rule::footnote => Self::Footnote,
@@ -773,6 +991,23 @@ impl From<Rule> for MdParseEnum {
             | Rule::i_char_var_1
             | Rule::i_char_var_2
             | Rule::latex_char
+            | Rule::math_char
+            | Rule::html_name
+            | Rule::html_attr_name
+            | Rule::html_attr_value
+            | Rule::html_attr
+            | Rule::front_matter_line
+            | Rule::m_char
+            | Rule::definition_marker
+            | Rule::definition_line
+            | Rule::definition_group
+            | Rule::table_delimiter
+            | Rule::yaml_front_matter
+            | Rule::toml_front_matter
+            | Rule::setext_text
+            | Rule::link_reference_title
+            | Rule::setext_underline
+            | Rule::EOI
             | Rule::quote_marking
             | Rule::inline_link_char
             | Rule::s_char
@@ -790,7 +1025,13 @@ impl From<Rule> for MdParseEnum {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::nodes::textcomponent::TextNode;
+    use crate::nodes::{
+        root::ClickTarget,
+        textcomponent::TextNode,
+        word::{Word, WordType},
+    };
+    use crate::util::toggle_checkbox;
+    use ratatui::layout::Alignment;
 
     fn component_kinds(md: &str) -> Vec<TextNode> {
         parse_markdown(None, md, 80)
@@ -879,6 +1120,248 @@ mod tests {
         );
     }
 
+    #[test]
+    fn details_folds_survive_a_reparse() {
+        let md = "<details>\n<summary>A</summary>\n\nbody\n\n</details>\n\n<details open>\n<summary>B</summary>\n\nbody\n\n</details>\n";
+        let mut root = parse_markdown(None, md, 80);
+        assert_eq!(root.details_folds(), vec![true, false]);
+        let folded_height = root.height();
+
+        let mut reparsed = parse_markdown(None, md, 80);
+        reparsed.set_details_folds(&[false, false]);
+        assert_eq!(reparsed.details_folds(), vec![false, false]);
+        assert!(reparsed.height() > folded_height);
+
+        // A different number of blocks means the document changed
+        root.set_details_folds(&[false]);
+        assert_eq!(root.details_folds(), vec![true, false]);
+    }
+
+    #[test]
+    fn clicks_hit_links_and_summaries() {
+        let md = "see [one](a.md) and [two](b.md)\n\n<details>\n<summary>S</summary>\n\nbody\n\n</details>\n";
+        let mut root = parse_markdown(None, md, 80);
+        root.set_scroll(0);
+        let row = |line: &str| {
+            root.content()
+                .iter()
+                .position(|l| l.contains(line))
+                .unwrap() as u16
+        };
+        let links = row("see");
+        let column = |text: &str| root.content()[links as usize].find(text).unwrap() as u16;
+
+        assert_eq!(
+            root.click_target(links, column("one"), 80),
+            Some(ClickTarget::Link(0))
+        );
+        assert_eq!(
+            root.click_target(links, column("two") + 1, 80),
+            Some(ClickTarget::Link(1))
+        );
+        assert_eq!(root.click_target(links, column("and"), 80), None);
+        assert_eq!(
+            root.click_target(row("S"), 0, 80),
+            Some(ClickTarget::Details(0))
+        );
+    }
+
+    #[test]
+    fn math_is_rendered_as_unicode() {
+        let md = "Let $\\alpha + \\beta^2$ hold.\n\n$$\n\\frac{1}{n}\n$$\n\nafter\n";
+        let root = parse_markdown(None, md, 80);
+        let components = root.components();
+
+        let math: Vec<&str> = components[0]
+            .content()
+            .iter()
+            .flatten()
+            .filter(|w| w.kind() == WordType::Math)
+            .map(|w| w.content())
+            .collect();
+        assert_eq!(math.concat(), " α + β²");
+
+        let block = components
+            .iter()
+            .find(|c| c.kind() == TextNode::MathBlock)
+            .unwrap();
+        let lines: Vec<String> = block
+            .content()
+            .iter()
+            .map(|l| l.iter().map(Word::content).collect())
+            .collect();
+        assert_eq!(lines, vec![" 1 ", "───", " n "]);
+        assert!(root.content().iter().any(|l| l.contains("after")));
+    }
+
+    #[test]
+    fn toggled_task_is_written_at_its_marker() {
+        let md = "# Todo\n\n- [ ] first\n- [x] second\n";
+        let mut root = parse_markdown(Some("todo.md"), md, 80);
+        assert_eq!(root.task_index_and_height().len(), 2);
+
+        root.select_task(1).unwrap();
+        let offset = root.selected_task_marker().unwrap();
+        assert_eq!(&md[offset..offset + 3], "[x]");
+
+        let toggled = toggle_checkbox(md, offset).unwrap();
+        assert_eq!(toggled, "# Todo\n\n- [ ] first\n- [ ] second\n");
+        assert_eq!(toggle_checkbox(md, offset + 1), None);
+    }
+
+    #[test]
+    fn nested_tasks_are_toggled_in_place() {
+        let md = "- [ ] parent\n  1. [x] child\n";
+        let mut root = parse_markdown(None, md, 80);
+        root.select_task(1).unwrap();
+        let offset = root.selected_task_marker().unwrap();
+        assert_eq!(
+            toggle_checkbox(md, offset).unwrap(),
+            "- [ ] parent\n  1. [ ] child\n"
+        );
+    }
+
+    #[test]
+    fn nested_lists_are_renumbered_and_indented() {
+        let md = "3. one\n4. two\n   9. nested\n   9. again\n\n      More text.\n\n      ```sh\n      ls\n      ```\n   * bullet\n     - deeper\n5. three\n";
+        let root = parse_markdown(None, md, 80);
+        let rows: Vec<(TextNode, u16, String)> = root
+            .components()
+            .into_iter()
+            .filter(|c| c.kind() != TextNode::LineBreak)
+            .map(|c| {
+                let first = c
+                    .content()
+                    .first()
+                    .map(|line| line.iter().map(Word::content).collect::<String>());
+                (c.kind(), c.indent(), first.unwrap_or_default())
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (TextNode::List, 0, "3. one".to_owned()),
+                (TextNode::List, 0, "4. two".to_owned()),
+                (TextNode::List, 3, " 9. nested".to_owned()),
+                (TextNode::List, 3, "10. again".to_owned()),
+                (TextNode::Paragraph, 7, "More text.".to_owned()),
+                (TextNode::CodeBlock, 7, String::new()),
+                (TextNode::List, 3, "◦ bullet".to_owned()),
+                (TextNode::List, 5, "▪ deeper".to_owned()),
+                (TextNode::List, 0, "5. three".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn deeply_nested_lists_fit_narrow_views() {
+        let nested = |depth: usize, item: &str| {
+            (0..depth)
+                .map(|level| format!("{}{item} step {level}\n", "  ".repeat(level)))
+                .collect::<String>()
+        };
+        for (md, width) in [
+            (nested(5, "-"), 10),
+            (nested(12, "-"), 24),
+            (nested(3, "- [ ]"), 8),
+        ] {
+            let root = parse_markdown(None, &md, width);
+            let text = root.content().join(" ");
+            assert!(text.contains('0') && text.contains('2'), "got {text:?}");
+        }
+    }
+
+    #[test]
+    fn lists_end_where_commonmark_ends_them() {
+        let rows = |md: &str| -> Vec<(TextNode, u16, String)> {
+            parse_markdown(None, md, 80)
+                .components()
+                .into_iter()
+                .filter(|c| c.kind() != TextNode::LineBreak)
+                .map(|c| {
+                    let first = c
+                        .content()
+                        .first()
+                        .map(|line| line.iter().map(Word::content).collect::<String>());
+                    (c.kind(), c.indent(), first.unwrap_or_default())
+                })
+                .collect()
+        };
+
+        // More than nine digits is no list, and nine count on without overflow
+        assert_eq!(
+            rows("18446744073709551615. x\n18446744073709551615. y\n")[0].0,
+            TextNode::Paragraph
+        );
+        assert_eq!(
+            rows("999999999. a\n999999999. b\n"),
+            vec![
+                (TextNode::List, 0, " 999999999. a".to_owned()),
+                (TextNode::List, 0, "1000000000. b".to_owned()),
+            ]
+        );
+        assert_eq!(
+            rows("1. a\n2. b\n5) z\n"),
+            vec![
+                (TextNode::List, 0, "1. a".to_owned()),
+                (TextNode::List, 0, "2. b".to_owned()),
+                (TextNode::List, 0, "5) z".to_owned()),
+            ]
+        );
+        assert_eq!(
+            rows("1. a\n\n   b\n\n  c\n"),
+            vec![
+                (TextNode::List, 0, "1. a".to_owned()),
+                (TextNode::Paragraph, 3, "b".to_owned()),
+                (TextNode::Paragraph, 0, "c".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn code_blocks_keep_their_code_as_written() {
+        let md = "```rust\nfn main() {\n    let x = 1;\n}\n```\n\n    indented\n      more\n\n- item\n\n  ```\n  in a list\n  ```\n";
+        let root = parse_markdown(None, md, 80);
+        let code = root
+            .components()
+            .into_iter()
+            .filter_map(TextComponent::code)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            code,
+            [
+                "fn main() {\n    let x = 1;\n}",
+                "indented\n  more",
+                "in a list"
+            ]
+        );
+    }
+
+    #[test]
+    fn components_know_their_source_lines() {
+        let md = "# Title\n\nfirst line\nsecond line\n\n```rust\nlet x = 1;\n```\n\n- [ ] task\n";
+        let root = parse_markdown(None, md, 80);
+        let spans: Vec<(TextNode, Range<usize>)> = root
+            .components()
+            .into_iter()
+            .filter(|c| c.kind() != TextNode::LineBreak)
+            .map(|c| (c.kind(), c.source().lines.clone()))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (TextNode::Heading, 0..1),
+                (TextNode::Paragraph, 2..4),
+                (TextNode::CodeBlock, 5..8),
+                (TextNode::Task, 9..10),
+            ]
+        );
+
+        let paragraph = root.components()[2].source().clone();
+        assert_eq!(&md[paragraph.bytes.clone()], "first line\nsecond line");
+        assert_eq!(paragraph.first_line(), 3);
+    }
+
     #[test]
     fn malformed_details_does_not_panic() {
         let md = "<details>\n<summary>S</summary>\n\nbody never closes\n";
@@ -929,7 +1412,7 @@ mod tests {
         );
         let table_count = kinds
             .iter()
-            .filter(|k| matches!(k, TextNode::Table(_, _)))
+            .filter(|k| matches!(k, TextNode::Table(..)))
             .count();
         assert_eq!(
             table_count, 2,
@@ -1186,6 +1669,236 @@ mod tests {
         assert_eq!(heading_text("## word `code`tight\n"), "## word codetight");
     }
 
+    #[test]
+    fn headings_lists_level_text_and_offset() {
+        let md = "# Top\n\nSome text.\n\n## Second `code`\n\nMore text.\n";
+        let root = parse_markdown(None, md, 80);
+        let headings = root.headings();
+        assert_eq!(headings.len(), 2, "got {headings:?}");
+        assert_eq!(headings[0].0, 1);
+        assert_eq!(headings[0].1, "Top");
+        assert_eq!(headings[1].0, 2);
+        assert_eq!(headings[1].1, "Second code");
+        assert_eq!(
+            Ok(headings[1].2),
+            root.heading_offset("#second-code"),
+            "outline offsets must agree with heading_offset"
+        );
+    }
+
+    #[test]
+    fn setext_and_closed_headings() {
+        let md = "Title\n=====\n\nSome text\nSub title\n---\n\n## Closed ##\n\n### C# ###\n";
+        let root = parse_markdown(None, md, 80);
+        let headings: Vec<(u8, String)> = root
+            .headings()
+            .into_iter()
+            .map(|(level, text, _)| (level, text))
+            .collect();
+        assert_eq!(
+            headings,
+            vec![
+                (1, "Title".to_owned()),
+                (2, "Sub title".to_owned()),
+                (2, "Closed".to_owned()),
+                (3, "C#".to_owned()),
+            ]
+        );
+        assert!(root.heading_offset("#sub-title").is_ok());
+    }
+
+    #[test]
+    fn heading_ids_replace_the_slug() {
+        let md = "# Intro\n\ntext\n\n## Install steps {#setup} ##\n";
+        let root = parse_markdown(None, md, 80);
+        assert_eq!(heading_text(md), "Intro");
+        assert_eq!(root.headings()[1].1, "Install steps");
+        assert_eq!(root.heading_offset("#setup"), Ok(root.headings()[1].2));
+        assert!(root.heading_offset("#install-steps").is_err());
+    }
+
+    #[test]
+    fn reference_links_are_resolved() {
+        let md = "See [the docs][Docs] and [rust][], not [this][nope].\n\n[docs]: https://example.com/docs \"Docs\"\n[Rust]: <https://rust-lang.org>\n";
+        let mut root = parse_markdown(None, md, 80);
+        assert_eq!(root.num_links(), 2);
+        root.select(0).unwrap();
+        assert_eq!(root.selected(), "https://example.com/docs");
+        root.select(1).unwrap();
+        assert_eq!(root.selected(), "https://rust-lang.org");
+
+        let text = root.content().join("\n");
+        assert!(text.contains("[this][nope]"), "got {text:?}");
+        assert!(!text.contains("example.com"), "got {text:?}");
+    }
+
+    #[test]
+    fn html_tags_become_formatting_and_links() {
+        let md = "Press <kbd>Ctrl</kbd> and <b>go</b> to <a href=\"https://example.com\">the\nsite</a>. H<sub>2</sub>O<span class=x>!</span>\n";
+        let mut root = parse_markdown(None, md, 80);
+        assert_eq!(root.content()[0], "Press Ctrl and go to the site. H₂O!");
+        assert_eq!(root.num_links(), 1);
+        root.select(0).unwrap();
+        assert_eq!(root.selected(), "https://example.com");
+
+        let words = root.components()[0].content().concat();
+        let kind = |text: &str| words.iter().find(|w| w.content() == text).map(Word::kind);
+        assert_eq!(kind("Ctrl"), Some(WordType::Code));
+        assert_eq!(kind("go"), Some(WordType::Bold));
+    }
+
+    #[test]
+    fn html_breaks_and_centered_paragraphs() {
+        let root = parse_markdown(None, "<p align=\"center\">one<br>two <br/> three</p>\n", 80);
+        assert_eq!(root.content()[..3], ["one", "two", "three"]);
+        assert!(root.components()[0].is_centered());
+
+        let root = parse_markdown(None, "# One<br>two\n", 80);
+        assert_eq!(root.content()[0], "One two");
+    }
+
+    #[test]
+    fn html_blocks_keep_their_lines_and_other_brackets_their_text() {
+        let md = "<div align=center><h1>Title</h1><p>Para</p></div>\n";
+        let root = parse_markdown(None, md, 40);
+        let blocks = root
+            .components()
+            .into_iter()
+            .filter(|c| c.kind() != TextNode::LineBreak)
+            .map(|c| (c.kind(), c.content_as_lines().join("\n").trim().to_owned()))
+            .collect_vec();
+        assert_eq!(
+            blocks,
+            [
+                (TextNode::Heading, "Title".to_owned()),
+                (TextNode::Paragraph, "Para".to_owned()),
+            ]
+        );
+        assert!(root.components()[2].is_centered());
+
+        let root = parse_markdown(None, "Vec<String> in a <span>span</span>\n", 80);
+        assert_eq!(root.content()[0], "Vec<String> in a span");
+    }
+
+    #[test]
+    fn html_image_alone_is_an_image() {
+        let md =
+            "<p align=center>\n  <picture><img src=\"missing.png\" alt=\"Logo\"></picture>\n</p>\n";
+        let text = parse_markdown(None, md, 80).content().join("\n");
+        assert!(text.trim_end().ends_with("[Logo]"), "got {text:?}");
+        assert!(!text.contains('<'), "got {text:?}");
+    }
+
+    #[test]
+    fn front_matter_is_folded_under_its_fields() {
+        let md = "---\ntitle: \"Hello: world\"\nauthor: Jane\ndate: 2024-05-01\ntags:\n  - rust\n  - tui\ndraft: false\n---\n\n# Body\n";
+        let mut root = parse_markdown(None, md, 80);
+        let shown = |root: &ComponentRoot| {
+            root.components()
+                .iter()
+                .filter(|c| !c.is_hidden())
+                .flat_map(|c| c.content_as_lines())
+                .join("\n")
+        };
+        let text = shown(&root);
+        assert!(
+            text.starts_with("Hello: world\nJane · 2024-05-01\n#rust #tui\n"),
+            "got {text:?}"
+        );
+        assert!(!text.contains("draft"), "got {text:?}");
+        assert_eq!(root.details_folds(), [true]);
+
+        root.toggle_front_matter().unwrap();
+        assert!(shown(&root).contains("draft: false"));
+        assert_eq!(root.details_folds(), [false]);
+
+        let toml = "+++\ntitle = 'From TOML'\ntags = [\"a\", \"b\"]\n[params]\ntitle = \"no\"\n+++\nBody\n";
+        let text = parse_markdown(None, toml, 80).content().join("\n");
+        assert!(text.starts_with("From TOML\n#a #b\n"), "got {text:?}");
+
+        let mut root = parse_markdown(None, "Text\n\n---\n\nMore\n", 80);
+        assert!(root.toggle_front_matter().is_err());
+    }
+
+    #[test]
+    fn definition_lists_indent_definitions_under_terms() {
+        let md = "Apple\n:   A fruit\n    of the rose family.\n: A company.\n\nOrange\n: Another fruit.\n";
+        let root = parse_markdown(None, md, 80);
+        let components = root
+            .components()
+            .into_iter()
+            .filter(|c| c.kind() != TextNode::LineBreak)
+            .map(|c| (c.kind(), c.indent(), c.content_as_lines().join("\n")))
+            .collect_vec();
+        assert_eq!(
+            components,
+            [
+                (TextNode::DefinitionTerm, 0, "Apple".to_owned()),
+                (
+                    TextNode::Definition,
+                    4,
+                    "A fruit of the rose family.".to_owned()
+                ),
+                (TextNode::Definition, 4, "A company.".to_owned()),
+                (TextNode::DefinitionTerm, 0, "Orange".to_owned()),
+                (TextNode::Definition, 4, "Another fruit.".to_owned()),
+            ]
+        );
+        assert_eq!(
+            root.content()[..6],
+            [
+                "Apple",
+                "A fruit of the rose family.",
+                "A company.",
+                "",
+                "Orange",
+                "Another fruit."
+            ]
+        );
+    }
+
+    #[test]
+    fn abbreviations_expand_when_selected() {
+        let md = "The HTML spec, not HTML5.\n\n*[HTML]: Hyper Text Markup Language\n";
+        let mut root = parse_markdown(None, md, 80);
+        assert_eq!(root.content()[0], "The HTML spec, not HTML5.");
+        assert!(!root.content().join("\n").contains("*["));
+        assert_eq!(root.num_links(), 1);
+        root.select(0).unwrap();
+        assert_eq!(root.selected(), "Hyper Text Markup Language");
+        assert_eq!(root.selected_underlying_type(), WordType::Abbreviation);
+    }
+
+    #[test]
+    fn highlight_marks_words() {
+        let root = parse_markdown(None, "Some ==marked text== here, a == b.\n", 80);
+        assert_eq!(root.content()[0], "Some marked text here, a == b.");
+        let words = root.components()[0].content().concat();
+        let kind = |text: &str| words.iter().find(|w| w.content() == text).map(Word::kind);
+        assert_eq!(kind("marked"), Some(WordType::Highlight));
+        assert_eq!(kind("here,"), Some(WordType::Normal));
+    }
+
+    #[test]
+    fn tables_keep_alignment_escaped_pipes_and_ragged_rows() {
+        let md = "| Name | Size | Note |\n|:-----|-----:|:----:|\n| `a\\|b` | 10 | c |\n| x \\| y | 2000 |\n| `p | q` | 3 | d | extra |\n";
+        let root = parse_markdown(None, md, 80);
+        let table = &root.components()[0];
+        let TextNode::Table(widths, heights, alignments) = table.kind() else {
+            panic!("got {:?}", table.kind());
+        };
+        assert_eq!(
+            alignments,
+            [Alignment::Left, Alignment::Right, Alignment::Center]
+        );
+        assert_eq!(widths.len(), 3);
+        assert_eq!(heights.len(), 4);
+        assert_eq!(
+            table.content_as_lines(),
+            ["Name Size Note", "a|b 10 c", "x | y 2000 ", "p | q 3 d"]
+        );
+    }
+
     #[test]
     fn heading_plain_text_unchanged() {
         assert_eq!(heading_text("# Plain heading\n"), "Plain heading");
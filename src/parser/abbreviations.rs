@@ -0,0 +1,92 @@
+//! Abbreviations, as in PHP Markdown Extra: an `*[HTML]: Hyper Text Markup
+//! Language` line anywhere in the document marks every `HTML` in its text.
+//! The marked words can be selected like links to show what they stand for.
+
+use std::{cmp::Reverse, mem};
+
+use super::{MdParseEnum, ParseNode, take_definitions};
+
+/// Mark every defined term in `nodes` and below, and drop the definitions,
+/// which are not shown.
+pub(super) fn resolve_abbreviations(nodes: &mut Vec<ParseNode>) {
+    let definitions = take_definitions(nodes, MdParseEnum::AbbreviationDefinition, |node| {
+        let child = |kind| {
+            node.children()
+                .iter()
+                .find(|c| c.kind() == kind)
+                .map(|c| c.content().trim().to_owned())
+        };
+        let term = child(MdParseEnum::Abbreviation).filter(|term| !term.is_empty())?;
+        Some((term, child(MdParseEnum::AbbreviationData)?))
+    });
+    if !definitions.is_empty() {
+        resolve(nodes, &definitions);
+    }
+}
+
+fn resolve(nodes: &mut Vec<ParseNode>, definitions: &[(String, String)]) {
+    for node in nodes.iter_mut() {
+        // Headings and table rows are laid out a line each, without selection
+        if !matches!(node.kind(), MdParseEnum::Heading | MdParseEnum::Table) {
+            resolve(&mut node.children, definitions);
+        }
+    }
+
+    if nodes.iter().any(|n| n.kind() == MdParseEnum::Word) {
+        *nodes = mem::take(nodes)
+            .into_iter()
+            .flat_map(|node| {
+                if node.kind() == MdParseEnum::Word {
+                    split_word(node, definitions)
+                } else {
+                    vec![node]
+                }
+            })
+            .collect();
+    }
+}
+
+/// The word split around the terms in it, each followed by what it stands
+/// for. The text around a term, spaces included, stays a plain word.
+fn split_word(node: ParseNode, definitions: &[(String, String)]) -> Vec<ParseNode> {
+    let mut nodes = Vec::new();
+    let mut rest = node.content();
+    while let Some((start, term, text)) = find_term(rest, definitions) {
+        if start > 0 {
+            nodes.push(ParseNode::new(MdParseEnum::Word, rest[..start].to_owned()));
+        }
+        nodes.push(ParseNode::new(MdParseEnum::Abbreviation, term.to_owned()));
+        nodes.push(ParseNode::new(
+            MdParseEnum::AbbreviationData,
+            text.to_owned(),
+        ));
+        rest = &rest[start + term.len()..];
+    }
+
+    if nodes.is_empty() {
+        return vec![node];
+    }
+    if !rest.is_empty() {
+        nodes.push(ParseNode::new(MdParseEnum::Word, rest.to_owned()));
+    }
+    nodes
+}
+
+/// The first term in `word` that is not part of a longer word.
+fn find_term<'a>(
+    word: &str,
+    definitions: &'a [(String, String)],
+) -> Option<(usize, &'a str, &'a str)> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    definitions
+        .iter()
+        .flat_map(|(term, text)| {
+            word.match_indices(term.as_str())
+                .map(move |(start, _)| (start, term.as_str(), text.as_str()))
+        })
+        .filter(|(start, term, _)| {
+            !word[..*start].ends_with(is_word_char)
+                && !word[start + term.len()..].starts_with(is_word_char)
+        })
+        .min_by_key(|(start, term, _)| (*start, Reverse(term.len())))
+}
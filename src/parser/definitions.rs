@@ -0,0 +1,38 @@
+//! Definition lists, as in pandoc and PHP Markdown Extra: one or more terms,
+//! each on its own line, and below them definitions starting with `: `. The
+//! terms are shown bold and the definitions indented under them.
+
+use crate::nodes::{
+    root::Component,
+    textcomponent::{TextComponent, TextNode},
+};
+
+use super::{MdParseEnum, ParseNode, get_leaf_nodes, list::words};
+
+/// Columns a definition is drawn to the right of its term.
+const DEFINITION_INDENT: u16 = 4;
+
+pub(super) fn parse_definition_list(parse_node: ParseNode) -> Vec<Component> {
+    let mut components = Vec::new();
+    let mut previous = None;
+    for child in parse_node.children_owned() {
+        let kind = match child.kind() {
+            MdParseEnum::DefinitionTerm => TextNode::DefinitionTerm,
+            MdParseEnum::Definition => TextNode::Definition,
+            _ => continue,
+        };
+        let span = child.span().clone();
+        let mut component = TextComponent::new(kind.clone(), words(get_leaf_nodes(child)));
+        component.set_source(span);
+        if kind == TextNode::Definition {
+            component.set_indent(DEFINITION_INDENT);
+        }
+        // A group is kept together, and set apart from the group before it
+        component.set_list_continuation(previous.is_some_and(|previous| {
+            !(previous == TextNode::Definition && kind == TextNode::DefinitionTerm)
+        }));
+        previous = Some(kind);
+        components.push(Component::TextComponent(component));
+    }
+    components
+}
@@ -0,0 +1,179 @@
+//! Front matter, the YAML between `---` lines or TOML between `+++` lines that
+//! opens many Hugo, Jekyll and Obsidian files. Its title, author, date and
+//! tags are shown as a header, and the block itself is folded away under it.
+
+use itertools::Itertools;
+
+use crate::nodes::{
+    root::Component,
+    textcomponent::{HARD_BREAK, TextComponent, TextNode},
+    word::{MetaData, Word, WordType},
+};
+
+use super::{MdParseEnum, ParseNode, code_word, next_details_id, tag_owning_details};
+
+pub(super) fn parse_front_matter(parse_node: ParseNode) -> Vec<Component> {
+    let toml = parse_node.content().starts_with('+');
+    let span = parse_node.span().clone();
+    let source = parse_node
+        .children()
+        .iter()
+        .find(|c| c.kind() == MdParseEnum::FrontMatterContent)
+        .map(|c| c.content().to_owned())
+        .unwrap_or_default();
+    let fields = if toml {
+        toml_fields(&source)
+    } else {
+        yaml_fields(&source)
+    };
+
+    let id = next_details_id();
+    let mut header = TextComponent::new(
+        TextNode::FrontMatter { id, folded: true },
+        header_words(&fields),
+    );
+    header.set_source(span.clone());
+
+    let language = if toml { "toml" } else { "yaml" };
+    let mut rows = vec![vec![Word::new(
+        language.to_owned(),
+        WordType::MetaInfo(MetaData::PLanguage),
+    )]];
+    rows.extend(source.lines().chain([""]).map(|line| {
+        vec![Word::new(
+            format!("\n{line}"),
+            WordType::from(MdParseEnum::CodeBlockStr),
+        )]
+    }));
+    if let Some(row) = rows.last_mut() {
+        row.push(code_word(source.lines()));
+    }
+    let mut raw = TextComponent::new_formatted(TextNode::CodeBlock, rows);
+    raw.set_source(span);
+    // Right under the header when unfolded, like the next item of a list
+    raw.set_list_continuation(true);
+
+    let mut components = vec![
+        Component::TextComponent(header),
+        Component::TextComponent(raw),
+    ];
+    tag_owning_details(&mut components[1..], id);
+    components
+}
+
+/// The title, then the author and date, then the tags, each on its own row.
+/// Front matter with none of them shows the keys it has.
+fn header_words(fields: &[(String, Vec<String>)]) -> Vec<Word> {
+    let field = |names: &[&str]| {
+        fields
+            .iter()
+            .filter(|(key, _)| names.contains(&key.as_str()))
+            .flat_map(|(_, values)| values.iter().map(String::as_str))
+            .filter(|value| !value.is_empty())
+            .collect_vec()
+    };
+    let title = field(&["title"]);
+    let authors = field(&["author", "authors"]);
+    let date = field(&["date"]);
+    let tags = field(&["tags"]);
+
+    let mut rows = Vec::new();
+    if let Some(title) = title.first() {
+        rows.push(text_words(title, WordType::Bold));
+    } else {
+        rows.push(text_words("Front matter", WordType::Bold));
+    }
+
+    let byline = [authors.join(", "), date.join(", ")]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .join(" · ");
+    if !byline.is_empty() {
+        rows.push(text_words(&byline, WordType::Italic));
+    }
+
+    if !tags.is_empty() {
+        let words = tags
+            .iter()
+            .map(|tag| Word::new(format!("#{}", tag.trim_start_matches('#')), WordType::Code));
+        rows.push(
+            Itertools::intersperse(words, Word::new(" ".to_owned(), WordType::Normal)).collect(),
+        );
+    }
+
+    if rows.len() == 1 && title.is_empty() && !fields.is_empty() {
+        let keys = fields.iter().map(|(key, _)| key).join(", ");
+        rows.push(text_words(&keys, WordType::Italic));
+    }
+
+    Itertools::intersperse_with(rows.into_iter(), || {
+        vec![Word::new(HARD_BREAK.to_owned(), WordType::Normal)]
+    })
+    .flatten()
+    .collect()
+}
+
+/// `text` as words and the spaces between them, so it can be wrapped.
+fn text_words(text: &str, kind: WordType) -> Vec<Word> {
+    let words = text
+        .split_whitespace()
+        .map(|word| Word::new(word.to_owned(), kind));
+    Itertools::intersperse(words, Word::new(" ".to_owned(), kind)).collect()
+}
+
+/// The top-level `key: value` pairs of YAML. A list is written inline as
+/// `[a, b]` or as `- a` lines below its key.
+fn yaml_fields(source: &str) -> Vec<(String, Vec<String>)> {
+    let mut fields: Vec<(String, Vec<String>)> = Vec::new();
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if line.starts_with([' ', '\t', '-']) {
+            if let (Some(item), Some((_, values))) = (trimmed.strip_prefix('-'), fields.last_mut())
+            {
+                values.push(unquote(item.trim()));
+            }
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            fields.push((key.trim().to_owned(), values(value)));
+        }
+    }
+    fields
+}
+
+/// The `key = value` pairs of TOML that come before its first table.
+fn toml_fields(source: &str) -> Vec<(String, Vec<String>)> {
+    source
+        .lines()
+        .map(str::trim)
+        .take_while(|line| !line.starts_with('['))
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (unquote(key.trim()), values(value)))
+        .collect()
+}
+
+fn values(value: &str) -> Vec<String> {
+    let value = value.trim();
+    if let Some(list) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        list.split(',')
+            .map(|item| unquote(item.trim()))
+            .filter(|item| !item.is_empty())
+            .collect()
+    } else if value.is_empty() {
+        Vec::new()
+    } else {
+        vec![unquote(value)]
+    }
+}
+
+fn unquote(value: &str) -> String {
+    ['"', '\'']
+        .iter()
+        .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(value)
+        .to_owned()
+}
@@ -0,0 +1,531 @@
+//! Inline HTML, as found in many READMEs. Tags come out of the grammar as
+//! leaves between the words they wrap, and are read here, on the parse tree,
+//! into the formatting, links and images markdown would have given. HTML tags
+//! with no such counterpart are dropped and their text kept, and anything else
+//! in angle brackets, like the `<String>` of `Vec<String>`, is left as text.
+
+use std::mem;
+
+use itertools::Itertools;
+
+use crate::{math::script_text, nodes::textcomponent::HARD_BREAK};
+
+use super::{MdParseEnum, ParseNode, get_leaf_nodes};
+
+/// Tags styling the words inside them.
+const FORMATTING: [&str; 13] = [
+    "b", "strong", "i", "em", "cite", "code", "kbd", "samp", "tt", "var", "s", "del", "strike",
+];
+
+/// Names of HTML elements, the only tags read as tags.
+const ELEMENTS: [&str; 71] = [
+    "a",
+    "abbr",
+    "address",
+    "article",
+    "aside",
+    "b",
+    "bdi",
+    "bdo",
+    "big",
+    "blockquote",
+    "br",
+    "caption",
+    "center",
+    "cite",
+    "code",
+    "col",
+    "colgroup",
+    "data",
+    "dd",
+    "del",
+    "dfn",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "font",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "main",
+    "mark",
+    "nav",
+    "ol",
+    "p",
+    "picture",
+    "pre",
+    "q",
+    "s",
+    "samp",
+    "section",
+    "small",
+    "source",
+    "span",
+    "strike",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "time",
+    "tr",
+    "tt",
+    "u",
+    "ul",
+    "var",
+];
+
+/// Tags of blocks, which keep their own lines inside a paragraph.
+const BLOCKS: [&str; 8] = ["p", "div", "h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// Read every tag in `nodes` and below into the nodes markdown would have.
+pub(super) fn resolve_html(nodes: &mut Vec<ParseNode>) {
+    for node in nodes.iter_mut() {
+        resolve_html(&mut node.children);
+        if node
+            .children
+            .iter()
+            .any(|c| c.kind() == MdParseEnum::HtmlTag)
+        {
+            let children = mem::take(&mut node.children);
+            node.children = Inline::new(node.kind()).read(children);
+        }
+    }
+
+    // A paragraph of `<h1>`s, `<p>`s and the like is shown as those blocks
+    if nodes.iter().any(has_blocks) {
+        *nodes = mem::take(nodes)
+            .into_iter()
+            .flat_map(|node| {
+                if has_blocks(&node) {
+                    let span = node.span;
+                    node.children
+                        .into_iter()
+                        .map(|mut block| {
+                            block.span = span.clone();
+                            block
+                        })
+                        .collect()
+                } else {
+                    vec![node]
+                }
+            })
+            .collect();
+    }
+
+    // A paragraph of nothing but images is shown as those images
+    if nodes
+        .iter()
+        .any(|node| node.kind() == MdParseEnum::Paragraph && only_images(&node.children))
+    {
+        *nodes = mem::take(nodes)
+            .into_iter()
+            .flat_map(|node| {
+                if node.kind() == MdParseEnum::Paragraph && only_images(&node.children) {
+                    node.children
+                        .into_iter()
+                        .filter(|c| c.kind() == MdParseEnum::Image)
+                        .collect()
+                } else {
+                    vec![node]
+                }
+            })
+            .collect();
+    }
+}
+
+fn has_blocks(node: &ParseNode) -> bool {
+    node.kind() == MdParseEnum::Paragraph
+        && node
+            .children
+            .iter()
+            .any(|c| matches!(c.kind(), MdParseEnum::Paragraph | MdParseEnum::Heading))
+}
+
+/// Images among other words are shown as their alt text, as a word.
+fn images_as_words(nodes: &mut [ParseNode]) {
+    if only_images(nodes) {
+        return;
+    }
+    for node in nodes {
+        if node.kind() == MdParseEnum::Image {
+            node.kind = MdParseEnum::Word;
+            node.children.clear();
+        }
+    }
+}
+
+fn only_images(nodes: &[ParseNode]) -> bool {
+    nodes.iter().any(|n| n.kind() == MdParseEnum::Image)
+        && nodes.iter().all(|n| match n.kind() {
+            MdParseEnum::Image | MdParseEnum::AlignCenter => true,
+            MdParseEnum::Word => n.content().trim().is_empty(),
+            _ => false,
+        })
+}
+
+struct Link {
+    href: String,
+    text: String,
+    space: bool,
+}
+
+struct Script {
+    sup: bool,
+    text: String,
+    space: bool,
+}
+
+/// The children of one node, read in order with the tags still open.
+struct Inline {
+    parent: MdParseEnum,
+    nodes: Vec<ParseNode>,
+    /// Open formatting tags, innermost last.
+    formatting: Vec<String>,
+    link: Option<Link>,
+    script: Option<Script>,
+    /// A dropped tag had whitespace before it, which the next word gets.
+    space: bool,
+    /// Whether each open `<p>` or `<div>` is centered, itself or by its parent.
+    aligned: Vec<bool>,
+    /// Paragraphs and headings read so far, once a block tag was found.
+    blocks: Vec<ParseNode>,
+    /// The level of the open `<h1>` to `<h6>`.
+    heading: Option<usize>,
+}
+
+impl Inline {
+    fn new(parent: MdParseEnum) -> Self {
+        Self {
+            parent,
+            nodes: Vec::new(),
+            formatting: Vec::new(),
+            link: None,
+            script: None,
+            space: false,
+            aligned: Vec::new(),
+            blocks: Vec::new(),
+            heading: None,
+        }
+    }
+
+    fn read(mut self, nodes: Vec<ParseNode>) -> Vec<ParseNode> {
+        for node in nodes {
+            if node.kind() == MdParseEnum::HtmlTag {
+                self.tag(node.content());
+            } else {
+                self.push(node);
+            }
+        }
+        if !self.blocks.is_empty() {
+            self.end_block();
+            return self.blocks;
+        }
+        self.close_script();
+        self.close_link();
+        if self.parent == MdParseEnum::Paragraph {
+            images_as_words(&mut self.nodes);
+        }
+        if self.centered() {
+            self.nodes
+                .push(ParseNode::new(MdParseEnum::AlignCenter, String::new()));
+        }
+        self.nodes
+    }
+
+    fn centered(&self) -> bool {
+        self.aligned.last().copied().unwrap_or_default()
+    }
+
+    /// Move what was read since the last block tag into a block of its own.
+    fn end_block(&mut self) {
+        self.close_script();
+        self.close_link();
+        self.space = false;
+        let mut nodes = mem::take(&mut self.nodes);
+        if nodes
+            .iter()
+            .all(|node| node.kind() == MdParseEnum::Word && node.content().trim().is_empty())
+        {
+            return;
+        }
+        if let Some(first) = nodes.first_mut() {
+            first.content = first.content().trim_start().to_owned();
+        }
+
+        let mut block = if let Some(level) = self.heading {
+            ParseNode::new(MdParseEnum::Heading, format!("{} ", "#".repeat(level)))
+        } else {
+            images_as_words(&mut nodes);
+            if self.centered() {
+                nodes.push(ParseNode::new(MdParseEnum::AlignCenter, String::new()));
+            }
+            ParseNode::new(MdParseEnum::Paragraph, String::new())
+        };
+        block.children = nodes;
+        self.blocks.push(block);
+    }
+
+    fn push(&mut self, mut node: ParseNode) {
+        if mem::take(&mut self.space) && !node.content().starts_with(' ') {
+            node.content.insert(0, ' ');
+        }
+        if let Some(script) = &mut self.script {
+            script.text.push_str(&text(node));
+        } else if let Some(link) = &mut self.link {
+            link.text.push_str(&text(node));
+        } else {
+            if node.kind() == MdParseEnum::Word {
+                node.kind = self.style();
+                // The space before a styled word is not styled, as with `**`
+                if node.kind() != MdParseEnum::Word && node.content().starts_with(' ') {
+                    node.content = node.content().trim_start().to_owned();
+                    self.nodes
+                        .push(ParseNode::new(MdParseEnum::Word, " ".to_owned()));
+                }
+            }
+            self.nodes.push(node);
+        }
+    }
+
+    fn tag(&mut self, source: &str) {
+        let Some(tag) = Tag::parse(source).filter(|tag| ELEMENTS.contains(&tag.name.as_str()))
+        else {
+            let space = if source.starts_with(' ') { " " } else { "" };
+            let text = format!("{space}{}", source.trim_start());
+            self.push(ParseNode::new(MdParseEnum::Word, text));
+            return;
+        };
+        if source.starts_with(' ') {
+            self.space = true;
+        }
+
+        match (tag.name.as_str(), tag.closing) {
+            // Headings and table rows are a line each
+            ("br", _) if matches!(self.parent, MdParseEnum::Heading | MdParseEnum::TableCell) => {
+                self.space = true;
+            }
+            ("br", _) => {
+                self.space = false;
+                self.push(ParseNode::new(MdParseEnum::Word, HARD_BREAK.to_owned()));
+            }
+            ("a", false) => {
+                self.close_link();
+                if let Some(href) = tag.attribute("href") {
+                    self.link = Some(Link {
+                        href: href.to_owned(),
+                        text: String::new(),
+                        space: mem::take(&mut self.space),
+                    });
+                }
+            }
+            ("a", true) => self.close_link(),
+            ("img", false) => self.image(&tag),
+            ("sub" | "sup", false) => {
+                self.close_script();
+                self.script = Some(Script {
+                    sup: tag.name == "sup",
+                    text: String::new(),
+                    space: mem::take(&mut self.space),
+                });
+            }
+            ("sub" | "sup", true) => self.close_script(),
+            (name, closing) if BLOCKS.contains(&name) && self.parent == MdParseEnum::Paragraph => {
+                self.end_block();
+                self.heading = name
+                    .strip_prefix('h')
+                    .and_then(|level| level.parse().ok())
+                    .filter(|_| !closing);
+                match (name, closing) {
+                    ("p" | "div", false) => {
+                        let centered = self.centered() || tag.is_centered();
+                        self.aligned.push(centered);
+                    }
+                    ("p" | "div", true) => {
+                        self.aligned.pop();
+                    }
+                    _ => {}
+                }
+            }
+            ("p" | "div", false) if tag.is_centered() => self.aligned.push(true),
+            (name, false) if FORMATTING.contains(&name) => self.formatting.push(tag.name),
+            (name, true) if FORMATTING.contains(&name) => {
+                if let Some(i) = self.formatting.iter().rposition(|open| open == name) {
+                    self.formatting.remove(i);
+                }
+            }
+            // `<picture>`, `<source>` and tags with nothing to show
+            _ => {}
+        }
+    }
+
+    fn image(&mut self, tag: &Tag) {
+        let alt = tag.attribute("alt").unwrap_or_default();
+        let word = format!("[{}]", if alt.is_empty() { "image" } else { alt });
+        let src = tag.attribute("src");
+
+        if self.link.is_some()
+            || self.parent != MdParseEnum::Paragraph
+            || self.heading.is_some()
+            || src.is_none()
+        {
+            self.push(ParseNode::new(MdParseEnum::Word, word));
+            return;
+        }
+
+        let space = if mem::take(&mut self.space) { " " } else { "" };
+        let mut image = ParseNode::new(MdParseEnum::Image, format!("{space}{word}"));
+        image.children = vec![
+            ParseNode::new(MdParseEnum::AltText, alt.to_owned()),
+            ParseNode::new(MdParseEnum::LinkData, src.unwrap_or_default().to_owned()),
+        ];
+        self.nodes.push(image);
+    }
+
+    fn close_link(&mut self) {
+        let Some(link) = self.link.take() else {
+            return;
+        };
+        // Shown as one link word, like the text of `[text](url)`
+        let mut text = link.text.split_whitespace().join(" ");
+        if text.is_empty() {
+            text.clone_from(&link.href);
+        }
+
+        let space = if link.space { " " } else { "" };
+        let mut node = ParseNode::new(MdParseEnum::Link, format!("{space}{text}"));
+        node.children = vec![
+            ParseNode::new(MdParseEnum::Link, text),
+            ParseNode::new(MdParseEnum::LinkData, link.href),
+        ];
+        self.nodes.push(node);
+    }
+
+    fn close_script(&mut self) {
+        let Some(script) = self.script.take() else {
+            return;
+        };
+        let space = if script.space { " " } else { "" };
+        let text = format!("{space}{}", script_text(&script.text, script.sup));
+        self.push(ParseNode::new(MdParseEnum::Word, text));
+    }
+
+    /// The kind of a plain word inside the open formatting tags.
+    fn style(&self) -> MdParseEnum {
+        let open = |names: &[&str]| {
+            self.formatting
+                .iter()
+                .any(|open| names.contains(&open.as_str()))
+        };
+        let bold = open(&["b", "strong"]);
+        let italic = open(&["i", "em", "cite", "var"]);
+        if open(&["code", "kbd", "samp", "tt"]) {
+            MdParseEnum::Code
+        } else if bold && italic {
+            MdParseEnum::BoldItalic
+        } else if bold {
+            MdParseEnum::Bold
+        } else if italic {
+            MdParseEnum::Italic
+        } else if open(&["s", "del", "strike"]) {
+            MdParseEnum::Strikethrough
+        } else {
+            MdParseEnum::Word
+        }
+    }
+}
+
+/// The words of `node` as written, without any link urls.
+fn text(node: ParseNode) -> String {
+    get_leaf_nodes(node)
+        .iter()
+        .filter(|leaf| leaf.kind() != MdParseEnum::LinkData)
+        .map(ParseNode::content)
+        .collect()
+}
+
+struct Tag {
+    name: String,
+    closing: bool,
+    attributes: Vec<(String, String)>,
+}
+
+impl Tag {
+    fn parse(source: &str) -> Option<Self> {
+        let source = source.trim().strip_prefix('<')?.strip_suffix('>')?;
+        let (closing, source) = match source.strip_prefix('/') {
+            Some(source) => (true, source),
+            None => (false, source),
+        };
+        let end = source
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+            .unwrap_or(source.len());
+        let name = source[..end].to_ascii_lowercase();
+
+        let mut attributes = Vec::new();
+        let mut rest = &source[end..];
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                break;
+            }
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '=')
+                .unwrap_or(rest.len());
+            let key = rest[..end].to_ascii_lowercase();
+            rest = rest[end..].trim_start();
+
+            let mut value = "";
+            if let Some(after) = rest.strip_prefix('=') {
+                let after = after.trim_start();
+                (value, rest) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let inner = &after[1..];
+                        let end = inner.find(quote).unwrap_or(inner.len());
+                        (&inner[..end], inner.get(end + 1..).unwrap_or_default())
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        after.split_at(end)
+                    }
+                };
+            }
+            attributes.push((key, value.to_owned()));
+        }
+
+        Some(Self {
+            name,
+            closing,
+            attributes,
+        })
+    }
+
+    fn is_centered(&self) -> bool {
+        self.attribute("align")
+            .is_some_and(|align| align.eq_ignore_ascii_case("center"))
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
@@ -0,0 +1,337 @@
+//! Lists come out of the grammar flat, one node per item, paragraph or code
+//! block along with the indentation it was written at. Here they are nested
+//! into a tree by that indentation, numbered, and laid out as components that
+//! are shifted right to the column the text of their item starts at.
+
+use std::{iter::Peekable, vec::IntoIter};
+
+use itertools::Itertools;
+use unicode_width::UnicodeWidthStr;
+
+use crate::nodes::{
+    root::Component,
+    textcomponent::{TextComponent, TextNode},
+    word::{MetaData, Word, WordType},
+};
+
+use super::{MdParseEnum, ParseNode, SourceSpan, code_word, get_leaf_nodes};
+
+/// Bullets by depth, starting over below the third level.
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+/// Columns taken by the checkbox of a task, as drawn by the renderer.
+const TASK_WIDTH: u16 = 4;
+
+enum Marker {
+    Bullet,
+    /// The number the item was written with and the `.` or `)` after it.
+    Ordered(u64, char),
+}
+
+struct Item {
+    indent: usize,
+    /// Column the text of the item starts at, as written.
+    content: usize,
+    gap: bool,
+    span: SourceSpan,
+    marker: Marker,
+    /// Byte offset of the `[ ]` of a task item, and the checkbox as written.
+    task: Option<(usize, String)>,
+    words: Vec<Word>,
+    children: Vec<Child>,
+}
+
+/// A paragraph or code block continuing an item.
+struct Block {
+    indent: usize,
+    gap: bool,
+    component: TextComponent,
+}
+
+enum Entry {
+    Item(Item),
+    Block(Block),
+}
+
+impl Entry {
+    fn indent(&self) -> usize {
+        match self {
+            Entry::Item(item) => item.indent,
+            Entry::Block(block) => block.indent,
+        }
+    }
+
+    fn gap(&self) -> bool {
+        match self {
+            Entry::Item(item) => item.gap,
+            Entry::Block(block) => block.gap,
+        }
+    }
+}
+
+enum Child {
+    Block(Box<Block>),
+    List(Vec<Item>),
+}
+
+pub(super) fn parse_list(parse_node: ParseNode) -> Vec<Component> {
+    let mut entries = Vec::new();
+    let mut gap = false;
+    for child in parse_node.children_owned() {
+        if child.kind() == MdParseEnum::ListGap {
+            gap = true;
+            continue;
+        }
+        entries.push(parse_entry(child, gap));
+        gap = false;
+    }
+
+    let mut entries = entries.into_iter().peekable();
+    let mut components = Vec::new();
+    while entries.peek().is_some() {
+        let mut items = nest(&mut entries, None);
+        // What no item has room for ends the list, as a paragraph of its own
+        if items.is_empty() {
+            if let Some(Entry::Block(block)) = entries.next() {
+                components.push(Component::TextComponent(block.component));
+            }
+            continue;
+        }
+        if let Some(first) = items.first_mut() {
+            first.gap = false;
+        }
+        let start = components.len();
+        layout(items, 0, 0, &mut components);
+        for component in components.iter_mut().skip(start + 1) {
+            if let Component::TextComponent(component) = component {
+                component.set_list_continuation(true);
+            }
+        }
+    }
+    components
+}
+
+fn parse_entry(parse_node: ParseNode, gap: bool) -> Entry {
+    let kind = parse_node.kind();
+    let span = parse_node.span().clone();
+    let mut indent = 0;
+    let mut marker = Marker::Bullet;
+    let mut marker_width = 2;
+    let mut task = None;
+    let mut leaves = Vec::new();
+
+    for child in parse_node.children_owned() {
+        match child.kind() {
+            MdParseEnum::Indent => indent = columns(child.content()),
+            MdParseEnum::Digit => {
+                marker = ordered_marker(child.content());
+                marker_width = columns(child.content());
+            }
+            MdParseEnum::TaskOpen | MdParseEnum::TaskClosed => {
+                task = Some((child.span().bytes.start, child.content().to_owned()));
+            }
+            _ => leaves.extend(get_leaf_nodes(child)),
+        }
+    }
+
+    let mut component = match kind {
+        MdParseEnum::ListCodeBlock => {
+            let mut rows: Vec<Vec<Word>> = leaves
+                .into_iter()
+                .map(|node| {
+                    let word_type = WordType::from(node.kind());
+                    let content = if node.kind() == MdParseEnum::CodeBlockStr {
+                        // Code lines keep their newline, but not the indentation of the item
+                        let line = node.content().trim_start_matches('\n');
+                        let skip = line.len() - line.trim_start_matches(' ').len();
+                        format!("\n{}", &line[skip.min(indent)..])
+                    } else {
+                        node.content().to_owned()
+                    };
+                    vec![Word::new(content, word_type)]
+                })
+                .collect();
+            // The language is the only word without a newline in front
+            let code = code_word(
+                rows.iter()
+                    .flatten()
+                    .filter_map(|word| word.content().strip_prefix('\n')),
+            );
+            if let Some(row) = rows.last_mut() {
+                row.push(code);
+            }
+            TextComponent::new_formatted(TextNode::CodeBlock, rows)
+        }
+        MdParseEnum::ListParagraph => TextComponent::new(TextNode::Paragraph, words(leaves)),
+        _ => {
+            return Entry::Item(Item {
+                indent,
+                content: indent + marker_width,
+                gap,
+                span,
+                marker,
+                task,
+                words: words(leaves),
+                children: Vec::new(),
+            });
+        }
+    };
+    component.set_source(span);
+
+    Entry::Block(Block {
+        indent,
+        gap,
+        component,
+    })
+}
+
+/// Build the items of one list, and the lists nested in them, until an entry
+/// is indented no deeper than the item owning the list, at `parent`, or a
+/// block after a blank line is indented short of the text of the last item.
+fn nest(entries: &mut Peekable<IntoIter<Entry>>, parent: Option<usize>) -> Vec<Item> {
+    let mut items: Vec<Item> = Vec::new();
+    let mut level = None;
+
+    while let Some(entry) = entries.peek() {
+        let indent = entry.indent();
+        if parent.is_some_and(|parent| indent <= parent) {
+            break;
+        }
+        let is_item = matches!(entry, Entry::Item(_));
+
+        match (items.last_mut(), level) {
+            (Some(last), Some(level)) if is_item && indent > level => {
+                let list = nest(entries, Some(level));
+                last.children.push(Child::List(list));
+            }
+            // Without a blank line in between it is a lazy continuation and
+            // stays with the item wherever it starts
+            (Some(last), _) if !is_item && (!entry.gap() || indent >= last.content) => {
+                if let Some(Entry::Block(block)) = entries.next() {
+                    last.children.push(Child::Block(Box::new(block)));
+                }
+            }
+            _ if is_item => {
+                if let Some(Entry::Item(item)) = entries.next() {
+                    level.get_or_insert(item.indent);
+                    items.push(item);
+                }
+            }
+            _ => break,
+        }
+    }
+    items
+}
+
+/// Turn `items` into components, with their markers at `column`. The text of
+/// an item and everything nested in it starts right after its marker.
+fn layout(items: Vec<Item>, column: u16, depth: usize, components: &mut Vec<Component>) {
+    // Ordered items count on from the first of a run, whatever the others
+    // say. Another delimiter starts a new run, as it starts a new list.
+    let mut numbers = Vec::with_capacity(items.len());
+    let mut previous: Option<(u64, char)> = None;
+    for item in &items {
+        let number = match item.marker {
+            Marker::Ordered(start, delimiter) => Some(match previous {
+                Some((n, previous)) if previous == delimiter => (n.saturating_add(1), delimiter),
+                _ => (start, delimiter),
+            }),
+            Marker::Bullet => None,
+        };
+        numbers.push(number);
+        previous = number;
+    }
+
+    // and are right-aligned to the widest number of the run
+    let mut widths = Vec::with_capacity(items.len());
+    for (_, run) in &numbers
+        .iter()
+        .chunk_by(|number| number.map(|(_, delimiter)| delimiter))
+    {
+        let run = run.collect_vec();
+        let width = run
+            .iter()
+            .filter_map(|number| number.map(|(n, _)| n.to_string().len()))
+            .max()
+            .unwrap_or(0);
+        widths.extend(std::iter::repeat_n(width, run.len()));
+    }
+
+    for ((item, number), width) in items.into_iter().zip(numbers).zip(widths) {
+        let marker = match number {
+            Some((number, delimiter)) => format!("{number:>width$}{delimiter} "),
+            None => format!("{} ", BULLETS[depth % BULLETS.len()]),
+        };
+
+        let mut words = item.words;
+        let (mut component, text_column) = if let Some((offset, checkbox)) = item.task {
+            words.insert(0, Word::new(checkbox, WordType::MetaInfo(MetaData::Other)));
+            let mut component = TextComponent::new(TextNode::Task, words);
+            component.set_task_marker(Some(offset));
+            (component, column + TASK_WIDTH)
+        } else {
+            let width = marker.width() as u16;
+            words.insert(0, Word::new(marker, WordType::ListMarker));
+            (TextComponent::new(TextNode::List, words), column + width)
+        };
+        component.set_indent(column);
+        component.set_source(item.span);
+
+        if item.gap {
+            components.push(line_break());
+        }
+        components.push(Component::TextComponent(component));
+
+        for child in item.children {
+            match child {
+                Child::Block(mut block) => {
+                    if block.gap {
+                        components.push(line_break());
+                    }
+                    block.component.set_indent(text_column);
+                    components.push(Component::TextComponent(block.component));
+                }
+                Child::List(items) => layout(items, text_column, depth + 1, components),
+            }
+        }
+    }
+}
+
+pub(super) fn words(leaves: Vec<ParseNode>) -> Vec<Word> {
+    let mut words = Vec::new();
+    for node in leaves {
+        let word_type = WordType::from(node.kind());
+        let mut content: String = node
+            .content()
+            .chars()
+            .dedup_by(|x, y| *x == ' ' && *y == ' ')
+            .collect();
+
+        if matches!(node.kind(), MdParseEnum::WikiLink | MdParseEnum::InlineLink) {
+            words.push(Word::new(content.clone(), WordType::LinkData));
+        }
+        if content.starts_with(' ') {
+            content.remove(0);
+            words.push(Word::new(" ".to_owned(), word_type));
+        }
+        words.push(Word::new(content, word_type));
+    }
+    if let Some(word) = words.iter_mut().find(|word| word.is_renderable()) {
+        word.set_content(word.content().trim_start().to_owned());
+    }
+    words
+}
+
+fn ordered_marker(counter: &str) -> Marker {
+    let digits = counter.chars().take_while(char::is_ascii_digit).count();
+    let delimiter = counter[digits..].chars().next().unwrap_or('.');
+    Marker::Ordered(counter[..digits].parse().unwrap_or(1), delimiter)
+}
+
+fn columns(indent: &str) -> usize {
+    indent.chars().map(|c| if c == '\t' { 4 } else { 1 }).sum()
+}
+
+fn line_break() -> Component {
+    Component::TextComponent(TextComponent::new(TextNode::LineBreak, Vec::new()))
+}
@@ -0,0 +1,70 @@
+//! Reference links, `[text][label]` and `[label][]`, get their url from a
+//! `[label]: url` definition that can be anywhere in the document. They are
+//! resolved on the parse tree, before any components are made, into the same
+//! link words and link data as `[text](url)`.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use super::{MdParseEnum, ParseNode, take_definitions};
+
+/// Fill in the url of every reference link in `nodes` and drop the
+/// definitions, which are not shown.
+pub(super) fn resolve_references(nodes: &mut Vec<ParseNode>) {
+    let definitions: HashMap<String, String> =
+        take_definitions(nodes, MdParseEnum::LinkReferenceDefinition, |node| {
+            let child = |kind| node.children().iter().find(|c| c.kind() == kind);
+            let label = child(MdParseEnum::LinkLabel)?;
+            let url = child(MdParseEnum::LinkData)?.content();
+            Some((
+                normalize(label.content()),
+                url.trim_start_matches('<').trim_end_matches('>').to_owned(),
+            ))
+        })
+        .into_iter()
+        .collect();
+    resolve(nodes, &definitions);
+}
+
+fn resolve(nodes: &mut [ParseNode], definitions: &HashMap<String, String>) {
+    for node in nodes {
+        if node.kind() != MdParseEnum::Link {
+            resolve(&mut node.children, definitions);
+            continue;
+        }
+        let Some(index) = node
+            .children()
+            .iter()
+            .position(|c| c.kind() == MdParseEnum::LinkReference)
+        else {
+            continue;
+        };
+
+        let reference = node.children.remove(index);
+        let (labels, words): (Vec<_>, Vec<_>) = reference
+            .children_owned()
+            .into_iter()
+            .partition(|c| c.kind() == MdParseEnum::LinkLabel);
+        // `[label][]` uses its text as the label
+        let label = labels.first().map_or_else(
+            || words.iter().map(ParseNode::content).join(" "),
+            |label| label.content().to_owned(),
+        );
+
+        if let Some(url) = definitions.get(&normalize(&label)) {
+            node.children.extend(words);
+            node.children
+                .push(ParseNode::new(MdParseEnum::LinkData, url.clone()));
+        } else {
+            // Not a link after all, so it is shown as it was written
+            node.kind = MdParseEnum::Word;
+            node.children.clear();
+        }
+    }
+}
+
+/// Labels match regardless of case and runs of whitespace.
+fn normalize(label: &str) -> String {
+    label.split_whitespace().join(" ").to_lowercase()
+}
@@ -4,7 +4,10 @@ use itertools::Itertools;
 use strsim::damerau_levenshtein;
 
 use crate::{
-    nodes::word::{Word, WordType},
+    nodes::{
+        textcomponent::TextComponent,
+        word::{Word, WordType},
+    },
     pages::file_explorer::{FileTree, MdFile},
     util::general::GENERAL_CONFIG,
 };
@@ -315,8 +318,23 @@ fn char_windows(src: &str, win_size: usize) -> impl Iterator<Item = &'_ str> {
 }
 
 #[must_use]
-pub fn compare_heading(link_header: &str, header: &[Vec<Word>]) -> bool {
-    let header: String = header
+pub fn compare_heading(link_header: &str, heading: &TextComponent) -> bool {
+    link_header == heading_anchor(heading)
+}
+
+/// The anchor of `heading`: its `{#id}` if it has one, else the slug of its
+/// text.
+#[must_use]
+pub fn heading_anchor(heading: &TextComponent) -> String {
+    heading
+        .heading_id()
+        .map_or_else(|| heading_slug(heading.content()), str::to_owned)
+}
+
+/// The anchor a link uses to refer to a heading, without the leading `#`.
+#[must_use]
+pub fn heading_slug(header: &[Vec<Word>]) -> String {
+    header
         .iter()
         .flatten()
         .map(|word| word.content().to_lowercase())
@@ -326,9 +344,7 @@ pub fn compare_heading(link_header: &str, header: &[Vec<Word>]) -> bool {
         .filter(|c| c.is_alphanumeric() || *c == '-')
         .dedup_by(|a, b| *a == '-' && *b == '-')
         .skip_while(|c| *c == '-')
-        .collect();
-
-    link_header == header
+        .collect()
 }
 
 #[cfg(test)]
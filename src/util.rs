@@ -1,4 +1,7 @@
-use std::{cmp, io};
+use std::{
+    cmp, fs, io,
+    path::{Path, PathBuf},
+};
 
 use crossterm::{
     cursor,
@@ -7,12 +10,26 @@ use crossterm::{
     terminal::{LeaveAlternateScreen, disable_raw_mode},
 };
 use general::GENERAL_CONFIG;
+use keys::KeyInput;
+use marks::Marks;
+use state::{FilePosition, Positions};
 
-use crate::boxes::{errorbox::ErrorBox, help_box::HelpBox, linkbox::LinkBox, searchbox::SearchBox};
+use crate::{
+    boxes::{
+        errorbox::ErrorBox, help_box::HelpBox, linkbox::LinkBox, markbox::MarkBox,
+        searchbox::SearchBox, tocbox::TocBox,
+    },
+    nodes::root::ComponentRoot,
+};
 
+pub mod buffers;
+pub mod cli;
 pub mod colors;
+pub mod export;
 pub mod general;
 pub mod keys;
+pub mod marks;
+pub mod state;
 
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum Mode {
@@ -25,16 +42,20 @@ pub enum Mode {
 pub enum Boxes {
     Error,
     Search,
+    /// The search box, asking for the file to save a yanked block to.
+    Export,
     LinkPreview,
+    Toc,
+    Marks,
     #[default]
     None,
 }
 
 impl From<JumpHistory> for Mode {
     fn from(jump_history: JumpHistory) -> Self {
-        match jump_history.history.last() {
+        match jump_history.back.last() {
             Some(jump) => match jump {
-                Jump::File(_) => Mode::View,
+                Jump::File { .. } => Mode::View,
                 Jump::FileTree => Mode::FileTree,
             },
             None => Mode::FileTree,
@@ -50,6 +71,16 @@ pub struct App {
     pub select_index: usize,
     pub details_selected: bool,
     pub details_select_index: usize,
+    pub task_selected: bool,
+    pub task_select_index: usize,
+    /// A table or code block wider than the view is focused, to be
+    /// scrolled horizontally.
+    pub wide_selected: bool,
+    pub wide_select_index: usize,
+    /// A table is selected, with a cursor on one of its cells. Keys are
+    /// looked up in the table keymap then.
+    pub table_selected: bool,
+    pub table_select_index: usize,
     pub mode: Mode,
     pub boxes: Boxes,
     pub history: JumpHistory,
@@ -57,6 +88,11 @@ pub struct App {
     pub message_box: ErrorBox,
     pub help_box: HelpBox,
     pub link_box: LinkBox,
+    pub toc_box: TocBox,
+    pub positions: Positions,
+    pub marks: Marks,
+    pub mark_box: MarkBox,
+    pub key_input: KeyInput,
 }
 
 impl App {
@@ -66,10 +102,29 @@ impl App {
         self.select_index = 0;
         self.details_selected = false;
         self.details_select_index = 0;
+        self.task_selected = false;
+        self.task_select_index = 0;
+        self.wide_selected = false;
+        self.wide_select_index = 0;
+        self.table_selected = false;
+        self.table_select_index = 0;
         self.boxes = Boxes::None;
         self.help_box.close();
     }
 
+    /// Note where `markdown` was left, so it opens there next time.
+    pub fn remember_position(&mut self, markdown: &ComponentRoot) {
+        if let Some(file_name) = markdown.file_name() {
+            self.positions.set(
+                file_name,
+                FilePosition {
+                    scroll: self.vertical_scroll,
+                    folds: markdown.details_folds(),
+                },
+            );
+        }
+    }
+
     pub fn set_width(&mut self, width: u16) -> bool {
         let temp_width = self.width;
         self.width = cmp::min(width, GENERAL_CONFIG.width);
@@ -100,35 +155,125 @@ impl<'a> From<&'a str> for LinkType<'a> {
     }
 }
 
+/// `file_name` with symlinks, `.` and `..` resolved, so that names of the
+/// same file, e.g. `./a.md` and `a.md`, compare equal. Names that cannot be
+/// resolved are kept as they are.
+#[must_use]
+pub fn canonical_path(file_name: impl AsRef<Path>) -> PathBuf {
+    let file_name = file_name.as_ref();
+    fs::canonicalize(file_name).unwrap_or_else(|_| file_name.to_owned())
+}
+
+/// `source` with the checkbox of the task whose `[ ]` starts at byte
+/// `offset` flipped. `None` if there is no checkbox there, e.g. because the
+/// file changed since it was parsed.
+#[must_use]
+pub fn toggle_checkbox(source: &str, offset: usize) -> Option<String> {
+    let flipped = match source.get(offset..offset + 3)? {
+        "[ ]" => "[x]",
+        "[x]" | "[X]" => "[ ]",
+        _ => return None,
+    };
+    let mut text = String::with_capacity(source.len());
+    text.push_str(&source[..offset]);
+    text.push_str(flipped);
+    text.push_str(&source[offset + 3..]);
+    Some(text)
+}
+
+/// The `editor_command` template for `editor` when none is configured. Only
+/// editors known to take a line number get one, the rest just open the file.
+#[must_use]
+pub fn default_editor_command(editor: Option<&str>) -> &'static str {
+    let program = editor
+        .and_then(|editor| editor.split_whitespace().next())
+        .and_then(|program| Path::new(program).file_name())
+        .and_then(|program| program.to_str());
+    match program {
+        Some("vi" | "vim" | "nvim" | "nano" | "emacs") => "{editor} +{line} {file}",
+        Some("hx" | "helix") => "{editor} {file}:{line}",
+        _ => "{editor} {file}",
+    }
+}
+
+/// Program and arguments for opening `file` at `line` with `template`. In
+/// the template `{editor}` is `$EDITOR`, given as `editor`, and `{file}`
+/// and `{line}` are replaced wherever they appear, like in `{file}:{line}`.
+pub fn editor_command(
+    template: &str,
+    editor: Option<&str>,
+    file: &str,
+    line: usize,
+) -> Result<Vec<String>, String> {
+    let mut command = Vec::new();
+    for word in template.split_whitespace() {
+        if word == "{editor}" {
+            let editor = editor.ok_or_else(|| {
+                "No editor found. Please set the EDITOR environment variable".to_owned()
+            })?;
+            command.extend(editor.split_whitespace().map(str::to_owned));
+        } else {
+            command.push(
+                word.replace("{file}", file)
+                    .replace("{line}", &line.to_string()),
+            );
+        }
+    }
+    if command.is_empty() {
+        return Err("The editor_command setting is empty".to_owned());
+    }
+    Ok(command)
+}
+
 pub fn destruct_terminal() {
     disable_raw_mode().unwrap();
     execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture).unwrap();
     execute!(io::stdout(), cursor::Show).unwrap();
 }
 
+/// Back and forward jump list, like the one in a browser. Going back or
+/// forward takes the location being left, so it can be returned to.
 #[derive(Debug, Clone)]
 pub struct JumpHistory {
-    history: Vec<Jump>,
+    back: Vec<Jump>,
+    forward: Vec<Jump>,
 }
 
 impl JumpHistory {
     #[must_use]
     pub fn new() -> Self {
         Self {
-            history: Vec::new(),
+            back: Vec::new(),
+            forward: Vec::new(),
         }
     }
 
+    /// Record `jump` as the location being left for a new one. This drops
+    /// the forward path.
     pub fn push(&mut self, jump: Jump) {
-        self.history.push(jump);
+        self.back.push(jump);
+        self.forward.clear();
     }
 
-    pub fn pop(&mut self) -> Jump {
-        if let Some(jump) = self.history.pop() {
-            jump
-        } else {
-            Jump::FileTree
+    /// Step back from `current`. The file tree is returned when there is
+    /// nothing left to go back to, and the forward path is left alone.
+    pub fn back(&mut self, current: Option<Jump>) -> Jump {
+        let Some(jump) = self.back.pop() else {
+            return Jump::FileTree;
+        };
+        if let Some(current) = current {
+            self.forward.push(current);
+        }
+        jump
+    }
+
+    /// Step forward from `current`, if something was gone back from.
+    pub fn forward(&mut self, current: Option<Jump>) -> Option<Jump> {
+        let jump = self.forward.pop()?;
+        if let Some(current) = current {
+            self.back.push(current);
         }
+        Some(jump)
     }
 }
 
@@ -140,21 +285,81 @@ impl Default for JumpHistory {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Jump {
-    File(String),
+    File {
+        path: String,
+        scroll: u16,
+        /// Anchor of the heading `scroll` was in, used to find the place
+        /// again if the file changed in the meantime.
+        heading: Option<String>,
+    },
     FileTree,
 }
 
+impl Jump {
+    #[must_use]
+    pub fn file(path: &str) -> Self {
+        Self::File {
+            path: path.to_owned(),
+            scroll: 0,
+            heading: None,
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_editor_command() {
+    assert_eq!(
+        editor_command("{editor} +{line} {file}", Some("nvim"), "a b.md", 12).unwrap(),
+        vec!["nvim", "+12", "a b.md"]
+    );
+    assert_eq!(
+        editor_command("{editor} -g {file}:{line}", Some("code --wait"), "a.md", 3).unwrap(),
+        vec!["code", "--wait", "-g", "a.md:3"]
+    );
+    assert_eq!(
+        editor_command("hx {file}:{line}", None, "a.md", 1).unwrap(),
+        vec!["hx", "a.md:1"]
+    );
+    assert!(editor_command("{editor} {file}", None, "a.md", 1).is_err());
+
+    assert_eq!(
+        default_editor_command(Some("/usr/bin/nvim")),
+        "{editor} +{line} {file}"
+    );
+    assert_eq!(default_editor_command(Some("hx")), "{editor} {file}:{line}");
+    assert_eq!(
+        default_editor_command(Some("code --wait")),
+        "{editor} {file}"
+    );
+    assert_eq!(default_editor_command(None), "{editor} {file}");
+}
+
 #[cfg(test)]
 #[test]
 fn test_jump_history() {
     let mut jump_history = JumpHistory::default();
-    jump_history.push(Jump::File("file".to_string()));
-    jump_history.push(Jump::File("file2".to_string()));
+    jump_history.push(Jump::file("file"));
+    jump_history.push(Jump::file("file2"));
     jump_history.push(Jump::FileTree);
-    assert_eq!(jump_history.pop(), Jump::FileTree);
-    assert_eq!(jump_history.pop(), Jump::File("file2".to_string()));
-    assert_eq!(jump_history.pop(), Jump::File("file".to_string()));
-    assert_eq!(jump_history.pop(), Jump::FileTree);
-    assert_eq!(jump_history.pop(), Jump::FileTree);
-    assert_eq!(jump_history.pop(), Jump::FileTree);
+    assert_eq!(jump_history.back(None), Jump::FileTree);
+    assert_eq!(jump_history.back(None), Jump::file("file2"));
+    assert_eq!(
+        jump_history.back(Some(Jump::file("file2"))),
+        Jump::file("file")
+    );
+    assert_eq!(
+        jump_history.forward(Some(Jump::file("file"))),
+        Some(Jump::file("file2"))
+    );
+    assert_eq!(jump_history.forward(None), None);
+    assert_eq!(jump_history.back(None), Jump::file("file"));
+    assert_eq!(jump_history.back(None), Jump::FileTree);
+    // Nothing left to go back to, so there is nothing to go forward to
+    // from the file tree either
+    assert_eq!(jump_history.back(Some(Jump::FileTree)), Jump::FileTree);
+    assert_eq!(jump_history.back(Some(Jump::FileTree)), Jump::FileTree);
+    assert_eq!(jump_history.forward(None), None);
+    jump_history.push(Jump::file("file3"));
+    assert_eq!(jump_history.forward(None), None);
 }
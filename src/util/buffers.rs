@@ -0,0 +1,337 @@
+use std::{cmp, fs::read_to_string, mem, path::PathBuf};
+
+use crate::{
+    nodes::root::ComponentRoot,
+    parser::parse_markdown,
+    util::{App, canonical_path},
+};
+
+/// The part of `App` that belongs to a single document and has to survive
+/// switching away from it and back again.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ViewState {
+    pub vertical_scroll: u16,
+    pub selected: bool,
+    pub select_index: usize,
+    pub details_selected: bool,
+    pub details_select_index: usize,
+    pub task_selected: bool,
+    pub task_select_index: usize,
+    pub wide_selected: bool,
+    pub wide_select_index: usize,
+    pub table_selected: bool,
+    pub table_select_index: usize,
+}
+
+impl ViewState {
+    fn save(app: &App) -> Self {
+        Self {
+            vertical_scroll: app.vertical_scroll,
+            selected: app.selected,
+            select_index: app.select_index,
+            details_selected: app.details_selected,
+            details_select_index: app.details_select_index,
+            task_selected: app.task_selected,
+            task_select_index: app.task_select_index,
+            wide_selected: app.wide_selected,
+            wide_select_index: app.wide_select_index,
+            table_selected: app.table_selected,
+            table_select_index: app.table_select_index,
+        }
+    }
+
+    fn restore(self, app: &mut App) {
+        app.vertical_scroll = self.vertical_scroll;
+        app.selected = self.selected;
+        app.select_index = self.select_index;
+        app.details_selected = self.details_selected;
+        app.details_select_index = self.details_select_index;
+        app.task_selected = self.task_selected;
+        app.task_select_index = self.task_select_index;
+        app.wide_selected = self.wide_selected;
+        app.wide_select_index = self.wide_select_index;
+        app.table_selected = self.table_selected;
+        app.table_select_index = self.table_select_index;
+    }
+}
+
+struct Buffer {
+    /// Holds an empty placeholder while the buffer is the active one, the
+    /// real root lives in the caller's `markdown` then.
+    root: ComponentRoot,
+    file_name: Option<String>,
+    /// `file_name` resolved, to find the buffer by any name of its file.
+    path: Option<PathBuf>,
+    state: ViewState,
+    width: u16,
+    stale: bool,
+}
+
+/// Every document that has been opened in this session. Only the active
+/// buffer's root is handed to the renderer and the key handlers; the rest
+/// keep their parsed components, search marks and fold state so switching
+/// back is instant.
+#[derive(Default)]
+pub struct BufferList {
+    buffers: Vec<Buffer>,
+    current: Option<usize>,
+    last_active: Option<usize>,
+}
+
+impl BufferList {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    /// Index of the active buffer, `None` while in the file tree.
+    #[must_use]
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    #[must_use]
+    pub fn file_names(&self) -> Vec<Option<&str>> {
+        self.buffers
+            .iter()
+            .map(|b| b.file_name.as_deref())
+            .collect()
+    }
+
+    #[must_use]
+    pub fn position(&self, file_name: &str) -> Option<usize> {
+        let path = canonical_path(file_name);
+        self.buffers
+            .iter()
+            .position(|b| b.path.as_ref() == Some(&path))
+    }
+
+    /// Make `root` the active buffer. It is inserted right after the
+    /// currently active one, and `app` starts from a fresh view state, or
+    /// from where the file was left last time.
+    pub fn push(&mut self, markdown: &mut ComponentRoot, app: &mut App, root: ComponentRoot) {
+        self.park(markdown, app);
+        let index = self.current_or_last().map_or(0, |i| i + 1);
+        self.buffers.insert(
+            index,
+            Buffer {
+                root: ComponentRoot::new(None, Vec::new()),
+                file_name: root.file_name().map(str::to_string),
+                path: root.file_name().map(canonical_path),
+                state: ViewState::default(),
+                width: app.width(),
+                stale: false,
+            },
+        );
+        *markdown = root;
+        ViewState::default().restore(app);
+        if let Some(position) = markdown
+            .file_name()
+            .and_then(|file_name| app.positions.get(file_name))
+            .cloned()
+        {
+            markdown.set_details_folds(&position.folds);
+            app.vertical_scroll = cmp::min(position.scroll, markdown.height());
+        }
+        self.current = Some(index);
+        self.last_active = Some(index);
+    }
+
+    /// Switch to the buffer at `index`, stashing the active one.
+    pub fn activate(&mut self, index: usize, markdown: &mut ComponentRoot, app: &mut App) {
+        if index >= self.buffers.len() || self.current == Some(index) {
+            return;
+        }
+        self.park(markdown, app);
+
+        let buffer = &mut self.buffers[index];
+        mem::swap(markdown, &mut buffer.root);
+
+        if (buffer.stale || buffer.width != app.width())
+            && let Some(file_name) = buffer.file_name.as_deref()
+            && let Ok(text) = read_to_string(file_name)
+        {
+            reparse(markdown, &text, app.width() - 2);
+            buffer.state.selected = false;
+            buffer.state.details_selected = false;
+            buffer.state.task_selected = false;
+            buffer.state.table_selected = false;
+        }
+        buffer.stale = false;
+        buffer.width = app.width();
+
+        buffer.state.restore(app);
+        app.vertical_scroll = cmp::min(app.vertical_scroll, markdown.height());
+        self.current = Some(index);
+        self.last_active = Some(index);
+    }
+
+    /// Switch to the buffer showing `file_name`. Returns `false` if no such
+    /// buffer is open.
+    pub fn activate_file(
+        &mut self,
+        file_name: &str,
+        markdown: &mut ComponentRoot,
+        app: &mut App,
+    ) -> bool {
+        if let Some(index) = self.position(file_name) {
+            self.activate(index, markdown, app);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn next(&mut self, markdown: &mut ComponentRoot, app: &mut App) {
+        if self.buffers.is_empty() {
+            return;
+        }
+        let index = self.current.map_or(0, |i| (i + 1) % self.buffers.len());
+        self.activate(index, markdown, app);
+    }
+
+    pub fn previous(&mut self, markdown: &mut ComponentRoot, app: &mut App) {
+        if self.buffers.is_empty() {
+            return;
+        }
+        let index = self.current.map_or(self.buffers.len() - 1, |i| {
+            (i + self.buffers.len() - 1) % self.buffers.len()
+        });
+        self.activate(index, markdown, app);
+    }
+
+    /// Stash the active buffer and leave `markdown` empty, e.g. before
+    /// going to the file tree.
+    pub fn park(&mut self, markdown: &mut ComponentRoot, app: &mut App) {
+        if let Some(index) = self.current.take() {
+            app.remember_position(markdown);
+            let buffer = &mut self.buffers[index];
+            buffer.state = ViewState::save(app);
+            mem::swap(markdown, &mut buffer.root);
+        }
+        markdown.clear();
+    }
+
+    /// Re-read the inactive buffers of `paths`, as given by
+    /// `canonical_path`, the next time they are shown, after the files
+    /// changed on disk.
+    pub fn invalidate_files(&mut self, paths: &[PathBuf]) {
+        for (i, buffer) in self.buffers.iter_mut().enumerate() {
+            if Some(i) != self.current
+                && buffer
+                    .path
+                    .as_ref()
+                    .is_some_and(|path| paths.contains(path))
+            {
+                buffer.stale = true;
+            }
+        }
+    }
+
+    /// Re-read every inactive buffer the next time it is shown, e.g. after
+    /// the terminal was resized.
+    pub fn invalidate_inactive(&mut self) {
+        for (i, buffer) in self.buffers.iter_mut().enumerate() {
+            if Some(i) != self.current {
+                buffer.stale = true;
+            }
+        }
+    }
+
+    fn current_or_last(&self) -> Option<usize> {
+        self.current.or(self.last_active)
+    }
+}
+
+/// Parse `text`, the file of `markdown` as it is now, into `markdown`.
+/// Folded details and search results are kept.
+pub fn reparse(markdown: &mut ComponentRoot, text: &str, width: u16) {
+    let folds = markdown.details_folds();
+    let search = markdown.search().map(str::to_owned);
+    *markdown = parse_markdown(markdown.file_name(), text, width);
+    markdown.set_details_folds(&folds);
+    if let Some(search) = search {
+        markdown.find_and_mark(&search);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switching_keeps_scroll_per_buffer() {
+        let mut app = App::default();
+        app.set_width(80);
+        let mut markdown = ComponentRoot::new(None, Vec::new());
+        let mut buffers = BufferList::new();
+
+        for name in ["a.md", "b.md"] {
+            let root = parse_markdown(Some(name), "# Title\n\ntext\n", 78);
+            buffers.push(&mut markdown, &mut app, root);
+        }
+        assert_eq!(buffers.current(), Some(1));
+        app.vertical_scroll = 2;
+
+        buffers.next(&mut markdown, &mut app);
+        assert_eq!(markdown.file_name(), Some("a.md"));
+        assert_eq!(app.vertical_scroll, 0);
+        app.vertical_scroll = 1;
+
+        buffers.previous(&mut markdown, &mut app);
+        assert_eq!(markdown.file_name(), Some("b.md"));
+        assert_eq!(app.vertical_scroll, 2);
+
+        buffers.park(&mut markdown, &mut app);
+        assert_eq!(markdown.file_name(), None);
+        assert!(buffers.activate_file("a.md", &mut markdown, &mut app));
+        assert_eq!(app.vertical_scroll, 1);
+    }
+
+    #[test]
+    fn changed_buffer_is_reread_with_its_search_results() {
+        let dir = std::env::temp_dir().join(format!("mdt-buffers-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let [a, b] = ["a.md", "b.md"].map(|name| dir.join(name));
+        std::fs::write(&a, "Some needle here\n").unwrap();
+        std::fs::write(&b, "Other text\n").unwrap();
+
+        let mut app = App::default();
+        app.set_width(80);
+        let mut markdown = ComponentRoot::new(None, Vec::new());
+        let mut buffers = BufferList::new();
+        for path in [&a, &b] {
+            let name = path.to_string_lossy();
+            let text = read_to_string(path).unwrap();
+            buffers.push(
+                &mut markdown,
+                &mut app,
+                parse_markdown(Some(&name), &text, 78),
+            );
+            markdown.find_and_mark("needle");
+        }
+
+        buffers.invalidate_files(std::slice::from_ref(&b));
+        assert!(!buffers.buffers[0].stale);
+        std::fs::write(&a, "A new needle\n\nand more\n").unwrap();
+        buffers.invalidate_files(std::slice::from_ref(&a));
+        assert!(buffers.buffers[0].stale);
+
+        buffers.previous(&mut markdown, &mut app);
+        assert_eq!(markdown.search(), Some("needle"));
+        assert!(markdown.words().iter().any(|word| word.content() == "A"));
+        assert_eq!(markdown.search_results_heights().len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
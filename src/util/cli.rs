@@ -0,0 +1,210 @@
+use std::path::PathBuf;
+
+use crate::util::general::ConfigOverrides;
+
+pub const USAGE: &str = "\
+A terminal markdown viewer
+
+Usage: mdt [OPTIONS] [FILE]...
+
+With no FILE, mdt reads from standard input if it is piped, otherwise it
+searches the current directory for markdown files. A FILE of - is standard
+input.
+
+Options:
+  -p, --print              Print the rendered markdown to stdout and exit
+  -w, --width <N>          Maximum width of the document (0 for full width)
+  -a, --alignment <ALIGN>  Document alignment: left, center or right
+      --no-images          Do not load or render images
+  -c, --config <PATH>      Read configuration from PATH instead of
+                           ~/.config/mdt/config.toml
+      --theme <PATH>       Read colors from PATH on top of the configuration
+  -l, --line <N>           Open the first file at source line N
+      --heading <ANCHOR>   Open the first file at heading ANCHOR
+  -h, --help               Print this help and exit
+  -V, --version            Print the version and exit";
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Cli {
+    pub files: Vec<String>,
+    pub print: bool,
+    pub help: bool,
+    pub version: bool,
+    pub width: Option<u16>,
+    pub alignment: Option<String>,
+    pub no_images: bool,
+    pub config: Option<String>,
+    pub theme: Option<String>,
+    /// Line in the source of the first file, counted from one.
+    pub line: Option<usize>,
+    pub heading: Option<String>,
+}
+
+impl Cli {
+    /// Parse command line arguments, not including the program name.
+    /// Both `--flag value` and `--flag=value` are accepted, and everything
+    /// after a lone `--` is treated as a file.
+    pub fn parse<I>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                cli.files.extend(args.by_ref());
+                break;
+            }
+
+            if !arg.starts_with('-') || arg == "-" {
+                cli.files.push(arg);
+                continue;
+            }
+
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_owned(), Some(value.to_owned()))
+                }
+                _ => (arg.clone(), None),
+            };
+
+            let mut value = |name: &str| -> Result<String, String> {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("Missing value for {name}"))
+            };
+
+            match flag.as_str() {
+                "-p" | "--print" => cli.print = true,
+                "-h" | "--help" => cli.help = true,
+                "-V" | "-v" | "--version" => cli.version = true,
+                "--no-images" => cli.no_images = true,
+                "-w" | "--width" => {
+                    let width = value(&flag)?;
+                    cli.width = Some(
+                        width
+                            .parse()
+                            .map_err(|_| format!("Invalid width: {width}"))?,
+                    );
+                }
+                "-a" | "--alignment" => {
+                    let alignment = value(&flag)?.to_lowercase();
+                    if !matches!(alignment.as_str(), "left" | "center" | "right") {
+                        return Err(format!(
+                            "Invalid alignment: {alignment} (expected left, center or right)"
+                        ));
+                    }
+                    cli.alignment = Some(alignment);
+                }
+                "-c" | "--config" => cli.config = Some(value(&flag)?),
+                "--theme" => cli.theme = Some(value(&flag)?),
+                "-l" | "--line" => {
+                    let line = value(&flag)?;
+                    cli.line = Some(
+                        line.parse()
+                            .map_err(|_| format!("Invalid line number: {line}"))?,
+                    );
+                }
+                "--heading" => {
+                    let heading = value(&flag)?;
+                    cli.heading = Some(heading.trim_start_matches('#').to_owned());
+                }
+                _ => return Err(format!("Unknown option: {arg}")),
+            }
+        }
+
+        Ok(cli)
+    }
+
+    /// Configuration overrides implied by the flags.
+    #[must_use]
+    pub fn config_overrides(&self) -> ConfigOverrides {
+        let mut values = Vec::new();
+
+        if let Some(width) = self.width {
+            values.push(("width".to_owned(), width.to_string()));
+        }
+
+        if let Some(alignment) = &self.alignment {
+            let centering = match alignment.as_str() {
+                "center" => "Center",
+                "right" => "Right",
+                _ => "Left",
+            };
+            values.push(("alignment".to_owned(), centering.to_owned()));
+        }
+
+        if self.no_images {
+            values.push(("images".to_owned(), "false".to_owned()));
+        }
+
+        ConfigOverrides {
+            config_file: self.config.as_ref().map(PathBuf::from),
+            theme_file: self.theme.as_ref().map(PathBuf::from),
+            values,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, String> {
+        Cli::parse(args.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn parses_files_and_flags() {
+        let cli = parse(&["--width", "80", "a.md", "--no-images", "b.md"]).unwrap();
+        assert_eq!(cli.files, vec!["a.md", "b.md"]);
+        assert_eq!(cli.width, Some(80));
+        assert!(cli.no_images);
+    }
+
+    #[test]
+    fn parses_inline_values() {
+        let cli = parse(&["--alignment=Center", "--heading=#usage", "README.md"]).unwrap();
+        assert_eq!(cli.alignment.as_deref(), Some("center"));
+        assert_eq!(cli.heading.as_deref(), Some("usage"));
+        assert_eq!(cli.files, vec!["README.md"]);
+    }
+
+    #[test]
+    fn dash_is_standard_input() {
+        let cli = parse(&["-", "--line", "120", "notes.md"]).unwrap();
+        assert_eq!(cli.files, vec!["-", "notes.md"]);
+        assert_eq!(cli.line, Some(120));
+    }
+
+    #[test]
+    fn double_dash_ends_options() {
+        let cli = parse(&["--", "--print"]).unwrap();
+        assert!(!cli.print);
+        assert_eq!(cli.files, vec!["--print"]);
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(parse(&["--bogus"]).is_err());
+        assert!(parse(&["--width"]).is_err());
+        assert!(parse(&["--width", "wide"]).is_err());
+        assert!(parse(&["--alignment", "middle"]).is_err());
+    }
+
+    #[test]
+    fn overrides_use_config_keys() {
+        let cli = parse(&["-w", "0", "-a", "right", "--no-images"]).unwrap();
+        let overrides = cli.config_overrides();
+        assert_eq!(
+            overrides.values,
+            vec![
+                ("width".to_owned(), "0".to_owned()),
+                ("alignment".to_owned(), "Right".to_owned()),
+                ("images".to_owned(), "false".to_owned()),
+            ]
+        );
+    }
+}
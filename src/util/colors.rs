@@ -3,9 +3,10 @@ use std::{
     sync::{Arc, LazyLock, RwLock},
 };
 
-use config::{Config, Environment, File};
 use ratatui::style::Color;
 
+use crate::util::general::{load_file_settings, load_settings};
+
 #[derive(Debug, Clone, Copy)]
 pub struct ColorConfig {
     // Inline styles
@@ -18,6 +19,9 @@ pub struct ColorConfig {
     pub link_color: Color,
     pub link_selected_fg_color: Color,
     pub link_selected_bg_color: Color,
+    pub math_color: Color,
+    pub highlight_fg_color: Color,
+    pub highlight_bg_color: Color,
 
     // Block styles
     pub code_block_bg_color: Color,
@@ -44,13 +48,7 @@ pub struct ColorConfig {
 
 #[must_use]
 pub fn read_color_config_from_file() -> ColorConfig {
-    let config_dir = dirs::home_dir().unwrap();
-    let config_file = config_dir.join(".config").join("mdt").join("config.toml");
-    let settings = Config::builder()
-        .add_source(File::with_name(config_file.to_str().unwrap()).required(false))
-        .add_source(Environment::with_prefix("MDT").separator("_"))
-        .build()
-        .unwrap();
+    let settings = load_settings();
 
     ColorConfig {
         heading_bg_color: Color::from_str(
@@ -91,6 +89,20 @@ pub fn read_color_config_from_file() -> ColorConfig {
         .unwrap_or(Color::Rgb(48, 48, 48)),
         link_color: Color::from_str(&settings.get::<String>("link_color").unwrap_or_default())
             .unwrap_or(Color::Blue),
+        math_color: Color::from_str(&settings.get::<String>("math_color").unwrap_or_default())
+            .unwrap_or(Color::LightCyan),
+        highlight_fg_color: Color::from_str(
+            &settings
+                .get::<String>("highlight_fg_color")
+                .unwrap_or_default(),
+        )
+        .unwrap_or(Color::Black),
+        highlight_bg_color: Color::from_str(
+            &settings
+                .get::<String>("highlight_bg_color")
+                .unwrap_or_default(),
+        )
+        .unwrap_or(Color::Yellow),
         link_selected_fg_color: Color::from_str(
             &settings
                 .get::<String>("link_selected_fg_color")
@@ -198,12 +210,7 @@ pub struct HeadingColors {
 
 #[must_use]
 pub fn read_heading_colors_from_file() -> HeadingColors {
-    let config_dir = dirs::home_dir().unwrap();
-    let config_file = config_dir.join(".config").join("mdt").join("config.toml");
-    let settings = Config::builder()
-        .add_source(File::with_name(config_file.to_str().unwrap()).required(false))
-        .build()
-        .unwrap();
+    let settings = load_file_settings();
 
     HeadingColors {
         level_2: settings
@@ -0,0 +1,291 @@
+//! Getting code blocks and tables out of mdt as text: code as written and
+//! tables as markdown, CSV or TSV, copied to the clipboard or saved.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use itertools::Itertools;
+use ratatui::layout::Alignment;
+use unicode_width::UnicodeWidthStr;
+
+use crate::nodes::{
+    root::ComponentRoot,
+    textcomponent::{TextComponent, TextNode},
+    word::WordType,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Markdown,
+    Csv,
+    Tsv,
+}
+
+impl TableFormat {
+    /// The format called `name`, which is also the extension of its files.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "csv" => Some(Self::Csv),
+            "tsv" => Some(Self::Tsv),
+            _ => None,
+        }
+    }
+}
+
+/// The code of a code block, or the cells of a table in `format`.
+#[must_use]
+pub fn component_text(component: &TextComponent, format: TableFormat) -> Option<String> {
+    match component.kind() {
+        TextNode::CodeBlock => component.code().map(ToOwned::to_owned),
+        TextNode::Table(_, _, alignments) => {
+            Some(format_table(&component.table_cells(), &alignments, format))
+        }
+        _ => None,
+    }
+}
+
+/// The target of the selected link, or what a selected footnote stands
+/// for, the text `Hover` shows.
+#[must_use]
+pub fn link_text(markdown: &ComponentRoot) -> String {
+    let link = markdown.selected();
+    match markdown.selected_underlying_type() {
+        WordType::FootnoteInline => markdown.find_footnote(link),
+        _ => link.to_owned(),
+    }
+}
+
+/// `file.md#anchor` for the heading at or above line `scroll`, to link to
+/// where the user is reading.
+pub fn permalink(markdown: &ComponentRoot, scroll: u16) -> Result<String, String> {
+    let file_name = markdown
+        .file_name()
+        .ok_or("No file to link to".to_owned())?;
+    let anchor = markdown
+        .heading_anchor(scroll)
+        .ok_or("No heading above the view".to_owned())?;
+    Ok(format!("{file_name}#{anchor}"))
+}
+
+/// `rows` as a table in `format`, header first, one line per row.
+#[must_use]
+pub fn format_table(rows: &[Vec<String>], alignments: &[Alignment], format: TableFormat) -> String {
+    match format {
+        TableFormat::Csv => rows
+            .iter()
+            .map(|row| row.iter().map(|cell| csv_field(cell)).join(","))
+            .join("\n"),
+        TableFormat::Tsv => rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.replace(['\t', '\n'], " "))
+                    .join("\t")
+            })
+            .join("\n"),
+        TableFormat::Markdown => markdown_table(rows, alignments),
+    }
+}
+
+fn csv_field(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_owned()
+    }
+}
+
+/// A GitHub table with its columns lined up and the alignment of each in
+/// the line below the header.
+fn markdown_table(rows: &[Vec<String>], alignments: &[Alignment]) -> String {
+    let rows = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.replace('|', "\\|"))
+                .collect_vec()
+        })
+        .collect_vec();
+    let column_count = rows.first().map_or(0, Vec::len);
+    // Room for the dashes and colons of the delimiter row
+    let widths = (0..column_count)
+        .map(|i| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .map(|cell| cell.width())
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect_vec();
+    let alignment = |i: usize| alignments.get(i).copied().unwrap_or(Alignment::Left);
+
+    let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+    let row_line = |row: &Vec<String>| {
+        line(
+            widths
+                .iter()
+                .enumerate()
+                .map(|(i, width)| {
+                    let cell = row.get(i).map_or("", String::as_str);
+                    let padding = " ".repeat(width.saturating_sub(cell.width()));
+                    if alignment(i) == Alignment::Right {
+                        format!("{padding}{cell}")
+                    } else {
+                        format!("{cell}{padding}")
+                    }
+                })
+                .collect(),
+        )
+    };
+    let delimiter = line(
+        widths
+            .iter()
+            .enumerate()
+            .map(|(i, width)| match alignment(i) {
+                Alignment::Left => "-".repeat(*width),
+                Alignment::Center => format!(":{}:", "-".repeat(width - 2)),
+                Alignment::Right => format!("{}:", "-".repeat(width - 1)),
+            })
+            .collect(),
+    );
+
+    let mut lines = rows.iter().map(row_line).collect_vec();
+    lines.insert(1.min(lines.len()), delimiter);
+    lines.join("\n")
+}
+
+/// Copy `text` to the system clipboard with the OSC 52 terminal escape. It
+/// needs no clipboard tool and works over SSH, in terminals that allow it.
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()
+}
+
+/// Write `text` to the file at `path`, where `~/` is the home directory.
+/// Returns the path written to.
+pub fn save_to_file(path: &str, text: &str) -> io::Result<PathBuf> {
+    let path = match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    };
+    fs::write(&path, format!("{text}\n"))?;
+    Ok(path)
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | u32::from(*byte) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nodes::root::ComponentRoot, parser::parse_markdown};
+
+    fn rows(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect()
+    }
+
+    #[test]
+    fn tables_are_formatted_as_markdown_csv_and_tsv() {
+        let rows = rows(&[&["Name", "Qty"], &["a|b", "12"], &["say \"hi\", bye", "3"]]);
+        let alignments = [Alignment::Left, Alignment::Right];
+
+        assert_eq!(
+            format_table(&rows, &alignments, TableFormat::Markdown),
+            "| Name          | Qty |\n\
+             | ------------- | --: |\n\
+             | a\\|b          |  12 |\n\
+             | say \"hi\", bye |   3 |"
+        );
+        assert_eq!(
+            format_table(&rows, &alignments, TableFormat::Csv),
+            "Name,Qty\na|b,12\n\"say \"\"hi\"\", bye\",3"
+        );
+        assert_eq!(
+            format_table(&rows, &alignments, TableFormat::Tsv),
+            "Name\tQty\na|b\t12\nsay \"hi\", bye\t3"
+        );
+    }
+
+    #[test]
+    fn yanks_a_selected_table_as_shown() {
+        let md = "Intro\n\n| Item | Qty |\n|:-:|---:|\n| Widget | 12 |\n| Gadget | 1,200 |\n| Bolt | 7 |\n";
+        let mut root = parse_markdown(None, md, 40);
+        let yank = |root: &ComponentRoot, format| {
+            component_text(root.export_target(0, 20).unwrap(), format).unwrap()
+        };
+        assert_eq!(
+            yank(&root, TableFormat::Tsv),
+            "Item\tQty\nWidget\t12\nGadget\t1,200\nBolt\t7"
+        );
+
+        root.select_table(0, 40).unwrap();
+        root.update_table(40, |view| {
+            view.move_cursor(0, 1);
+            view.cycle_sort();
+            view.set_filter("DG");
+        })
+        .unwrap();
+        assert_eq!(
+            yank(&root, TableFormat::Markdown),
+            "| Item   |   Qty |\n\
+             | :----: | ----: |\n\
+             | Widget |    12 |\n\
+             | Gadget | 1,200 |"
+        );
+    }
+
+    #[test]
+    fn yanks_a_selected_link_or_a_permalink() {
+        let md = "# Intro\n\nSee [the docs](https://example.com/docs) and[^1].\n\n## Getting started\n\nText\n\n[^1]: A note.\n";
+        let mut root = parse_markdown(Some("notes.md"), md, 40);
+        root.select(0).unwrap();
+        assert_eq!(link_text(&root), "https://example.com/docs");
+        root.select(1).unwrap();
+        assert_eq!(link_text(&root), "A note.");
+
+        assert_eq!(permalink(&root, 0), Ok("notes.md#intro".to_owned()));
+        assert_eq!(
+            permalink(&root, 5),
+            Ok("notes.md#getting-started".to_owned())
+        );
+
+        let root = parse_markdown(None, md, 40);
+        assert!(permalink(&root, 0).is_err());
+        let root = parse_markdown(Some("notes.md"), "No heading\n", 40);
+        assert!(permalink(&root, 0).is_err());
+    }
+
+    #[test]
+    fn encodes_base64_with_padding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64("mdt ✓".as_bytes()), "bWR0IOKckw==");
+    }
+}
@@ -1,14 +1,28 @@
-use std::sync::LazyLock;
+use std::{
+    path::PathBuf,
+    sync::{LazyLock, OnceLock},
+};
 
-use config::{Config, Environment, File};
+use config::{Config, ConfigError, Environment, File};
 use serde::Deserialize;
 
+use super::export::TableFormat;
+
 #[derive(Debug)]
 pub struct GeneralConfig {
     pub width: u16,
     pub gitignore: bool,
     pub centering: Centering,
     pub help_menu: bool,
+    pub images: bool,
+    pub persist_history: bool,
+    pub remember_position: bool,
+    pub mouse: bool,
+    /// Command run by `edit`, see `util::editor_command`. Without one it
+    /// depends on the editor, see `util::default_editor_command`.
+    pub editor_command: Option<String>,
+    /// Format of yanked tables, unless the file saved to says otherwise.
+    pub table_format: TableFormat,
 }
 
 #[derive(Debug, Deserialize)]
@@ -18,14 +32,74 @@ pub enum Centering {
     Right,
 }
 
-pub static GENERAL_CONFIG: LazyLock<GeneralConfig> = LazyLock::new(|| {
+/// Settings given on the command line. They take precedence over both the
+/// config file and `MDT_*` environment variables.
+#[derive(Debug, Default, Clone)]
+pub struct ConfigOverrides {
+    pub config_file: Option<PathBuf>,
+    pub theme_file: Option<PathBuf>,
+    pub values: Vec<(String, String)>,
+}
+
+static CONFIG_OVERRIDES: OnceLock<ConfigOverrides> = OnceLock::new();
+
+/// Register command line overrides. Must be called before any of the
+/// configuration statics are first accessed, otherwise it has no effect.
+pub fn set_config_overrides(overrides: ConfigOverrides) {
+    let _ = CONFIG_OVERRIDES.set(overrides);
+}
+
+#[must_use]
+pub fn default_config_file() -> PathBuf {
     let config_dir = dirs::home_dir().unwrap();
-    let config_file = config_dir.join(".config").join("mdt").join("config.toml");
-    let settings = Config::builder()
-        .add_source(File::with_name(config_file.to_str().unwrap()).required(false))
-        .add_source(Environment::with_prefix("MDT").separator("_"))
-        .build()
-        .unwrap();
+    config_dir.join(".config").join("mdt").join("config.toml")
+}
+
+/// Build the layered settings used by every configuration section: config
+/// file, optional theme file, environment and finally command line.
+pub(crate) fn load_settings() -> Config {
+    build_settings(true).unwrap()
+}
+
+/// Like `load_settings`, without the `MDT_*` environment variables.
+pub(crate) fn load_file_settings() -> Config {
+    build_settings(false).unwrap()
+}
+
+/// Why the settings cannot be loaded, e.g. because a config file given on
+/// the command line does not exist. Check before any configuration is read.
+#[must_use]
+pub fn settings_error() -> Option<String> {
+    build_settings(true).err().map(|e| e.to_string())
+}
+
+fn build_settings(environment: bool) -> Result<Config, ConfigError> {
+    let overrides = CONFIG_OVERRIDES.get().cloned().unwrap_or_default();
+
+    // Files the user named have to exist, the default one does not
+    let config_file = overrides.config_file.as_ref().map_or_else(
+        || File::with_name(&default_config_file().to_string_lossy()).required(false),
+        |path| File::with_name(&path.to_string_lossy()).required(true),
+    );
+    let mut builder = Config::builder().add_source(config_file);
+
+    if let Some(theme_file) = overrides.theme_file {
+        builder = builder.add_source(File::with_name(&theme_file.to_string_lossy()).required(true));
+    }
+
+    if environment {
+        builder = builder.add_source(Environment::with_prefix("MDT").separator("_"));
+    }
+
+    for (key, value) in overrides.values {
+        builder = builder.set_override(key, value)?;
+    }
+
+    builder.build()
+}
+
+pub static GENERAL_CONFIG: LazyLock<GeneralConfig> = LazyLock::new(|| {
+    let settings = load_settings();
 
     let width = settings.get::<u16>("width").unwrap_or(100);
     GeneralConfig {
@@ -36,5 +110,15 @@ pub static GENERAL_CONFIG: LazyLock<GeneralConfig> = LazyLock::new(|| {
             .get::<Centering>("alignment")
             .unwrap_or(Centering::Left),
         help_menu: settings.get::<bool>("help_menu").unwrap_or(true),
+        images: settings.get::<bool>("images").unwrap_or(true),
+        persist_history: settings.get::<bool>("persist_history").unwrap_or(false),
+        remember_position: settings.get::<bool>("remember_position").unwrap_or(true),
+        mouse: settings.get::<bool>("mouse").unwrap_or(true),
+        editor_command: settings.get::<String>("editor_command").ok(),
+        table_format: settings
+            .get::<String>("table_format")
+            .ok()
+            .and_then(|name| TableFormat::from_name(&name))
+            .unwrap_or(TableFormat::Markdown),
     }
 });
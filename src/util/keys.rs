@@ -1,8 +1,11 @@
-use std::sync::LazyLock;
+use std::{fmt, sync::LazyLock};
 
-use config::{Config, Environment, File};
-use crossterm::event::KeyCode;
+use config::Config;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
+use crate::util::{general::load_settings, marks::Marks};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Action {
     Up,
     Down,
@@ -14,6 +17,18 @@ pub enum Action {
     SelectLink,
     SelectLinkAlt,
     SelectDetails,
+    SelectTask,
+    SelectWide,
+    ScrollLeft,
+    ScrollRight,
+    SelectTable,
+    Left,
+    Right,
+    Yank,
+    YankToFile,
+    CopyPermalink,
+    CopyView,
+    FrontMatter,
     SearchNext,
     SearchPrevious,
     Edit,
@@ -24,160 +39,821 @@ pub enum Action {
     ToBottom,
     Help,
     Back,
+    Forward,
     ToFileTree,
     Sort,
+    Toc,
+    SetMark,
+    JumpToMark,
+    Marks,
+    NextBuffer,
+    PreviousBuffer,
+    Quit,
+    DeleteBack,
     None,
 }
 
-#[derive(Debug)]
-pub struct KeyConfig {
-    pub up: char,
-    pub down: char,
-    pub page_up: char,
-    pub page_down: char,
-    pub half_page_up: char,
-    pub half_page_down: char,
-    pub search: char,
-    pub search_next: char,
-    pub search_previous: char,
-    pub select_link: char,
-    pub select_link_alt: char,
-    pub select_details: char,
-    pub edit: char,
-    pub hover: char,
-    pub top: char,
-    pub bottom: char,
-    pub back: char,
-    pub file_tree: char,
-    pub sort: char,
+impl Action {
+    /// Whether a count typed before the action repeats it, like `3n`.
+    /// Movements like `5j` take their count at once instead.
+    #[must_use]
+    pub fn is_repeatable(self) -> bool {
+        matches!(
+            self,
+            Action::SearchNext
+                | Action::SearchPrevious
+                | Action::Back
+                | Action::Forward
+                | Action::NextBuffer
+                | Action::PreviousBuffer
+                | Action::ScrollLeft
+                | Action::ScrollRight
+                | Action::Left
+                | Action::Right
+        )
+    }
+
+    /// Whether the action is followed by the letter of a mark, like `ma`.
+    #[must_use]
+    pub fn takes_mark(self) -> bool {
+        matches!(self, Action::SetMark | Action::JumpToMark)
+    }
 }
 
-#[must_use]
-pub fn key_to_action(key: KeyCode) -> Action {
-    match key {
-        KeyCode::Char(c) => {
-            if c == KEY_CONFIG.up {
-                return Action::Up;
-            }
+/// Config key, action and default bindings for everything that can be
+/// rebound in a scope. A config value is either a single binding or a list
+/// of them.
+type Bindings = &'static [(&'static str, Action, &'static [&'static str])];
 
-            if c == KEY_CONFIG.down {
-                return Action::Down;
-            }
+const VIEW_BINDINGS: Bindings = &[
+    ("up", Action::Up, &["k"]),
+    ("down", Action::Down, &["j"]),
+    ("page_up", Action::PageUp, &["u"]),
+    ("page_down", Action::PageDown, &["d"]),
+    ("half_page_up", Action::HalfPageUp, &["h", "<C-u>"]),
+    ("half_page_down", Action::HalfPageDown, &["l", "<C-d>"]),
+    ("search", Action::Search, &["f", "/"]),
+    ("search_next", Action::SearchNext, &["n"]),
+    ("search_previous", Action::SearchPrevious, &["N"]),
+    ("select_link", Action::SelectLink, &["s"]),
+    ("select_link_alt", Action::SelectLinkAlt, &["S"]),
+    ("select_details", Action::SelectDetails, &["D"]),
+    ("select_task", Action::SelectTask, &["x"]),
+    ("select_wide", Action::SelectWide, &["w"]),
+    ("scroll_left", Action::ScrollLeft, &["H"]),
+    ("scroll_right", Action::ScrollRight, &["L"]),
+    ("select_table", Action::SelectTable, &["c"]),
+    ("yank", Action::Yank, &["y"]),
+    ("yank_to_file", Action::YankToFile, &["Y"]),
+    ("copy_permalink", Action::CopyPermalink, &["p"]),
+    ("copy_view", Action::CopyView, &["v"]),
+    ("front_matter", Action::FrontMatter, &["F"]),
+    ("edit", Action::Edit, &["e"]),
+    ("hover", Action::Hover, &["K"]),
+    ("top", Action::ToTop, &["g"]),
+    ("bottom", Action::ToBottom, &["G"]),
+    ("back", Action::Back, &["b", "<C-o>"]),
+    ("forward", Action::Forward, &["B", "<C-i>"]),
+    ("file_tree", Action::ToFileTree, &["t"]),
+    ("toc", Action::Toc, &["T"]),
+    ("mark", Action::SetMark, &["m"]),
+    ("jump_to_mark", Action::JumpToMark, &["'"]),
+    ("marks", Action::Marks, &["M"]),
+    ("next_buffer", Action::NextBuffer, &["]b"]),
+    ("previous_buffer", Action::PreviousBuffer, &["[b"]),
+    ("help", Action::Help, &["?"]),
+    ("quit", Action::Quit, &["q"]),
+];
 
-            if c == KEY_CONFIG.page_up {
-                return Action::PageUp;
-            }
+const FILE_TREE_BINDINGS: Bindings = &[
+    ("up", Action::Up, &["k"]),
+    ("down", Action::Down, &["j"]),
+    ("page_up", Action::PageUp, &["u"]),
+    ("page_down", Action::PageDown, &["d"]),
+    ("search", Action::Search, &["f", "/"]),
+    ("top", Action::ToTop, &["g"]),
+    ("bottom", Action::ToBottom, &["G"]),
+    ("back", Action::Back, &["b", "<C-o>"]),
+    ("forward", Action::Forward, &["B", "<C-i>"]),
+    ("sort", Action::Sort, &["o"]),
+    ("jump_to_mark", Action::JumpToMark, &["'"]),
+    ("next_buffer", Action::NextBuffer, &["]b"]),
+    ("previous_buffer", Action::PreviousBuffer, &["[b"]),
+    ("help", Action::Help, &["?"]),
+    ("quit", Action::Quit, &["q"]),
+];
 
-            if c == KEY_CONFIG.page_down {
-                return Action::PageDown;
-            }
+/// While a table is selected the cursor moves between its cells, and
+/// searching filters its rows.
+const TABLE_BINDINGS: Bindings = &[
+    ("up", Action::Up, &["k"]),
+    ("down", Action::Down, &["j"]),
+    ("left", Action::Left, &["h"]),
+    ("right", Action::Right, &["l"]),
+    ("top", Action::ToTop, &["g"]),
+    ("bottom", Action::ToBottom, &["G"]),
+    ("sort", Action::Sort, &["o"]),
+    ("search", Action::Search, &["f", "/"]),
+    ("hover", Action::Hover, &["K"]),
+    ("select_table", Action::SelectTable, &["c"]),
+    ("yank", Action::Yank, &["y"]),
+    ("yank_to_file", Action::YankToFile, &["Y"]),
+    ("help", Action::Help, &["?"]),
+    ("quit", Action::Quit, &["q"]),
+];
 
-            if c == KEY_CONFIG.half_page_up {
-                return Action::HalfPageUp;
-            }
+/// Keys typed into the search box that are not bound here are inserted
+/// as text.
+const SEARCH_BINDINGS: Bindings = &[
+    ("confirm", Action::Enter, &["<Enter>"]),
+    ("cancel", Action::Escape, &["<Esc>"]),
+    ("delete", Action::DeleteBack, &["<BS>"]),
+];
 
-            if c == KEY_CONFIG.half_page_down {
-                return Action::HalfPageDown;
-            }
+/// Bindings that are always there in the view and the file tree, on top
+/// of the configurable ones.
+const FIXED_BINDINGS: &[(&str, Action)] = &[
+    ("<Up>", Action::Up),
+    ("<Down>", Action::Down),
+    ("<PageUp>", Action::PageUp),
+    ("<PageDown>", Action::PageDown),
+    ("<Right>", Action::PageDown),
+    ("<Left>", Action::PageUp),
+    ("<Enter>", Action::Enter),
+    ("<Esc>", Action::Escape),
+];
 
-            if c == KEY_CONFIG.search || c == '/' {
-                return Action::Search;
-            }
+/// The arrow keys move the cursor of a selected table.
+const TABLE_FIXED_BINDINGS: &[(&str, Action)] = &[
+    ("<Up>", Action::Up),
+    ("<Down>", Action::Down),
+    ("<Left>", Action::Left),
+    ("<Right>", Action::Right),
+    ("<Enter>", Action::Enter),
+    ("<Esc>", Action::Escape),
+];
 
-            if c == KEY_CONFIG.select_link {
-                return Action::SelectLink;
-            }
+/// Where a key press happens. Each scope has its own keymap, configured
+/// in the `[keys.<scope>]` table of the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyScope {
+    View,
+    FileTree,
+    Table,
+    Search,
+}
 
-            if c == KEY_CONFIG.select_link_alt {
-                return Action::SelectLinkAlt;
-            }
+impl KeyScope {
+    const ALL: [KeyScope; 4] = [
+        KeyScope::View,
+        KeyScope::FileTree,
+        KeyScope::Table,
+        KeyScope::Search,
+    ];
 
-            if c == KEY_CONFIG.select_details {
-                return Action::SelectDetails;
-            }
+    #[must_use]
+    pub fn table(self) -> &'static str {
+        match self {
+            KeyScope::View => "view",
+            KeyScope::FileTree => "file_tree",
+            KeyScope::Table => "table",
+            KeyScope::Search => "search",
+        }
+    }
 
-            if c == KEY_CONFIG.search_next {
-                return Action::SearchNext;
-            }
+    fn bindings(self) -> Bindings {
+        match self {
+            KeyScope::View => VIEW_BINDINGS,
+            KeyScope::FileTree => FILE_TREE_BINDINGS,
+            KeyScope::Table => TABLE_BINDINGS,
+            KeyScope::Search => SEARCH_BINDINGS,
+        }
+    }
 
-            if c == KEY_CONFIG.search_previous {
-                return Action::SearchPrevious;
-            }
+    fn fixed_bindings(self) -> &'static [(&'static str, Action)] {
+        match self {
+            KeyScope::View | KeyScope::FileTree => FIXED_BINDINGS,
+            KeyScope::Table => TABLE_FIXED_BINDINGS,
+            KeyScope::Search => &[],
+        }
+    }
+}
+
+/// A single key press as written in a binding: `j`, `G`, `<C-d>`,
+/// `<A-x>` or `<Down>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyPress {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyPress {
+    #[must_use]
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut press = Self { code, modifiers };
+        press.normalize();
+        press
+    }
 
-            if c == KEY_CONFIG.edit {
-                return Action::Edit;
+    /// Shift is part of the character for printable keys (`G`, not
+    /// `<S-g>`), and terminals cannot tell `<C-i>` apart from `<Tab>`.
+    fn normalize(&mut self) {
+        if let KeyCode::Char(c) = self.code {
+            if self.modifiers.contains(KeyModifiers::SHIFT) {
+                self.code = KeyCode::Char(c.to_ascii_uppercase());
+                self.modifiers.remove(KeyModifiers::SHIFT);
+            }
+            if self.modifiers.contains(KeyModifiers::CONTROL) {
+                self.code = KeyCode::Char(c.to_ascii_lowercase());
             }
+        }
+        if self.code == KeyCode::Char('i') && self.modifiers == KeyModifiers::CONTROL {
+            self.code = KeyCode::Tab;
+            self.modifiers = KeyModifiers::NONE;
+        }
+        self.modifiers &= KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT;
+    }
+}
 
-            if c == KEY_CONFIG.hover {
-                return Action::Hover;
+impl From<KeyEvent> for KeyPress {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+impl fmt::Display for KeyPress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.code {
+            KeyCode::Char(' ') => "Space".to_owned(),
+            KeyCode::Char('<') => "lt".to_owned(),
+            KeyCode::Char(c) if self.modifiers.is_empty() => return write!(f, "{c}"),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Up => "Up".to_owned(),
+            KeyCode::Down => "Down".to_owned(),
+            KeyCode::Left => "Left".to_owned(),
+            KeyCode::Right => "Right".to_owned(),
+            KeyCode::PageUp => "PageUp".to_owned(),
+            KeyCode::PageDown => "PageDown".to_owned(),
+            KeyCode::Home => "Home".to_owned(),
+            KeyCode::End => "End".to_owned(),
+            KeyCode::Enter => "Enter".to_owned(),
+            KeyCode::Esc => "Esc".to_owned(),
+            KeyCode::Tab => "Tab".to_owned(),
+            KeyCode::Backspace => "BS".to_owned(),
+            KeyCode::Delete => "Del".to_owned(),
+            other => format!("{other:?}"),
+        };
+        write!(f, "<")?;
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "C-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "A-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "S-")?;
+        }
+        write!(f, "{name}>")
+    }
+}
+
+/// Parse a binding such as `gg`, `<C-d>` or `]b` into its key presses.
+pub fn parse_keys(binding: &str) -> Result<Vec<KeyPress>, String> {
+    let mut keys = Vec::new();
+    let mut rest = binding;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '<'
+            && let Some(end) = rest.find('>')
+            && end > 1
+        {
+            keys.push(parse_special(&rest[1..end])?);
+            rest = &rest[end + 1..];
+        } else {
+            keys.push(KeyPress::new(KeyCode::Char(c), KeyModifiers::NONE));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    if keys.is_empty() {
+        return Err("Empty key binding".to_owned());
+    }
+    Ok(keys)
+}
+
+/// Parse what is between `<` and `>`: modifiers followed by a key name.
+fn parse_special(name: &str) -> Result<KeyPress, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut key = name;
+    while key.len() > 2 {
+        let modifier = match key.get(..2) {
+            Some("C-" | "c-") => KeyModifiers::CONTROL,
+            Some("A-" | "a-" | "M-" | "m-") => KeyModifiers::ALT,
+            Some("S-" | "s-") => KeyModifiers::SHIFT,
+            _ => break,
+        };
+        modifiers |= modifier;
+        key = &key[2..];
+    }
+
+    let code = match key.to_lowercase().as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "enter" | "cr" | "return" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "bs" | "backspace" => KeyCode::Backspace,
+        "del" | "delete" => KeyCode::Delete,
+        "space" => KeyCode::Char(' '),
+        "lt" => KeyCode::Char('<'),
+        _ => {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => return Err(format!("Unknown key <{name}>")),
             }
+        }
+    };
+
+    Ok(KeyPress::new(code, modifiers))
+}
+
+#[must_use]
+pub fn format_keys(keys: &[KeyPress]) -> String {
+    keys.iter().map(ToString::to_string).collect()
+}
 
-            if c == KEY_CONFIG.top {
-                return Action::ToTop;
+#[derive(Debug)]
+struct Binding {
+    keys: Vec<KeyPress>,
+    action: Action,
+    /// Config key the binding came from, for error messages.
+    name: &'static str,
+}
+
+#[derive(Debug, Default)]
+pub struct KeyConfig {
+    bindings: Vec<Binding>,
+}
+
+impl KeyConfig {
+    /// The action bound to exactly `keys`.
+    #[must_use]
+    pub fn action(&self, keys: &[KeyPress]) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|b| b.keys == keys)
+            .map(|b| b.action)
+    }
+
+    /// Whether `keys` is the start of a longer binding, so more keys are
+    /// needed before anything can happen.
+    #[must_use]
+    pub fn is_prefix(&self, keys: &[KeyPress]) -> bool {
+        self.bindings
+            .iter()
+            .any(|b| b.keys.len() > keys.len() && b.keys.starts_with(keys))
+    }
+
+    /// The first binding of `action`, as shown in the help menu.
+    #[must_use]
+    pub fn keys_for(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|b| b.action == action)
+            .map(|b| format_keys(&b.keys))
+            .unwrap_or_default()
+    }
+
+    fn bind(&mut self, binding: &str, action: Action, name: &'static str) -> Result<(), String> {
+        let keys = parse_keys(binding)?;
+        self.bindings.push(Binding { keys, action, name });
+        Ok(())
+    }
+
+    /// Bindings that can never both work: the same keys bound to two
+    /// actions, or keys that are the start of another binding, which makes
+    /// the shorter one unreachable.
+    fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        for (i, a) in self.bindings.iter().enumerate() {
+            for b in &self.bindings[i + 1..] {
+                if a.action == b.action {
+                    continue;
+                }
+                let (a_keys, b_keys) = (format_keys(&a.keys), format_keys(&b.keys));
+                if a.keys == b.keys {
+                    conflicts.push(format!(
+                        "`{a_keys}` is bound to both {} and {}",
+                        a.name, b.name
+                    ));
+                } else if b.keys.starts_with(&a.keys) {
+                    conflicts.push(format!(
+                        "`{a_keys}` ({}) can never be typed because `{b_keys}` ({}) starts with it",
+                        a.name, b.name
+                    ));
+                } else if a.keys.starts_with(&b.keys) {
+                    conflicts.push(format!(
+                        "`{b_keys}` ({}) can never be typed because `{a_keys}` ({}) starts with it",
+                        b.name, a.name
+                    ));
+                }
             }
+        }
+        conflicts
+    }
+}
+
+/// What a key press amounted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyResult {
+    /// Part of a count or of a longer binding, wait for the next key.
+    Pending,
+    /// A complete binding, with the count typed before it, if any.
+    /// Unbound keys give `Action::None`.
+    Action(Action, Option<usize>),
+    /// A mark command and the letter of its mark.
+    Mark(Action, char),
+}
+
+/// The largest count, as in vim. More digits are ignored.
+pub const MAX_COUNT: usize = 9999;
 
-            if c == KEY_CONFIG.bottom {
-                return Action::ToBottom;
+/// Keys typed so far that may still become a binding, and the count typed
+/// before them.
+#[derive(Debug, Clone, Default)]
+pub struct KeyInput {
+    pending: Vec<KeyPress>,
+    count: Option<usize>,
+    /// A mark command waiting for the letter of its mark.
+    mark: Option<Action>,
+}
+
+impl KeyInput {
+    pub fn feed(&mut self, scope: KeyScope, event: KeyEvent) -> KeyResult {
+        self.feed_with(keymap(scope), event.into())
+    }
+
+    fn feed_with(&mut self, config: &KeyConfig, press: KeyPress) -> KeyResult {
+        if let Some(action) = self.mark.take() {
+            return match press.code {
+                KeyCode::Char(c) if press.modifiers.is_empty() && Marks::is_mark_letter(c) => {
+                    KeyResult::Mark(action, c)
+                }
+                _ => KeyResult::Action(Action::None, None),
+            };
+        }
+
+        if self.pending.is_empty()
+            && press.modifiers.is_empty()
+            && let KeyCode::Char(c) = press.code
+            && let Some(digit) = c.to_digit(10)
+            && (digit != 0 || self.count.is_some())
+        {
+            let count = self.count.unwrap_or(0) * 10 + digit as usize;
+            self.count = Some(count.min(MAX_COUNT));
+            return KeyResult::Pending;
+        }
+
+        self.pending.push(press);
+        if config.is_prefix(&self.pending) {
+            return KeyResult::Pending;
+        }
+
+        if let Some(action) = config.action(&self.pending) {
+            self.pending.clear();
+            if action.takes_mark() {
+                self.count = None;
+                self.mark = Some(action);
+                return KeyResult::Pending;
             }
+            return KeyResult::Action(action, self.count.take());
+        }
+
+        // Not a binding after all, start over from the key just typed
+        let retry = self.pending.len() > 1;
+        self.clear();
+        if retry {
+            return self.feed_with(config, press);
+        }
+        KeyResult::Action(Action::None, None)
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.count = None;
+        self.mark = None;
+    }
+}
+
+/// Action bound to `key` on its own. Used where sequences and counts make
+/// no sense, like inside popups.
+#[must_use]
+pub fn key_to_action(scope: KeyScope, key: KeyEvent) -> Action {
+    keymap(scope)
+        .action(&[KeyPress::from(key)])
+        .unwrap_or(Action::None)
+}
 
-            if c == KEY_CONFIG.back {
-                return Action::Back;
+#[derive(Debug)]
+pub struct Keymaps {
+    view: KeyConfig,
+    file_tree: KeyConfig,
+    table: KeyConfig,
+    search: KeyConfig,
+    errors: Vec<String>,
+}
+
+pub static KEYMAPS: LazyLock<Keymaps> = LazyLock::new(|| keymaps(&load_settings()));
+
+#[must_use]
+pub fn keymap(scope: KeyScope) -> &'static KeyConfig {
+    match scope {
+        KeyScope::View => &KEYMAPS.view,
+        KeyScope::FileTree => &KEYMAPS.file_tree,
+        KeyScope::Table => &KEYMAPS.table,
+        KeyScope::Search => &KEYMAPS.search,
+    }
+}
+
+/// Everything wrong with the configured keys: unknown actions, bindings
+/// that do not parse and conflicting bindings. Checked at startup.
+#[must_use]
+pub fn keymap_errors() -> &'static [String] {
+    &KEYMAPS.errors
+}
+
+fn keymaps(settings: &Config) -> Keymaps {
+    let mut errors = Vec::new();
+
+    if let Ok(tables) = settings.get_table("keys") {
+        for table in tables.keys() {
+            if !KeyScope::ALL.iter().any(|scope| scope.table() == table) {
+                errors.push(format!(
+                    "Unknown keymap [keys.{table}], expected view, file_tree, table or search"
+                ));
             }
+        }
+    }
 
-            if c == KEY_CONFIG.file_tree {
-                return Action::ToFileTree;
+    let mut build = |scope: KeyScope| {
+        let (config, scope_errors) = key_config(settings, scope);
+        for error in scope_errors {
+            if !errors.contains(&error) {
+                errors.push(error);
             }
+        }
+        config
+    };
 
-            if c == KEY_CONFIG.sort {
-                return Action::Sort;
+    Keymaps {
+        view: build(KeyScope::View),
+        file_tree: build(KeyScope::FileTree),
+        table: build(KeyScope::Table),
+        search: build(KeyScope::Search),
+        errors,
+    }
+}
+
+/// Build the keymap of `scope`. Each action takes its bindings from
+/// `[keys.<scope>]`, then from the top level of the config, then from the
+/// defaults.
+fn key_config(settings: &Config, scope: KeyScope) -> (KeyConfig, Vec<String>) {
+    let mut config = KeyConfig::default();
+    let mut errors = Vec::new();
+    let table = scope.table();
+
+    if let Ok(keys) = settings.get_table(&format!("keys.{table}")) {
+        for name in keys.keys() {
+            if !scope.bindings().iter().any(|(n, _, _)| n == name) {
+                errors.push(format!("[keys.{table}] Unknown action `{name}`"));
             }
+        }
+    }
+
+    for (name, action, defaults) in scope.bindings() {
+        let scoped = format!("keys.{table}.{name}");
+        let (source, configured) = [scoped.as_str(), name]
+            .into_iter()
+            .find_map(|key| {
+                settings
+                    .get::<Vec<String>>(key)
+                    .or_else(|_| settings.get::<String>(key).map(|b| vec![b]))
+                    .ok()
+                    .map(|bindings| (key, bindings))
+            })
+            .unwrap_or(("", defaults.iter().map(ToString::to_string).collect()));
 
-            if c == '?' {
-                return Action::Help;
+        for binding in configured {
+            if let Err(e) = config.bind(&binding, *action, name) {
+                errors.push(format!("`{source}` = \"{binding}\": {e}"));
             }
+        }
+    }
+
+    for (binding, action) in scope.fixed_bindings() {
+        let _ = config.bind(binding, *action, "built-in");
+    }
+
+    errors.extend(
+        config
+            .conflicts()
+            .into_iter()
+            .map(|conflict| format!("[keys.{table}] {conflict}")),
+    );
+
+    (config, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(bindings: &[(&str, Action)]) -> KeyConfig {
+        let mut config = KeyConfig::default();
+        for (binding, action) in bindings {
+            config.bind(binding, *action, "test").unwrap();
+        }
+        config
+    }
+
+    fn settings(toml: &str) -> Config {
+        Config::builder()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .unwrap()
+    }
+
+    fn feed(input: &mut KeyInput, config: &KeyConfig, keys: &str) -> KeyResult {
+        let mut result = KeyResult::Pending;
+        for key in parse_keys(keys).unwrap() {
+            result = input.feed_with(config, key);
+        }
+        result
+    }
+
+    #[test]
+    fn parses_bindings() {
+        let keys = parse_keys("<C-d>gG<Down><lt>").unwrap();
+        assert_eq!(
+            keys,
+            vec![
+                KeyPress::new(KeyCode::Char('d'), KeyModifiers::CONTROL),
+                KeyPress::new(KeyCode::Char('g'), KeyModifiers::NONE),
+                KeyPress::new(KeyCode::Char('G'), KeyModifiers::NONE),
+                KeyPress::new(KeyCode::Down, KeyModifiers::NONE),
+                KeyPress::new(KeyCode::Char('<'), KeyModifiers::NONE),
+            ]
+        );
+        assert_eq!(format_keys(&keys), "<C-d>gG<Down><lt>");
+        assert_eq!(
+            parse_keys("<C-i>").unwrap(),
+            vec![KeyPress::new(KeyCode::Tab, KeyModifiers::NONE)]
+        );
+        assert!(parse_keys("<Nope>").is_err());
+        assert!(parse_keys("").is_err());
+    }
 
-            Action::None
-        }
-        KeyCode::Up => Action::Up,
-        KeyCode::Down => Action::Down,
-        KeyCode::PageUp => Action::PageUp,
-        KeyCode::PageDown => Action::PageDown,
-        KeyCode::Right => Action::PageDown,
-        KeyCode::Left => Action::PageUp,
-        KeyCode::Enter => Action::Enter,
-        KeyCode::Esc => Action::Escape,
-        _ => Action::None,
-    }
-}
-
-pub static KEY_CONFIG: LazyLock<KeyConfig> = LazyLock::new(|| {
-    let config_dir = dirs::home_dir().unwrap();
-    let config_file = config_dir.join(".config").join("mdt").join("config.toml");
-    let settings = Config::builder()
-        .add_source(File::with_name(config_file.to_str().unwrap()).required(false))
-        .add_source(Environment::with_prefix("MDT").separator("_"))
-        .build()
-        .unwrap();
-
-    KeyConfig {
-        up: settings.get::<char>("up").unwrap_or('k'),
-        down: settings.get::<char>("down").unwrap_or('j'),
-        page_up: settings.get::<char>("page_up").unwrap_or('u'),
-        page_down: settings.get::<char>("page_down").unwrap_or('d'),
-        half_page_up: settings.get::<char>("half_page_up").unwrap_or('h'),
-        half_page_down: settings.get::<char>("half_page_down").unwrap_or('l'),
-        search: settings.get::<char>("search").unwrap_or('f'),
-        select_link: settings.get::<char>("select_link").unwrap_or('s'),
-        select_link_alt: settings.get::<char>("select_link_alt").unwrap_or('S'),
-        select_details: settings.get::<char>("select_details").unwrap_or('D'),
-        search_next: settings.get::<char>("search_next").unwrap_or('n'),
-        search_previous: settings.get::<char>("search_previous").unwrap_or('N'),
-        edit: settings.get::<char>("edit").unwrap_or('e'),
-        hover: settings.get::<char>("hover").unwrap_or('K'),
-        top: settings.get::<char>("top").unwrap_or('g'),
-        bottom: settings.get::<char>("bottom").unwrap_or('G'),
-        back: settings.get::<char>("back").unwrap_or('b'),
-        file_tree: settings.get::<char>("file_tree").unwrap_or('t'),
-        sort: settings.get::<char>("sort").unwrap_or('o'),
-    }
-});
+    #[test]
+    fn terminal_shift_is_folded_into_the_character() {
+        let press = KeyPress::from(KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT));
+        assert_eq!(press, parse_keys("G").unwrap()[0]);
+    }
+
+    #[test]
+    fn sequences_wait_for_the_full_binding() {
+        let config = config(&[("gg", Action::ToTop), ("j", Action::Down)]);
+        let mut input = KeyInput::default();
+
+        assert_eq!(feed(&mut input, &config, "g"), KeyResult::Pending);
+        assert_eq!(
+            feed(&mut input, &config, "g"),
+            KeyResult::Action(Action::ToTop, None)
+        );
+        // A broken sequence starts over from the last key
+        assert_eq!(
+            feed(&mut input, &config, "gj"),
+            KeyResult::Action(Action::Down, None)
+        );
+        assert_eq!(
+            feed(&mut input, &config, "x"),
+            KeyResult::Action(Action::None, None)
+        );
+    }
+
+    #[test]
+    fn mark_commands_take_the_next_letter() {
+        let config = config(&[("m", Action::SetMark), ("'", Action::JumpToMark)]);
+        let mut input = KeyInput::default();
+
+        assert_eq!(feed(&mut input, &config, "m"), KeyResult::Pending);
+        assert_eq!(
+            feed(&mut input, &config, "a"),
+            KeyResult::Mark(Action::SetMark, 'a')
+        );
+        assert_eq!(
+            feed(&mut input, &config, "2'B"),
+            KeyResult::Mark(Action::JumpToMark, 'B')
+        );
+        // Anything but a letter cancels the command
+        assert_eq!(
+            feed(&mut input, &config, "m<Esc>"),
+            KeyResult::Action(Action::None, None)
+        );
+        assert_eq!(
+            feed(&mut input, &config, "m1"),
+            KeyResult::Action(Action::None, None)
+        );
+    }
+
+    #[test]
+    fn reads_single_and_listed_bindings_from_config() {
+        let settings = settings("top = \"gg\"\ndown = [\"j\", \"<C-n>\"]\n");
+        let (config, errors) = key_config(&settings, KeyScope::View);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        assert_eq!(
+            config.action(&parse_keys("gg").unwrap()),
+            Some(Action::ToTop)
+        );
+        assert_eq!(config.action(&parse_keys("g").unwrap()), None);
+        assert_eq!(
+            config.action(&parse_keys("<C-n>").unwrap()),
+            Some(Action::Down)
+        );
+        assert_eq!(config.action(&parse_keys("j").unwrap()), Some(Action::Down));
+        // Unset keys keep their defaults
+        assert_eq!(config.action(&parse_keys("k").unwrap()), Some(Action::Up));
+        assert_eq!(config.keys_for(Action::ToTop), "gg");
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        let keymaps = keymaps(&Config::default());
+        assert!(keymaps.errors.is_empty(), "{:?}", keymaps.errors);
+    }
+
+    #[test]
+    fn scopes_override_the_top_level() {
+        let settings = settings("down = \"J\"\n[keys.file_tree]\ndown = \"<C-n>\"\n");
+        let keymaps = keymaps(&settings);
+        assert!(keymaps.errors.is_empty(), "{:?}", keymaps.errors);
+        assert_eq!(
+            keymaps.view.action(&parse_keys("J").unwrap()),
+            Some(Action::Down)
+        );
+        assert_eq!(keymaps.file_tree.action(&parse_keys("J").unwrap()), None);
+        assert_eq!(
+            keymaps.file_tree.action(&parse_keys("<C-n>").unwrap()),
+            Some(Action::Down)
+        );
+    }
+
+    #[test]
+    fn reports_conflicts_and_unknown_names() {
+        let settings = settings(
+            "[keys.view]\nedit = \"K\"\ntop = \"z\"\nbottom = \"zz\"\nnope = \"x\"\nup = \"<Nope>\"\n[keys.modal]\nup = \"k\"\n",
+        );
+        let errors = keymaps(&settings).errors;
+        let has = |needle: &str| errors.iter().any(|e| e.contains(needle));
+        assert!(has("[keys.modal]"), "{errors:?}");
+        assert!(has("Unknown action `nope`"), "{errors:?}");
+        assert!(has("<Nope>"), "{errors:?}");
+        assert!(has("`K` is bound to both edit and hover"), "{errors:?}");
+        assert!(has("`z` (top) can never be typed"), "{errors:?}");
+    }
+
+    #[test]
+    fn counts_prefix_actions() {
+        let config = config(&[("j", Action::Down), ("<C-d>", Action::HalfPageDown)]);
+        let mut input = KeyInput::default();
+
+        assert_eq!(
+            feed(&mut input, &config, "12j"),
+            KeyResult::Action(Action::Down, Some(12))
+        );
+        assert_eq!(
+            feed(&mut input, &config, "j"),
+            KeyResult::Action(Action::Down, None)
+        );
+        assert_eq!(
+            feed(&mut input, &config, "3<C-d>"),
+            KeyResult::Action(Action::HalfPageDown, Some(3))
+        );
+        // A leading zero is not a count
+        assert_eq!(
+            feed(&mut input, &config, "0"),
+            KeyResult::Action(Action::None, None)
+        );
+        assert_eq!(
+            feed(&mut input, &config, "99999999999j"),
+            KeyResult::Action(Action::Down, Some(MAX_COUNT))
+        );
+    }
+}
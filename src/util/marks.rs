@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+
+use crate::util::{Jump, canonical_path};
+
+/// A named place in a document. Lowercase marks belong to the file they
+/// were set in, uppercase marks are global and jump across files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mark {
+    letter: char,
+    path: String,
+    /// `path` resolved, to find the marks of a file by any of its names.
+    file: PathBuf,
+    scroll: u16,
+    heading: Option<String>,
+}
+
+impl Mark {
+    #[must_use]
+    pub fn new(letter: char, path: String, scroll: u16, heading: Option<String>) -> Self {
+        Self {
+            letter,
+            file: canonical_path(&path),
+            path,
+            scroll,
+            heading,
+        }
+    }
+
+    #[must_use]
+    pub fn letter(&self) -> char {
+        self.letter
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    #[must_use]
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    #[must_use]
+    pub fn scroll(&self) -> u16 {
+        self.scroll
+    }
+
+    #[must_use]
+    pub fn heading(&self) -> Option<&str> {
+        self.heading.as_deref()
+    }
+
+    #[must_use]
+    pub fn is_global(&self) -> bool {
+        self.letter.is_ascii_uppercase()
+    }
+
+    #[must_use]
+    pub fn jump(&self) -> Jump {
+        Jump::File {
+            path: self.path.clone(),
+            scroll: self.scroll,
+            heading: self.heading.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Marks {
+    marks: Vec<Mark>,
+}
+
+impl Marks {
+    /// Whether `c` can name a mark.
+    #[must_use]
+    pub fn is_mark_letter(c: char) -> bool {
+        c.is_ascii_alphabetic()
+    }
+
+    /// Set `mark`, replacing the mark it shadows: the global mark with the
+    /// same letter, or the local one in the same file.
+    pub fn set(&mut self, mark: Mark) {
+        self.marks
+            .retain(|m| m.letter != mark.letter || (!m.is_global() && m.file != mark.file));
+        self.marks.push(mark);
+    }
+
+    /// The mark `letter` refers to when in `file_name`.
+    #[must_use]
+    pub fn get(&self, letter: char, file_name: Option<&str>) -> Option<&Mark> {
+        let file = file_name.map(canonical_path);
+        self.marks
+            .iter()
+            .find(|m| m.letter == letter && (m.is_global() || file.as_ref() == Some(&m.file)))
+    }
+
+    /// Marks reachable from `file_name`: its local marks followed by every
+    /// global mark, each sorted by letter.
+    #[must_use]
+    pub fn visible(&self, file_name: Option<&str>) -> Vec<Mark> {
+        let file = file_name.map(canonical_path);
+        let mut marks: Vec<Mark> = self
+            .marks
+            .iter()
+            .filter(|m| m.is_global() || file.as_ref() == Some(&m.file))
+            .cloned()
+            .collect();
+        marks.sort_by_key(|m| (m.is_global(), m.letter));
+        marks
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Mark> {
+        self.marks.iter()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mark(letter: char, path: &str, scroll: u16) -> Mark {
+        Mark::new(letter, path.to_owned(), scroll, None)
+    }
+
+    #[test]
+    fn local_marks_are_per_file() {
+        let mut marks = Marks::default();
+        marks.set(mark('a', "one.md", 1));
+        marks.set(mark('a', "two.md", 2));
+        marks.set(mark('a', "one.md", 3));
+
+        assert_eq!(marks.get('a', Some("one.md")).map(Mark::scroll), Some(3));
+        assert_eq!(marks.get('a', Some("two.md")).map(Mark::scroll), Some(2));
+        assert_eq!(marks.get('a', None), None);
+        assert_eq!(marks.iter().count(), 2);
+    }
+
+    #[test]
+    fn global_marks_are_shared() {
+        let mut marks = Marks::default();
+        marks.set(mark('A', "one.md", 1));
+        marks.set(mark('A', "two.md", 5));
+        marks.set(mark('b', "two.md", 7));
+
+        assert_eq!(
+            marks.get('A', Some("one.md")).map(Mark::path),
+            Some("two.md")
+        );
+        assert_eq!(marks.get('A', None).map(Mark::scroll), Some(5));
+
+        let visible: Vec<char> = marks
+            .visible(Some("two.md"))
+            .iter()
+            .map(Mark::letter)
+            .collect();
+        assert_eq!(visible, vec!['b', 'A']);
+        assert_eq!(marks.visible(Some("one.md")).len(), 1);
+    }
+}
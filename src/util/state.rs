@@ -0,0 +1,302 @@
+use std::{fs, io, path::PathBuf};
+
+use crate::util::{
+    Jump, JumpHistory, canonical_path,
+    marks::{Mark, Marks},
+};
+
+const HISTORY_FILE: &str = "history";
+const POSITIONS_FILE: &str = "positions";
+const MARKS_FILE: &str = "marks";
+/// Only the most recently read files are remembered.
+const MAX_POSITIONS: usize = 500;
+
+/// Directory mdt keeps state between sessions in, `$XDG_DATA_HOME/mdt` on
+/// Linux.
+#[must_use]
+pub fn state_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("mdt"))
+}
+
+/// Write the jump list and the location currently shown, so the next
+/// session can pick up from there.
+pub fn save_history(history: &JumpHistory, current: Option<&Jump>) -> io::Result<()> {
+    let Some(dir) = state_dir() else {
+        return Ok(());
+    };
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(HISTORY_FILE), format_history(history, current))
+}
+
+/// Read back what `save_history` wrote. Missing or unreadable state is
+/// treated as an empty history.
+#[must_use]
+pub fn load_history() -> (JumpHistory, Option<Jump>) {
+    state_dir()
+        .and_then(|dir| fs::read_to_string(dir.join(HISTORY_FILE)).ok())
+        .map(|text| parse_history(&text))
+        .unwrap_or_default()
+}
+
+/// Where a document was left: its scroll offset and which `<details>`
+/// blocks were folded, in document order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilePosition {
+    pub scroll: u16,
+    pub folds: Vec<bool>,
+}
+
+/// Last position in every recently read file, keyed by canonical path and
+/// ordered from least to most recently left.
+#[derive(Debug, Clone, Default)]
+pub struct Positions {
+    entries: Vec<(PathBuf, FilePosition)>,
+}
+
+impl Positions {
+    /// Read the positions file. Missing or unreadable state gives an empty
+    /// set.
+    #[must_use]
+    pub fn load() -> Self {
+        state_dir()
+            .and_then(|dir| fs::read_to_string(dir.join(POSITIONS_FILE)).ok())
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(dir) = state_dir() else {
+            return Ok(());
+        };
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(POSITIONS_FILE), self.format())
+    }
+
+    #[must_use]
+    pub fn get(&self, file_name: &str) -> Option<&FilePosition> {
+        let path = canonical_path(file_name);
+        self.entries
+            .iter()
+            .find(|(p, _)| *p == path)
+            .map(|(_, position)| position)
+    }
+
+    pub fn set(&mut self, file_name: &str, position: FilePosition) {
+        let path = canonical_path(file_name);
+        self.entries.retain(|(p, _)| *p != path);
+        self.entries.push((path, position));
+        if self.entries.len() > MAX_POSITIONS {
+            self.entries.drain(..self.entries.len() - MAX_POSITIONS);
+        }
+    }
+
+    /// One file per line: path, scroll offset and the fold state as a
+    /// string of `0` and `1`, separated by tabs.
+    fn format(&self) -> String {
+        let mut out = String::new();
+        for (path, position) in &self.entries {
+            let folds: String = position
+                .folds
+                .iter()
+                .map(|folded| if *folded { '1' } else { '0' })
+                .collect();
+            out.push_str(&format!(
+                "{}\t{}\t{folds}\n",
+                path.to_string_lossy(),
+                position.scroll
+            ));
+        }
+        out
+    }
+
+    fn parse(text: &str) -> Self {
+        let entries = text
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                let path = PathBuf::from(fields.next().filter(|p| !p.is_empty())?);
+                let scroll = fields.next()?.parse().ok()?;
+                let folds = fields.next().unwrap_or_default().chars().map(|c| c == '1');
+                Some((
+                    path,
+                    FilePosition {
+                        scroll,
+                        folds: folds.collect(),
+                    },
+                ))
+            })
+            .collect();
+        Self { entries }
+    }
+}
+
+/// Write every mark, local and global, one per line: letter, path, scroll
+/// offset and heading anchor.
+pub fn save_marks(marks: &Marks) -> io::Result<()> {
+    let Some(dir) = state_dir() else {
+        return Ok(());
+    };
+    fs::create_dir_all(&dir)?;
+    let mut out = String::new();
+    for mark in marks.iter() {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            mark.letter(),
+            mark.file().to_string_lossy(),
+            mark.scroll(),
+            mark.heading().unwrap_or_default()
+        ));
+    }
+    fs::write(dir.join(MARKS_FILE), out)
+}
+
+#[must_use]
+pub fn load_marks() -> Marks {
+    let mut marks = Marks::default();
+    let Some(text) = state_dir().and_then(|dir| fs::read_to_string(dir.join(MARKS_FILE)).ok())
+    else {
+        return marks;
+    };
+    for line in text.lines() {
+        let mut fields = line.split('\t');
+        let (Some(letter), Some(path), Some(scroll)) = (
+            fields.next().and_then(|l| l.chars().next()),
+            fields.next(),
+            fields.next().and_then(|s| s.parse().ok()),
+        ) else {
+            continue;
+        };
+        if !Marks::is_mark_letter(letter) {
+            continue;
+        }
+        let heading = fields.next().filter(|h| !h.is_empty()).map(str::to_owned);
+        marks.set(Mark::new(letter, path.to_owned(), scroll, heading));
+    }
+    marks
+}
+
+/// One jump per line, tab separated: which list it belongs to, then either
+/// `tree` or `file`, path, scroll offset and heading anchor.
+fn format_history(history: &JumpHistory, current: Option<&Jump>) -> String {
+    let mut out = String::new();
+    let lists = [
+        ("back", history.back.iter().collect::<Vec<_>>()),
+        ("current", current.into_iter().collect()),
+        ("forward", history.forward.iter().collect()),
+    ];
+    for (list, jumps) in lists {
+        for jump in jumps {
+            out.push_str(list);
+            out.push('\t');
+            match jump {
+                Jump::File {
+                    path,
+                    scroll,
+                    heading,
+                } => {
+                    let path = canonical_path(path);
+                    out.push_str(&format!(
+                        "file\t{}\t{scroll}\t{}",
+                        path.to_string_lossy(),
+                        heading.as_deref().unwrap_or_default()
+                    ));
+                }
+                Jump::FileTree => out.push_str("tree"),
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn parse_history(text: &str) -> (JumpHistory, Option<Jump>) {
+    let mut history = JumpHistory::new();
+    let mut current = None;
+
+    for line in text.lines() {
+        let mut fields = line.split('\t');
+        let list = fields.next();
+        let jump = match fields.next() {
+            Some("tree") => Jump::FileTree,
+            Some("file") => {
+                let (Some(path), Some(scroll)) = (fields.next(), fields.next()) else {
+                    continue;
+                };
+                Jump::File {
+                    path: path.to_owned(),
+                    scroll: scroll.parse().unwrap_or(0),
+                    heading: fields.next().filter(|h| !h.is_empty()).map(str::to_owned),
+                }
+            }
+            _ => continue,
+        };
+        match list {
+            Some("back") => history.back.push(jump),
+            Some("current") => current = Some(jump),
+            Some("forward") => history.forward.push(jump),
+            _ => {}
+        }
+    }
+
+    (history, current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_round_trips() {
+        let mut history = JumpHistory::new();
+        history.push(Jump::FileTree);
+        history.push(Jump::File {
+            path: "/does/not/exist.md".to_owned(),
+            scroll: 12,
+            heading: Some("usage".to_owned()),
+        });
+        let current = Jump::file("/does/not/exist-either.md");
+
+        let text = format_history(&history, Some(&current));
+        let (parsed, parsed_current) = parse_history(&text);
+
+        assert_eq!(parsed.back, history.back);
+        assert!(parsed.forward.is_empty());
+        assert_eq!(parsed_current, Some(current));
+    }
+
+    #[test]
+    fn positions_keep_the_latest_entry_per_file() {
+        let mut positions = Positions::default();
+        positions.set("/no/such/a.md", FilePosition::default());
+        positions.set(
+            "/no/such/b.md",
+            FilePosition {
+                scroll: 3,
+                folds: vec![true, false],
+            },
+        );
+        positions.set(
+            "/no/such/a.md",
+            FilePosition {
+                scroll: 40,
+                folds: Vec::new(),
+            },
+        );
+
+        let parsed = Positions::parse(&positions.format());
+        assert_eq!(parsed.entries, positions.entries);
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.get("/no/such/a.md").map(|p| p.scroll), Some(40));
+        assert_eq!(
+            parsed.get("/no/such/b.md").map(|p| p.folds.clone()),
+            Some(vec![true, false])
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let (history, current) = parse_history("back\tfile\nnonsense\ncurrent\ttree\n");
+        assert!(history.back.is_empty());
+        assert_eq!(current, Some(Jump::FileTree));
+    }
+}